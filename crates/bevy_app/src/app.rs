@@ -1,5 +1,7 @@
-use crate::{app_builder::AppBuilder, DefaultTaskPoolOptions};
-use bevy_ecs::{ParallelExecutor, Resources, Schedule, World};
+use crate::{app_builder::AppBuilder, hot_reload::ReloadablePlugins, DefaultTaskPoolOptions};
+use bevy_ecs::{Executor, ParallelExecutor, Resource, Resources, Schedule, World};
+use bevy_tasks::{AsyncComputeTaskPool, ComputeTaskPool, IOTaskPool};
+use std::collections::HashSet;
 
 #[allow(clippy::needless_doctest_main)]
 /// Containers of app logic and data
@@ -29,10 +31,31 @@ pub struct App {
     pub runner: Box<dyn Fn(App)>,
     pub schedule: Schedule,
     pub executor: ParallelExecutor,
+    /// Runs the schedule instead of `executor` if set, see [AppBuilder::set_executor]
+    pub custom_executor: Option<Box<dyn Executor>>,
     pub startup_schedule: Schedule,
     pub startup_executor: ParallelExecutor,
+    /// Runs the startup schedule instead of `startup_executor` if set, see [AppBuilder::set_startup_executor]
+    pub custom_startup_executor: Option<Box<dyn Executor>>,
+    pub sub_apps: Vec<SubApp>,
+    pub reloadable_plugins: ReloadablePlugins,
+    // kept in the app rather than its builder, so that plugins rebuilt by a reload still see the plugins that were added
+    pub(crate) plugin_names: HashSet<String>,
 }
 
+/// An [App] owned by another app. It has its own World, Resources and Schedule, and is updated after its parent
+/// in every frame.
+pub struct SubApp {
+    pub name: &'static str,
+    pub app: App,
+    /// Runs before every update of the sub-app with mutable access to the parent app and the sub-app, to move data
+    /// between them
+    pub extract: ExtractFn,
+}
+
+/// The extract function of a [SubApp], called with the parent app and the sub-app
+pub type ExtractFn = Box<dyn Fn(&mut App, &mut App)>;
+
 impl Default for App {
     fn default() -> Self {
         Self {
@@ -40,9 +63,14 @@ impl Default for App {
             resources: Default::default(),
             schedule: Default::default(),
             executor: Default::default(),
+            custom_executor: None,
             startup_schedule: Default::default(),
             startup_executor: ParallelExecutor::without_tracker_clears(),
+            custom_startup_executor: None,
             runner: Box::new(run_once),
+            sub_apps: Vec::new(),
+            reloadable_plugins: Default::default(),
+            plugin_names: Default::default(),
         }
     }
 }
@@ -56,10 +84,87 @@ impl App {
         AppBuilder::default()
     }
 
+    /// Reloads changed dynamic plugins, then runs the app's schedule once, followed by the extract function and update of each sub-app
     pub fn update(&mut self) {
+        #[cfg(feature = "dynamic_plugins")]
+        self.reload_changed_plugins();
+
         self.schedule.initialize(&mut self.resources);
-        self.executor
-            .run(&mut self.schedule, &mut self.world, &mut self.resources);
+        match &mut self.custom_executor {
+            Some(executor) => {
+                executor.run(&mut self.schedule, &mut self.world, &mut self.resources)
+            }
+            None => self
+                .executor
+                .run(&mut self.schedule, &mut self.world, &mut self.resources),
+        }
+
+        // sub-apps are taken out of the app so that their extract functions can borrow it
+        let mut sub_apps = std::mem::take(&mut self.sub_apps);
+        for sub_app in sub_apps.iter_mut() {
+            (sub_app.extract)(self, &mut sub_app.app);
+            sub_app.app.update();
+        }
+        self.sub_apps = sub_apps;
+    }
+
+    /// Adds a sub-app that is updated after this app in every frame. `extract` runs before each update of the
+    /// sub-app.
+    ///
+    /// # Panics
+    /// Panics if this app already has a sub-app called `name`.
+    pub fn add_sub_app(
+        &mut self,
+        name: &'static str,
+        app: App,
+        extract: impl Fn(&mut App, &mut App) + 'static,
+    ) -> &mut Self {
+        if self.sub_apps.iter().any(|sub_app| sub_app.name == name) {
+            panic!("Sub-app already exists: {}", name);
+        }
+        self.sub_apps.push(SubApp {
+            name,
+            app,
+            extract: Box::new(extract),
+        });
+        self
+    }
+
+    pub fn sub_app(&self, name: &str) -> Option<&App> {
+        self.sub_apps
+            .iter()
+            .find(|sub_app| sub_app.name == name)
+            .map(|sub_app| &sub_app.app)
+    }
+
+    pub fn sub_app_mut(&mut self, name: &str) -> Option<&mut App> {
+        self.sub_apps
+            .iter_mut()
+            .find(|sub_app| sub_app.name == name)
+            .map(|sub_app| &mut sub_app.app)
+    }
+
+    /// Runs the startup schedules of the app and its sub-apps. Sub-apps share their parent's task pools unless they
+    /// have their own.
+    pub fn startup(&mut self) {
+        self.startup_schedule.initialize(&mut self.resources);
+        let executor = match &mut self.custom_startup_executor {
+            Some(executor) => &mut **executor,
+            None => &mut self.startup_executor as &mut dyn Executor,
+        };
+        executor.run(
+            &mut self.startup_schedule,
+            &mut self.world,
+            &mut self.resources,
+        );
+
+        for sub_app in self.sub_apps.iter_mut() {
+            let resources = &mut sub_app.app.resources;
+            share_resource::<ComputeTaskPool>(&self.resources, resources);
+            share_resource::<AsyncComputeTaskPool>(&self.resources, resources);
+            share_resource::<IOTaskPool>(&self.resources, resources);
+            sub_app.app.startup();
+        }
     }
 
     pub fn run(mut self) {
@@ -69,17 +174,118 @@ impl App {
             .unwrap_or_else(DefaultTaskPoolOptions::default)
             .create_default_pools(&mut self.resources);
 
-        self.startup_schedule.initialize(&mut self.resources);
-        self.startup_executor.run(
-            &mut self.startup_schedule,
-            &mut self.world,
-            &mut self.resources,
-        );
+        self.startup();
 
         let runner = std::mem::replace(&mut self.runner, Box::new(run_once));
         (runner)(self);
     }
 }
 
+fn share_resource<T: Resource + Clone>(from: &Resources, to: &mut Resources) {
+    if !to.contains::<T>() {
+        if let Some(resource) = from.get_cloned::<T>() {
+            to.insert(resource);
+        }
+    }
+}
+
 /// An event that indicates the app should exit. This will fully exit the app process.
+#[derive(Debug, Clone)]
 pub struct AppExit;
+
+#[cfg(test)]
+mod tests {
+    use super::App;
+    use crate::stage;
+    use bevy_ecs::{IntoQuerySystem, Query, ResMut, SerialExecutor};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Position(u32);
+
+    fn simulate(mut query: Query<&mut Position>) {
+        for mut position in &mut query.iter() {
+            position.0 += 1;
+        }
+    }
+
+    fn render(mut frame: ResMut<Vec<u32>>, mut query: Query<&Position>) {
+        frame.clear();
+        frame.extend(query.iter().iter().map(|position| position.0));
+    }
+
+    fn build_simulation() -> App {
+        let mut app = App::build();
+        app.set_executor(SerialExecutor::default())
+            .set_startup_executor(SerialExecutor::without_tracker_clears())
+            .add_system_to_stage(stage::UPDATE, simulate.system());
+        app.app.world.spawn((Position(0),));
+        std::mem::take(&mut app.app)
+    }
+
+    #[test]
+    fn sub_apps() {
+        let mut app = build_simulation();
+
+        let mut render_app = App::build();
+        render_app
+            .set_executor(SerialExecutor::default())
+            .set_startup_executor(SerialExecutor::without_tracker_clears())
+            .init_resource::<Vec<u32>>()
+            .add_system_to_stage(stage::UPDATE, render.system());
+        app.add_sub_app(
+            "render",
+            std::mem::take(&mut render_app.app),
+            |main, render| {
+                // extract the positions of the main world into the render world
+                render.world.clear();
+                let positions = main
+                    .world
+                    .query::<&Position>()
+                    .iter()
+                    .copied()
+                    .collect::<Vec<_>>();
+                render
+                    .world
+                    .spawn_batch(positions.into_iter().map(|position| (position,)));
+            },
+        );
+        // an isolated instance that is never synchronized with the main app
+        app.add_sub_app("instance", build_simulation(), |_, _| {});
+
+        app.startup();
+        app.update();
+        app.update();
+
+        let render_app = app.sub_app("render").unwrap();
+        assert_eq!(*render_app.resources.get::<Vec<u32>>().unwrap(), vec![2]);
+        let instance = app.sub_app_mut("instance").unwrap();
+        instance.world.spawn((Position(10),));
+        app.update();
+        let mut positions = app
+            .sub_app_mut("instance")
+            .unwrap()
+            .world
+            .query::<&Position>()
+            .iter()
+            .map(|position| position.0)
+            .collect::<Vec<_>>();
+        positions.sort_unstable();
+        assert_eq!(positions, vec![3, 11]);
+        assert_eq!(
+            *app.sub_app("render")
+                .unwrap()
+                .resources
+                .get::<Vec<u32>>()
+                .unwrap(),
+            vec![3]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Sub-app already exists: render")]
+    fn duplicate_sub_app() {
+        let mut app = App::default();
+        app.add_sub_app("render", App::default(), |_, _| {});
+        app.add_sub_app("render", App::default(), |_, _| {});
+    }
+}
@@ -4,9 +4,15 @@ use crate::{
     app::{App, AppExit},
     event::Events,
     plugin::Plugin,
+    plugin_group::{PluginGroup, PluginGroupBuilder},
+    replay::{EventRecording, EventRecordingSystems},
     stage, startup_stage,
+    state::{State, StateStages, StateValue},
+};
+use bevy_ecs::{
+    AmbiguityDetection, Executor, FromResources, IntoQuerySystem, Resources, RunCriteria, System,
+    World,
 };
-use bevy_ecs::{FromResources, IntoQuerySystem, Resources, System, World};
 
 /// Configure [App]s using the builder pattern
 pub struct AppBuilder {
@@ -15,9 +21,7 @@ pub struct AppBuilder {
 
 impl Default for AppBuilder {
     fn default() -> Self {
-        let mut app_builder = AppBuilder {
-            app: App::default(),
-        };
+        let mut app_builder = AppBuilder::empty();
 
         app_builder.add_default_stages();
         app_builder.add_event::<AppExit>();
@@ -32,6 +36,10 @@ impl AppBuilder {
         }
     }
 
+    pub(crate) fn from_app(app: App) -> AppBuilder {
+        AppBuilder { app }
+    }
+
     pub fn resources(&self) -> &Resources {
         &self.app.resources
     }
@@ -69,6 +77,22 @@ impl AppBuilder {
         self
     }
 
+    /// Sets the [RunCriteria] of the given stage, replacing any existing criteria. The criteria are evaluated each time
+    /// the stage is about to run, after the stages before it ran, and return a [ShouldRun](bevy_ecs::ShouldRun):
+    /// `No` skips the stage for this update, `Yes` runs it once, and `YesAndLoop` runs it and evaluates the criteria
+    /// again.
+    ///
+    /// # Panics
+    /// Panics if the stage does not exist.
+    pub fn set_stage_run_criteria(
+        &mut self,
+        stage_name: &'static str,
+        run_criteria: impl RunCriteria + 'static,
+    ) -> &mut Self {
+        self.app.schedule.set_run_criteria(stage_name, run_criteria);
+        self
+    }
+
     pub fn add_startup_stage(&mut self, stage_name: &'static str) -> &mut Self {
         self.app.startup_schedule.add_stage(stage_name);
         self
@@ -199,6 +223,96 @@ impl AppBuilder {
             .add_system_to_stage(stage::EVENT_UPDATE, Events::<T>::update_system.system())
     }
 
+    /// Records the events of type `T` in each frame while a [Replay](crate::Replay) is recorded and sends the recorded
+    /// events while it is replayed. Only events sent from outside of the schedule, like input events, are recorded,
+    /// because systems send their events again when a frame is replayed. The event type must have been added with
+    /// [AppBuilder::add_event] and the replay with [ReplayPlugin](crate::ReplayPlugin). Recording an event type again
+    /// does nothing.
+    pub fn record_event<T>(&mut self) -> &mut Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        if self.resources().get::<EventRecording<T>>().is_none() {
+            self.init_resource::<EventRecording<T>>();
+        }
+        if self.resources().get::<EventRecordingSystems<T>>().is_none() {
+            self.init_resource::<EventRecordingSystems<T>>()
+                .add_system_to_stage(stage::FIRST, EventRecording::<T>::record_system.system())
+                .add_system_to_stage(stage::LAST, EventRecording::<T>::skip_system.system());
+        }
+        self
+    }
+
+    /// Adds a [State] resource with the given initial value. Systems can then be registered to run when a value of the
+    /// state is entered, while it is active, and when it is exited. Transitions are applied right before [stage::UPDATE].
+    pub fn add_state<T: StateValue>(&mut self, initial: T) -> &mut Self {
+        self.init_resource::<StateStages<T>>();
+        let transition_stage = State::<T>::transition_stage();
+        self.app
+            .schedule
+            .add_stage_before(stage::UPDATE, transition_stage.clone());
+        self.app
+            .schedule
+            .add_system_to_stage(transition_stage, State::<T>::transition_system.system());
+        self.add_resource(State::new(initial))
+    }
+
+    /// Adds a system that runs once when the [State] changes to the given value
+    pub fn on_state_enter<T: StateValue>(
+        &mut self,
+        value: T,
+        system: Box<dyn System>,
+    ) -> &mut Self {
+        let stage_name = State::<T>::enter_stage(&self.app.resources, &value);
+        if !self.app.schedule.has_stage(&stage_name) {
+            self.app
+                .schedule
+                .add_stage_after(State::<T>::transition_stage(), stage_name.clone());
+            self.app
+                .schedule
+                .set_run_criteria(stage_name.clone(), State::enter_criteria(value));
+        }
+
+        self.app.schedule.add_system_to_stage(stage_name, system);
+        self
+    }
+
+    /// Adds a system that runs every update while the [State] has the given value
+    pub fn on_state_update<T: StateValue>(
+        &mut self,
+        value: T,
+        system: Box<dyn System>,
+    ) -> &mut Self {
+        let stage_name = State::<T>::update_stage(&self.app.resources, &value);
+        if !self.app.schedule.has_stage(&stage_name) {
+            self.app
+                .schedule
+                .add_stage_before(stage::UPDATE, stage_name.clone());
+            self.app
+                .schedule
+                .set_run_criteria(stage_name.clone(), State::update_criteria(value));
+        }
+
+        self.app.schedule.add_system_to_stage(stage_name, system);
+        self
+    }
+
+    /// Adds a system that runs once when the [State] changes away from the given value
+    pub fn on_state_exit<T: StateValue>(&mut self, value: T, system: Box<dyn System>) -> &mut Self {
+        let stage_name = State::<T>::exit_stage(&self.app.resources, &value);
+        if !self.app.schedule.has_stage(&stage_name) {
+            self.app
+                .schedule
+                .add_stage_before(State::<T>::transition_stage(), stage_name.clone());
+            self.app
+                .schedule
+                .set_run_criteria(stage_name.clone(), State::exit_criteria(value));
+        }
+
+        self.app.schedule.add_system_to_stage(stage_name, system);
+        self
+    }
+
     pub fn add_resource<T>(&mut self, resource: T) -> &mut Self
     where
         T: Send + Sync + 'static,
@@ -217,6 +331,46 @@ impl AppBuilder {
         self
     }
 
+    /// Sets how the app's schedules report systems with an ambiguous order when they are initialized. See
+    /// [AmbiguityDetection].
+    pub fn set_ambiguity_detection(
+        &mut self,
+        ambiguity_detection: AmbiguityDetection,
+    ) -> &mut Self {
+        self.app
+            .schedule
+            .set_ambiguity_detection(ambiguity_detection);
+        self.app
+            .startup_schedule
+            .set_ambiguity_detection(ambiguity_detection);
+        self
+    }
+
+    /// Runs the app's schedule with `executor` every update instead of the app's `ParallelExecutor`
+    pub fn set_executor(&mut self, executor: impl Executor + 'static) -> &mut Self {
+        self.app.custom_executor = Some(Box::new(executor));
+        self
+    }
+
+    /// Runs the app's startup schedule with `executor` instead of the app's startup `ParallelExecutor`. It should not
+    /// clear the trackers, so that the first update sees the changes made during startup.
+    pub fn set_startup_executor(&mut self, executor: impl Executor + 'static) -> &mut Self {
+        self.app.custom_startup_executor = Some(Box::new(executor));
+        self
+    }
+
+    /// Adds a sub-app, see [App::add_sub_app]
+    pub fn add_sub_app(
+        &mut self,
+        name: &'static str,
+        mut sub_app: AppBuilder,
+        extract: impl Fn(&mut App, &mut App) + 'static,
+    ) -> &mut Self {
+        self.app
+            .add_sub_app(name, std::mem::take(&mut sub_app.app), extract);
+        self
+    }
+
     pub fn set_runner(&mut self, run_fn: impl Fn(App) + 'static) -> &mut Self {
         self.app.runner = Box::new(run_fn);
         self
@@ -226,16 +380,63 @@ impl AppBuilder {
     pub fn load_plugin(&mut self, path: &str) -> &mut Self {
         let (_lib, plugin) = dynamically_load_plugin(path);
         log::debug!("loaded plugin: {}", plugin.name());
+        self.register_plugin(plugin.as_ref());
         plugin.build(self);
         self
     }
 
+    /// Adds a plugin to the app and builds it.
+    ///
+    /// # Panics
+    /// Panics if a plugin with the same name was already added.
     pub fn add_plugin<T>(&mut self, plugin: T) -> &mut Self
     where
         T: Plugin,
     {
         log::debug!("added plugin: {}", plugin.name());
+        self.register_plugin(&plugin);
         plugin.build(self);
         self
     }
+
+    /// Adds a boxed plugin to the app and builds it, like [AppBuilder::add_plugin]. Used for plugins whose type is only
+    /// known at runtime, like the plugins of a [PluginGroup].
+    ///
+    /// # Panics
+    /// Panics if a plugin with the same name was already added.
+    pub fn add_boxed_plugin(&mut self, plugin: Box<dyn Plugin>) -> &mut Self {
+        log::debug!("added plugin: {}", plugin.name());
+        self.register_plugin(plugin.as_ref());
+        plugin.build(self);
+        self
+    }
+
+    pub(crate) fn register_plugin(&mut self, plugin: &dyn Plugin) {
+        if !self.app.plugin_names.insert(plugin.name().to_string()) {
+            panic!("Plugin already added: {}", plugin.name());
+        }
+    }
+
+    /// Returns true if a plugin with the given name was added
+    pub fn has_plugin(&self, name: &str) -> bool {
+        self.app.plugin_names.contains(name)
+    }
+
+    /// Adds the plugins of a [PluginGroup] in order
+    pub fn add_plugins<T: PluginGroup>(&mut self, group: T) -> &mut Self {
+        self.add_plugins_with(group, |group| group)
+    }
+
+    /// Adds the plugins of a [PluginGroup] after `f` has enabled, disabled, replaced or inserted plugins in it
+    pub fn add_plugins_with<T, F>(&mut self, mut group: T, f: F) -> &mut Self
+    where
+        T: PluginGroup,
+        F: FnOnce(&mut PluginGroupBuilder) -> &mut PluginGroupBuilder,
+    {
+        let mut builder = PluginGroupBuilder::default();
+        group.build(&mut builder);
+        f(&mut builder);
+        builder.finish(self);
+        self
+    }
 }
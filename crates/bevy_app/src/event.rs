@@ -228,6 +228,17 @@ impl<T: bevy_ecs::Resource> Events<T> {
         self.events_b.clear();
     }
 
+    /// Removes the events that were sent after `reader` last read events. Readers that already read those events will
+    /// skip as many of the events that are sent next.
+    pub(crate) fn discard_unread(&mut self, reader: &EventReader<T>) {
+        let event_count = reader.last_event_count;
+        self.events_a
+            .retain(|event_instance| event_instance.event_count < event_count);
+        self.events_b
+            .retain(|event_instance| event_instance.event_count < event_count);
+        self.event_count = self.event_count.min(event_count);
+    }
+
     /// Creates a draining iterator that removes all events.
     pub fn drain<'a>(&'a mut self) -> impl Iterator<Item = T> + 'a {
         let map = |i: EventInstance<T>| i.event;
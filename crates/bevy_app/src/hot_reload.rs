@@ -0,0 +1,382 @@
+#[cfg(feature = "dynamic_plugins")]
+use crate::plugin::CreatePlugin;
+use crate::{App, AppBuilder, Plugin};
+use bevy_ecs::{Resources, SystemId, World};
+#[cfg(feature = "dynamic_plugins")]
+use libloading::{Library, Symbol};
+use std::{any::TypeId, collections::HashSet};
+#[cfg(feature = "dynamic_plugins")]
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+
+/// Runs while reloadable plugins are built and unloaded, so that other parts of an app can keep the state of a plugin
+/// across reloads. Hooks are added with [AppBuilder::add_reload_hook].
+pub trait ReloadHook: Send + Sync + 'static {
+    /// Runs before the plugin called `plugin` is built, both when it is first added and when it is reloaded
+    fn before_build(&mut self, _plugin: &str, _world: &mut World, _resources: &mut Resources) {}
+
+    /// Runs after the plugin called `plugin` is built
+    fn after_build(&mut self, _plugin: &str, _world: &mut World, _resources: &mut Resources) {}
+
+    /// Runs before the plugin called `plugin` is unloaded, while its systems and resources still exist
+    fn before_unload(&mut self, _plugin: &str, _world: &mut World, _resources: &mut Resources) {}
+}
+
+/// A plugin and everything it added to an app when it was built
+struct ReloadablePlugin {
+    name: String,
+    // kept so that the plugin is dropped when it is unloaded, before its library
+    _plugin: Box<dyn Plugin>,
+    systems: Vec<SystemId>,
+    startup_systems: Vec<SystemId>,
+    resources: Vec<TypeId>,
+    // declared last, so that the library is unloaded after the plugin has been dropped
+    #[cfg(feature = "dynamic_plugins")]
+    library: Option<PluginLibrary>,
+}
+
+/// The plugins of an app that can be replaced while it runs, see [AppBuilder::add_reloadable_plugin]
+#[derive(Default)]
+pub struct ReloadablePlugins {
+    plugins: Vec<ReloadablePlugin>,
+    hooks: Vec<Box<dyn ReloadHook>>,
+}
+
+impl ReloadablePlugins {
+    /// Returns the names of the reloadable plugins
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.plugins.iter().map(|plugin| plugin.name.as_str())
+    }
+}
+
+impl AppBuilder {
+    /// Adds a plugin that can be replaced while the app runs, see [App::reload_plugin]. The systems and resources the
+    /// plugin adds are tracked, so that they can be removed again. Stages added by the plugin are kept.
+    pub fn add_reloadable_plugin(&mut self, plugin: impl Plugin) -> &mut Self {
+        self.add_boxed_reloadable_plugin(Box::new(plugin));
+        self
+    }
+
+    fn add_boxed_reloadable_plugin(&mut self, plugin: Box<dyn Plugin>) -> &mut ReloadablePlugin {
+        self.register_plugin(plugin.as_ref());
+        let plugin = self.build_reloadable_plugin(plugin);
+        let plugins = &mut self.app.reloadable_plugins.plugins;
+        plugins.push(plugin);
+        plugins.last_mut().unwrap()
+    }
+
+    pub fn add_reload_hook(&mut self, hook: impl ReloadHook) -> &mut Self {
+        self.app.reloadable_plugins.hooks.push(Box::new(hook));
+        self
+    }
+
+    fn build_reloadable_plugin(&mut self, plugin: Box<dyn Plugin>) -> ReloadablePlugin {
+        let name = plugin.name().to_string();
+        log::debug!("building reloadable plugin: {}", name);
+        let app = &mut self.app;
+        for hook in app.reloadable_plugins.hooks.iter_mut() {
+            hook.before_build(&name, &mut app.world, &mut app.resources);
+        }
+        let systems = app.schedule.system_ids().collect::<HashSet<_>>();
+        let startup_systems = app.startup_schedule.system_ids().collect::<HashSet<_>>();
+        let resources = app.resources.resource_types().collect::<HashSet<_>>();
+
+        plugin.build(self);
+
+        let app = &mut self.app;
+        let plugin = ReloadablePlugin {
+            name,
+            _plugin: plugin,
+            systems: app
+                .schedule
+                .system_ids()
+                .filter(|id| !systems.contains(id))
+                .collect(),
+            startup_systems: app
+                .startup_schedule
+                .system_ids()
+                .filter(|id| !startup_systems.contains(id))
+                .collect(),
+            resources: app
+                .resources
+                .resource_types()
+                .filter(|ty| !resources.contains(ty))
+                .collect(),
+            #[cfg(feature = "dynamic_plugins")]
+            library: None,
+        };
+        for hook in app.reloadable_plugins.hooks.iter_mut() {
+            hook.after_build(&plugin.name, &mut app.world, &mut app.resources);
+        }
+        plugin
+    }
+}
+
+impl App {
+    /// Replaces the reloadable plugin called `name` with `plugin`. The systems and resources that the old plugin added
+    /// are removed, including the local resources of its systems, and then the new plugin is built. Startup systems
+    /// added by the new plugin are not run, because the state created by the old plugin's startup systems is kept.
+    ///
+    /// # Panics
+    /// Panics if there is no reloadable plugin called `name`.
+    pub fn reload_plugin(&mut self, name: &str, plugin: Box<dyn Plugin>) {
+        let index = self
+            .reloadable_plugins
+            .plugins
+            .iter()
+            .position(|plugin| plugin.name == name)
+            .unwrap_or_else(|| panic!("Reloadable plugin does not exist: {}", name));
+        self.reload_plugin_at(index, plugin);
+    }
+
+    fn reload_plugin_at(&mut self, index: usize, plugin: Box<dyn Plugin>) -> &mut ReloadablePlugin {
+        let old = self.reloadable_plugins.plugins.remove(index);
+        log::debug!("unloading reloadable plugin: {}", old.name);
+        for hook in self.reloadable_plugins.hooks.iter_mut() {
+            hook.before_unload(&old.name, &mut self.world, &mut self.resources);
+        }
+        for &id in old.systems.iter() {
+            self.schedule.remove_system(id);
+        }
+        for &id in old.startup_systems.iter() {
+            self.startup_schedule.remove_system(id);
+        }
+        let system_ids = old
+            .systems
+            .iter()
+            .chain(old.startup_systems.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        self.resources.remove_local_resources(&system_ids);
+        for &ty in old.resources.iter() {
+            self.resources.remove_type(ty);
+        }
+        drop(old);
+
+        let mut builder = AppBuilder::from_app(std::mem::take(self));
+        let mut plugin = builder.build_reloadable_plugin(plugin);
+        for id in plugin.startup_systems.drain(..) {
+            builder.app.startup_schedule.remove_system(id);
+        }
+        *self = builder.app;
+
+        let plugins = &mut self.reloadable_plugins.plugins;
+        plugins.insert(index, plugin);
+        &mut plugins[index]
+    }
+}
+
+/// A copy of a plugin's dynamic library. Libraries are loaded from copies, so that the original file can be replaced
+/// and the new version loaded while the old one is still in use.
+#[cfg(feature = "dynamic_plugins")]
+struct PluginLibrary {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    copy: PathBuf,
+    library: Option<Library>,
+}
+
+#[cfg(feature = "dynamic_plugins")]
+impl PluginLibrary {
+    fn load(path: &Path) -> Result<(Self, Box<dyn Plugin>), Box<dyn std::error::Error>> {
+        static COPIES: AtomicUsize = AtomicUsize::new(0);
+
+        let modified = std::fs::metadata(path)?.modified().ok();
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| format!("not a file: {}", path.display()))?;
+        let copy = std::env::temp_dir().join(format!(
+            "{}-{}-{}",
+            std::process::id(),
+            COPIES.fetch_add(1, Ordering::Relaxed),
+            file_name.to_string_lossy()
+        ));
+        std::fs::copy(path, &copy)?;
+        let mut library = PluginLibrary {
+            path: path.to_owned(),
+            modified,
+            copy,
+            library: None,
+        };
+        let lib = Library::new(&library.copy)?;
+        let plugin = unsafe {
+            let func: Symbol<CreatePlugin> = lib.get(b"_create_plugin")?;
+            Box::from_raw(func())
+        };
+        library.library = Some(lib);
+        Ok((library, plugin))
+    }
+
+    fn changed(&self) -> bool {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        modified.is_some() && modified != self.modified
+    }
+}
+
+#[cfg(feature = "dynamic_plugins")]
+impl Drop for PluginLibrary {
+    fn drop(&mut self) {
+        self.library = None;
+        let _ = std::fs::remove_file(&self.copy);
+    }
+}
+
+#[cfg(feature = "dynamic_plugins")]
+impl AppBuilder {
+    /// Loads a plugin from a dynamic library like [AppBuilder::load_plugin], and reloads it with
+    /// [App::reload_plugin] whenever the library file changes.
+    ///
+    /// Components of types that the plugin registers with the app's `TypeRegistry` are kept across reloads. Values of
+    /// other types defined by the plugin must not outlive it.
+    pub fn load_reloadable_plugin(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        let path = path.as_ref();
+        let (library, plugin) = PluginLibrary::load(path)
+            .unwrap_or_else(|err| panic!("Failed to load plugin {}: {}", path.display(), err));
+        log::debug!("loaded reloadable plugin: {}", plugin.name());
+        self.add_boxed_reloadable_plugin(plugin).library = Some(library);
+        self
+    }
+}
+
+#[cfg(feature = "dynamic_plugins")]
+impl App {
+    /// Reloads the plugins loaded with [AppBuilder::load_reloadable_plugin] whose library file changed. This runs at
+    /// the start of every [App::update].
+    pub fn reload_changed_plugins(&mut self) {
+        for index in 0..self.reloadable_plugins.plugins.len() {
+            let path = match &self.reloadable_plugins.plugins[index].library {
+                Some(library) if library.changed() => library.path.clone(),
+                _ => continue,
+            };
+            match PluginLibrary::load(&path) {
+                Ok((library, plugin)) => {
+                    log::info!("reloading plugin: {}", path.display());
+                    self.reload_plugin_at(index, plugin).library = Some(library);
+                }
+                // the library might still be being written, so try again in the next update
+                Err(err) => log::warn!("failed to reload plugin {}: {}", path.display(), err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReloadHook;
+    use crate::{stage, App, AppBuilder, Plugin};
+    use bevy_ecs::{IntoQuerySystem, Local, ResMut, Resources, SerialExecutor, World};
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    struct Counter {
+        value: u32,
+        step: u32,
+    }
+
+    struct CountPlugin {
+        step: u32,
+    }
+
+    fn count(mut frames: Local<u32>, mut counter: ResMut<Counter>, mut log: ResMut<Log>) {
+        *frames += 1;
+        counter.value += counter.step;
+        log.0.push(format!("frame {} of {}", *frames, counter.step));
+    }
+
+    impl Plugin for CountPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.add_resource(Counter {
+                value: 100 * self.step,
+                step: self.step,
+            })
+            .add_system_to_stage(stage::UPDATE, count.system())
+            .add_startup_system(count.system());
+        }
+    }
+
+    struct LogHook;
+
+    impl ReloadHook for LogHook {
+        fn before_build(&mut self, plugin: &str, _world: &mut World, resources: &mut Resources) {
+            let name = plugin.rsplit("::").next().unwrap();
+            let message = format!("build {}", name);
+            resources.get_mut::<Log>().unwrap().0.push(message);
+        }
+
+        fn before_unload(&mut self, plugin: &str, _world: &mut World, resources: &mut Resources) {
+            let name = plugin.rsplit("::").next().unwrap();
+            let message = format!("unload {}", name);
+            resources.get_mut::<Log>().unwrap().0.push(message);
+        }
+    }
+
+    #[test]
+    fn reload_plugin() {
+        let mut builder = App::build();
+        builder
+            .set_executor(SerialExecutor::default())
+            .set_startup_executor(SerialExecutor::without_tracker_clears())
+            .init_resource::<Log>()
+            .add_reload_hook(LogHook)
+            .add_reloadable_plugin(CountPlugin { step: 1 });
+        let mut app = std::mem::take(&mut builder.app);
+        app.startup();
+        app.update();
+        assert_eq!(app.resources.get::<Counter>().unwrap().value, 102);
+
+        app.reload_plugin(
+            std::any::type_name::<CountPlugin>(),
+            Box::new(CountPlugin { step: 2 }),
+        );
+        app.update();
+        app.update();
+        assert_eq!(app.resources.get::<Counter>().unwrap().value, 204);
+        assert_eq!(
+            app.resources.get::<Log>().unwrap().0,
+            vec![
+                "build CountPlugin",
+                "frame 1 of 1",
+                "frame 1 of 1",
+                "unload CountPlugin",
+                "build CountPlugin",
+                "frame 1 of 2",
+                "frame 2 of 2",
+            ]
+        );
+        assert_eq!(app.startup_schedule.system_ids().count(), 0);
+    }
+
+    struct DependentPlugin;
+
+    impl Plugin for DependentPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            assert!(app.has_plugin(std::any::type_name::<CountPlugin>()));
+        }
+    }
+
+    #[test]
+    fn reload_keeps_plugin_names() {
+        let mut builder = App::build();
+        builder
+            .init_resource::<Log>()
+            .add_plugin(CountPlugin { step: 1 })
+            .add_reloadable_plugin(DependentPlugin);
+        let mut app = std::mem::take(&mut builder.app);
+        app.reload_plugin(
+            std::any::type_name::<DependentPlugin>(),
+            Box::new(DependentPlugin),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Reloadable plugin does not exist: missing")]
+    fn reload_missing_plugin() {
+        App::default().reload_plugin("missing", Box::new(CountPlugin { step: 1 }));
+    }
+}
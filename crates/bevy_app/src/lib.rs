@@ -2,20 +2,30 @@
 pub mod stage;
 /// The names of the default App startup stages
 pub mod startup_stage;
+/// Helpers for testing Apps frame by frame
+pub mod testing;
 
 mod app;
 mod app_builder;
 mod event;
+mod hot_reload;
 mod plugin;
+mod plugin_group;
+mod replay;
 mod schedule_runner;
+mod state;
 mod task_pool_options;
 
 pub use app::*;
 pub use app_builder::*;
 pub use bevy_derive::DynamicPlugin;
 pub use event::*;
+pub use hot_reload::*;
 pub use plugin::*;
+pub use plugin_group::*;
+pub use replay::*;
 pub use schedule_runner::*;
+pub use state::*;
 pub use task_pool_options::*;
 
 pub mod prelude {
@@ -24,6 +34,9 @@ pub mod prelude {
         app_builder::AppBuilder,
         event::{EventReader, Events},
         plugin::Plugin,
-        stage, DynamicPlugin,
+        plugin_group::{PluginGroup, PluginGroupBuilder},
+        stage,
+        state::State,
+        DynamicPlugin,
     };
 }
@@ -0,0 +1,186 @@
+use crate::{AppBuilder, Plugin};
+use std::{any::TypeId, collections::HashMap};
+
+/// An ordered list of [Plugin]s that are added to an app together. Use
+/// [AppBuilder::add_plugins_with](crate::AppBuilder::add_plugins_with) to change the group before it is added.
+pub trait PluginGroup: 'static {
+    fn build(&mut self, group: &mut PluginGroupBuilder);
+}
+
+struct PluginEntry {
+    plugin: Box<dyn Plugin>,
+    enabled: bool,
+}
+
+/// The plugins of a [PluginGroup], in the order they are added. Plugins are identified by their type.
+#[derive(Default)]
+pub struct PluginGroupBuilder {
+    plugins: HashMap<TypeId, PluginEntry>,
+    order: Vec<TypeId>,
+}
+
+impl PluginGroupBuilder {
+    fn index_of<Target: Plugin>(&self) -> usize {
+        self.order
+            .iter()
+            .position(|&ty| ty == TypeId::of::<Target>())
+            .unwrap_or_else(|| panic!("Plugin does not exist: {}", std::any::type_name::<Target>()))
+    }
+
+    fn insert<T: Plugin>(&mut self, index: usize, plugin: T) -> &mut Self {
+        if self.plugins.contains_key(&TypeId::of::<T>()) {
+            panic!("Plugin already exists: {}", plugin.name());
+        }
+        self.plugins.insert(
+            TypeId::of::<T>(),
+            PluginEntry {
+                plugin: Box::new(plugin),
+                enabled: true,
+            },
+        );
+        self.order.insert(index, TypeId::of::<T>());
+        self
+    }
+
+    /// Adds a plugin to the end of the group
+    pub fn add<T: Plugin>(&mut self, plugin: T) -> &mut Self {
+        self.insert(self.order.len(), plugin)
+    }
+
+    /// Adds a plugin right before the plugin of type `Target`
+    pub fn add_before<Target: Plugin, T: Plugin>(&mut self, plugin: T) -> &mut Self {
+        let index = self.index_of::<Target>();
+        self.insert(index, plugin)
+    }
+
+    /// Adds a plugin right after the plugin of type `Target`
+    pub fn add_after<Target: Plugin, T: Plugin>(&mut self, plugin: T) -> &mut Self {
+        let index = self.index_of::<Target>() + 1;
+        self.insert(index, plugin)
+    }
+
+    /// Replaces the plugin of type `Target` with another plugin, which takes its place in the group
+    pub fn replace<Target: Plugin, T: Plugin>(&mut self, plugin: T) -> &mut Self {
+        let index = self.index_of::<Target>();
+        self.order.remove(index);
+        self.plugins.remove(&TypeId::of::<Target>());
+        self.insert(index, plugin)
+    }
+
+    /// Enables the plugin of type `T`. Plugins are enabled when they are added.
+    pub fn enable<T: Plugin>(&mut self) -> &mut Self {
+        self.set_enabled::<T>(true)
+    }
+
+    /// Disables the plugin of type `T`, so that it is not added to the app
+    pub fn disable<T: Plugin>(&mut self) -> &mut Self {
+        self.set_enabled::<T>(false)
+    }
+
+    fn set_enabled<T: Plugin>(&mut self, enabled: bool) -> &mut Self {
+        let entry = self
+            .plugins
+            .get_mut(&TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("Plugin does not exist: {}", std::any::type_name::<T>()));
+        entry.enabled = enabled;
+        self
+    }
+
+    /// Returns the names of the enabled plugins, in order
+    pub fn plugin_names(&self) -> Vec<&str> {
+        self.order
+            .iter()
+            .map(|ty| &self.plugins[ty])
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.plugin.name())
+            .collect()
+    }
+
+    /// Adds the enabled plugins to the app, in order
+    pub fn finish(mut self, app: &mut AppBuilder) {
+        for ty in self.order.iter() {
+            let entry = self.plugins.remove(ty).unwrap();
+            if entry.enabled {
+                app.add_boxed_plugin(entry.plugin);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PluginGroup, PluginGroupBuilder};
+    use crate::{AppBuilder, Plugin};
+
+    struct Log(Vec<&'static str>);
+
+    macro_rules! test_plugin {
+        ($name: ident) => {
+            struct $name;
+
+            impl Plugin for $name {
+                fn build(&self, app: &mut AppBuilder) {
+                    app.resources_mut()
+                        .get_mut::<Log>()
+                        .unwrap()
+                        .0
+                        .push(stringify!($name));
+                }
+            }
+        };
+    }
+
+    test_plugin!(A);
+    test_plugin!(B);
+    test_plugin!(C);
+    test_plugin!(D);
+    test_plugin!(E);
+
+    struct Group;
+
+    impl PluginGroup for Group {
+        fn build(&mut self, group: &mut PluginGroupBuilder) {
+            group.add(A).add(B).add(C);
+        }
+    }
+
+    fn build(
+        f: impl FnOnce(&mut PluginGroupBuilder) -> &mut PluginGroupBuilder,
+    ) -> Vec<&'static str> {
+        let mut app = AppBuilder::empty();
+        app.add_resource(Log(Vec::new())).add_plugins_with(Group, f);
+        let log = app.resources().get::<Log>().unwrap().0.clone();
+        log
+    }
+
+    #[test]
+    fn plugin_group() {
+        assert_eq!(build(|group| group), vec!["A", "B", "C"]);
+        assert_eq!(
+            build(|group| group
+                .disable::<B>()
+                .add_before::<A, _>(D)
+                .add_after::<C, _>(E)),
+            vec!["D", "A", "C", "E"]
+        );
+        assert_eq!(
+            build(|group| group.replace::<B, _>(D).disable::<A>().enable::<A>()),
+            vec!["A", "D", "C"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Plugin does not exist")]
+    fn missing_plugin() {
+        build(|group| group.disable::<D>());
+    }
+
+    #[test]
+    #[should_panic(expected = "Plugin already added")]
+    fn duplicate_plugin() {
+        let mut app = AppBuilder::empty();
+        app.add_resource(Log(Vec::new()))
+            .add_plugins(Group)
+            .add_plugin(B);
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{
+    app_builder::AppBuilder,
+    event::{EventReader, Events},
+    plugin::Plugin,
+    stage,
+};
+use bevy_ecs::{IntoQuerySystem, Res, ResMut};
+use std::{marker::PhantomData, time::Duration};
+
+/// Adds a [Replay] to an app and advances it at the end of each frame. A [Replay] that was added before this plugin is
+/// kept, so an app can be built to record or replay its inputs.
+#[derive(Default)]
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if app.resources().get::<Replay>().is_none() {
+            app.init_resource::<Replay>();
+        }
+        app.add_event::<ReplayFinished>()
+            .add_system_to_stage(stage::LAST, Replay::frame_system.system());
+    }
+}
+
+/// Whether an app records its inputs, replays recorded inputs, or does neither
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ReplayMode {
+    Off,
+    Record,
+    Replay,
+}
+
+/// Records the inputs of each frame of an app, or feeds previously recorded inputs back into it.
+///
+/// The inputs of a frame are the `Time` delta, which bevy_core records, and the events of the types registered with
+/// [AppBuilder::record_event](crate::AppBuilder::record_event), which are kept in an [EventRecording] per event type.
+/// Randomness is seeded with [Replay::seed] when the app starts.
+///
+/// Once every recorded frame has been replayed, the replay stops and sends a [ReplayFinished] event. The app then
+/// measures time and receives events as usual again.
+///
+/// An app that runs its systems with a [SerialExecutor](bevy_ecs::SerialExecutor) and does not depend on anything else
+/// that varies between runs produces the same [World](bevy_ecs::World) each time it is replayed with the same inputs.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    mode: ReplayMode,
+    seed: u64,
+    time_deltas: Vec<Duration>,
+    frame: usize,
+    finished: bool,
+}
+
+/// Sent when a [Replay] has replayed every recorded frame
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReplayFinished;
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            mode: ReplayMode::Off,
+            seed: 0,
+            time_deltas: Vec::new(),
+            frame: 0,
+            finished: false,
+        }
+    }
+}
+
+impl Replay {
+    /// Records the inputs of each frame of a run seeded with `seed`
+    pub fn record(seed: u64) -> Self {
+        Self {
+            mode: ReplayMode::Record,
+            seed,
+            ..Default::default()
+        }
+    }
+
+    /// Replays a run that was seeded with `seed` and had the given time deltas. Recorded events are replayed from the
+    /// app's [EventRecording]s.
+    pub fn from_recording(seed: u64, time_deltas: Vec<Duration>) -> Self {
+        Self {
+            mode: ReplayMode::Replay,
+            seed,
+            time_deltas,
+            ..Default::default()
+        }
+    }
+
+    pub fn mode(&self) -> ReplayMode {
+        self.mode
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The index of the current frame
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// The time delta of each frame that was recorded or is being replayed
+    pub fn time_deltas(&self) -> &[Duration] {
+        &self.time_deltas
+    }
+
+    /// Returns the recorded time delta of the current frame when replaying
+    pub fn replayed_time_delta(&self) -> Option<Duration> {
+        match self.mode {
+            ReplayMode::Replay => self.time_deltas.get(self.frame).copied(),
+            _ => None,
+        }
+    }
+
+    /// Records the time delta of the current frame when recording
+    pub fn record_time_delta(&mut self, delta: Duration) {
+        if self.mode == ReplayMode::Record {
+            self.time_deltas.truncate(self.frame);
+            self.time_deltas.push(delta);
+        }
+    }
+
+    /// Returns true if every recorded frame has been replayed
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// A system that advances [Replay::frame] at the end of each frame. When the last recorded frame has been replayed
+    /// it turns the replay off and sends [ReplayFinished].
+    pub fn frame_system(mut replay: ResMut<Replay>, mut finished: ResMut<Events<ReplayFinished>>) {
+        if replay.mode == ReplayMode::Off {
+            return;
+        }
+        replay.frame += 1;
+        if replay.mode == ReplayMode::Replay && replay.frame >= replay.time_deltas.len() {
+            replay.mode = ReplayMode::Off;
+            replay.finished = true;
+            finished.send(ReplayFinished);
+        }
+    }
+}
+
+/// The events of type `T` that were sent to an app from outside of its schedule in each frame of a [Replay]
+pub struct EventRecording<T> {
+    /// The events of each frame
+    pub frames: Vec<Vec<T>>,
+    reader: EventReader<T>,
+}
+
+/// Marks the event types whose [EventRecording] systems have been added to an app
+pub(crate) struct EventRecordingSystems<T>(PhantomData<T>);
+
+impl<T> Default for EventRecordingSystems<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for EventRecording<T> {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new(),
+            reader: Default::default(),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> EventRecording<T> {
+    pub fn new(frames: Vec<Vec<T>>) -> Self {
+        Self {
+            frames,
+            reader: Default::default(),
+        }
+    }
+
+    /// A system that runs at the start of each frame. When recording it stores the events that were sent since the
+    /// last frame. When replaying it replaces them with the recorded events of the current frame.
+    pub fn record_system(
+        replay: Res<Replay>,
+        mut recording: ResMut<EventRecording<T>>,
+        mut events: ResMut<Events<T>>,
+    ) {
+        let recording = &mut *recording;
+        match replay.mode {
+            ReplayMode::Off => {}
+            ReplayMode::Record => {
+                let frame = recording.reader.iter(&events).cloned().collect();
+                recording.frames.truncate(replay.frame);
+                recording.frames.push(frame);
+            }
+            ReplayMode::Replay => {
+                events.discard_unread(&recording.reader);
+                if let Some(frame) = recording.frames.get(replay.frame) {
+                    events.extend(frame.iter().cloned());
+                }
+            }
+        }
+    }
+
+    /// A system that runs at the end of each frame and skips the events that systems sent during the frame, which are
+    /// sent again when the frame is replayed
+    pub fn skip_system(mut recording: ResMut<EventRecording<T>>, events: Res<Events<T>>) {
+        for _ in recording.reader.iter(&events) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventRecording, Replay, ReplayFinished, ReplayPlugin};
+    use crate::{
+        app::App,
+        event::{EventReader, Events},
+        stage,
+    };
+    use bevy_ecs::{
+        Commands, Entity, IntoQuerySystem, Local, Query, Res, ResMut, SerialExecutor, World,
+    };
+    use std::time::Duration;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Input(u32);
+
+    #[derive(Clone)]
+    struct Echo;
+
+    #[derive(Debug, PartialEq)]
+    struct Counter(u32);
+
+    fn echo_system(
+        mut reader: Local<EventReader<Input>>,
+        inputs: Res<Events<Input>>,
+        mut echoes: ResMut<Events<Echo>>,
+        mut sum: ResMut<u32>,
+    ) {
+        for input in reader.iter(&inputs) {
+            *sum = *sum * 10 + input.0;
+            echoes.send(Echo);
+        }
+    }
+
+    fn spawn_system(
+        mut commands: Commands,
+        mut reader: Local<EventReader<Input>>,
+        inputs: Res<Events<Input>>,
+    ) {
+        for input in reader.iter(&inputs) {
+            commands.spawn((Counter(input.0),));
+        }
+    }
+
+    fn count_system(mut query: Query<&mut Counter>) {
+        for mut counter in &mut query.iter() {
+            counter.0 += 1;
+        }
+    }
+
+    fn counters(world: &World) -> Vec<(Entity, u32)> {
+        let mut counters = world
+            .query::<(Entity, &Counter)>()
+            .iter()
+            .map(|(entity, counter)| (entity, counter.0))
+            .collect::<Vec<_>>();
+        counters.sort_by_key(|(entity, _)| entity.id());
+        counters
+    }
+
+    fn run(replay: Replay, recording: EventRecording<Input>, frames: &[Vec<u32>]) -> App {
+        let mut app = App::build();
+        app.set_executor(SerialExecutor::default())
+            .add_resource(replay)
+            .add_plugin(ReplayPlugin)
+            .add_resource(recording)
+            .add_resource(0u32)
+            .add_event::<Input>()
+            .add_event::<Echo>()
+            .record_event::<Input>()
+            .record_event::<Echo>()
+            .add_system_to_stage(stage::UPDATE, echo_system.system())
+            .add_system_to_stage(stage::UPDATE, spawn_system.system())
+            .add_system_to_stage(stage::POST_UPDATE, count_system.system());
+        let mut app = std::mem::take(&mut app.app);
+        for frame in frames {
+            for input in frame {
+                app.resources
+                    .get_mut::<Events<Input>>()
+                    .unwrap()
+                    .send(Input(*input));
+            }
+            app.resources
+                .get_mut::<Replay>()
+                .unwrap()
+                .record_time_delta(Duration::from_millis(16));
+            app.update();
+        }
+        app
+    }
+
+    #[test]
+    fn record_and_replay_events() {
+        let recorded = run(
+            Replay::record(0),
+            EventRecording::default(),
+            &[vec![1], vec![], vec![2, 3]],
+        );
+        let recording = recorded.resources.get::<EventRecording<Input>>().unwrap();
+        assert_eq!(
+            recording.frames,
+            vec![vec![Input(1)], vec![], vec![Input(2), Input(3)]]
+        );
+        assert!(
+            recorded
+                .resources
+                .get::<EventRecording<Echo>>()
+                .unwrap()
+                .frames
+                .iter()
+                .all(|frame| frame.is_empty()),
+            "events sent by systems are not recorded"
+        );
+        assert_eq!(*recorded.resources.get::<u32>().unwrap(), 123);
+
+        let replay = Replay::clone(&recorded.resources.get::<Replay>().unwrap());
+        assert_eq!(replay.time_deltas().len(), 3);
+        let replayed = run(
+            Replay::from_recording(replay.seed(), replay.time_deltas().to_vec()),
+            EventRecording::new(recording.frames.clone()),
+            // live inputs are ignored while replaying
+            &[vec![7], vec![8], vec![9]],
+        );
+        assert_eq!(*replayed.resources.get::<u32>().unwrap(), 123);
+        assert!(replayed.resources.get::<Replay>().unwrap().is_finished());
+    }
+
+    #[test]
+    fn record_event_twice() {
+        let mut app = App::build();
+        app.set_executor(SerialExecutor::default())
+            .add_resource(Replay::record(0))
+            .add_plugin(ReplayPlugin)
+            .add_event::<Input>()
+            .record_event::<Input>()
+            .record_event::<Input>();
+        let mut app = std::mem::take(&mut app.app);
+        for input in &[1, 2] {
+            app.resources
+                .get_mut::<Events<Input>>()
+                .unwrap()
+                .send(Input(*input));
+            app.update();
+        }
+        let recording = app.resources.get::<EventRecording<Input>>().unwrap();
+        assert_eq!(recording.frames, vec![vec![Input(1)], vec![Input(2)]]);
+    }
+
+    #[test]
+    fn replayed_world_matches_recording() {
+        let frames = [vec![1, 2], vec![], vec![3], vec![4, 5]];
+        let recorded = run(Replay::record(0), EventRecording::default(), &frames);
+        let replay = Replay::clone(&recorded.resources.get::<Replay>().unwrap());
+        let recording = recorded.resources.get::<EventRecording<Input>>().unwrap();
+        let replayed = run(
+            Replay::from_recording(replay.seed(), replay.time_deltas().to_vec()),
+            EventRecording::new(recording.frames.clone()),
+            &[vec![], vec![9], vec![], vec![]],
+        );
+
+        let recorded_counters = counters(&recorded.world);
+        assert_eq!(recorded_counters.len(), 5);
+        assert_eq!(recorded_counters, counters(&replayed.world));
+    }
+
+    #[test]
+    fn replay_stops_when_recording_is_exhausted() {
+        let replayed = run(
+            Replay::from_recording(0, vec![Duration::from_millis(16); 2]),
+            EventRecording::new(vec![vec![Input(1)], vec![Input(2)]]),
+            &[vec![7], vec![8], vec![3]],
+        );
+        let replay = replayed.resources.get::<Replay>().unwrap();
+        assert!(replay.is_finished());
+        assert_eq!(replay.mode(), super::ReplayMode::Off);
+        assert_eq!(replay.frame(), 2);
+        // live inputs are handled again once the replay is finished
+        assert_eq!(*replayed.resources.get::<u32>().unwrap(), 123);
+
+        let finished = replayed.resources.get::<Events<ReplayFinished>>().unwrap();
+        assert_eq!(
+            EventReader::<ReplayFinished>::default()
+                .iter(&finished)
+                .count(),
+            1
+        );
+    }
+}
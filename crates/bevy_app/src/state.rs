@@ -0,0 +1,252 @@
+use bevy_ecs::{RefMut, ResMut, Resource, Resources, ShouldRun, World};
+use std::{any::type_name, fmt::Debug};
+
+/// A value that can be used as an app state, usually a fieldless enum
+pub trait StateValue: Resource + Clone + Eq + Debug {}
+impl<T: Resource + Clone + Eq + Debug> StateValue for T {}
+
+/// Tracks the current value of an app state and the transition queued to the next value.
+///
+/// Systems registered with [AppBuilder::on_state_enter](crate::AppBuilder::on_state_enter),
+/// [AppBuilder::on_state_update](crate::AppBuilder::on_state_update) and
+/// [AppBuilder::on_state_exit](crate::AppBuilder::on_state_exit) run according to this resource. A queued transition
+/// is applied once per update, in a single stage right before [stage::UPDATE](crate::stage::UPDATE): the exit systems
+/// of the current value run, then the value changes, then the enter systems of the new value run. The update systems
+/// of the new value also run before [stage::UPDATE](crate::stage::UPDATE).
+///
+/// A transition queued by a system in [stage::UPDATE](crate::stage::UPDATE) or a later stage is applied during the
+/// next update.
+#[derive(Debug)]
+pub struct State<T: StateValue> {
+    current: T,
+    next: Option<T>,
+    just_entered: bool,
+}
+
+impl<T: StateValue> State<T> {
+    pub fn new(initial: T) -> Self {
+        Self {
+            current: initial,
+            next: None,
+            just_entered: true,
+        }
+    }
+
+    pub fn current(&self) -> &T {
+        &self.current
+    }
+
+    /// The value the state will change to during the next transition, if one is queued
+    pub fn next(&self) -> Option<&T> {
+        self.next.as_ref()
+    }
+
+    /// Queues a transition to the given value. Queuing another transition before this one is applied replaces it.
+    pub fn set_next(&mut self, next: T) {
+        self.next = Some(next);
+    }
+
+    pub(crate) fn transition_stage() -> String {
+        format!("state_transition {}", type_name::<T>())
+    }
+
+    pub(crate) fn enter_stage(resources: &Resources, value: &T) -> String {
+        StateStages::<T>::stage_name(resources, "state_enter", value)
+    }
+
+    pub(crate) fn update_stage(resources: &Resources, value: &T) -> String {
+        StateStages::<T>::stage_name(resources, "state_update", value)
+    }
+
+    pub(crate) fn exit_stage(resources: &Resources, value: &T) -> String {
+        StateStages::<T>::stage_name(resources, "state_exit", value)
+    }
+
+    fn get(resources: &Resources) -> RefMut<'_, State<T>> {
+        resources
+            .get_mut::<State<T>>()
+            .unwrap_or_else(|| panic!("State resource does not exist: {}", type_name::<State<T>>()))
+    }
+
+    pub(crate) fn transition_system(mut state: ResMut<State<T>>) {
+        if let Some(next) = state.next.take() {
+            state.current = next;
+            state.just_entered = true;
+        }
+    }
+
+    pub(crate) fn enter_criteria(value: T) -> impl FnMut(&World, &Resources) -> ShouldRun {
+        move |_world, resources| {
+            let mut state = Self::get(resources);
+            if state.just_entered && state.current == value {
+                state.just_entered = false;
+                ShouldRun::Yes
+            } else {
+                ShouldRun::No
+            }
+        }
+    }
+
+    pub(crate) fn update_criteria(value: T) -> impl FnMut(&World, &Resources) -> ShouldRun {
+        move |_world, resources| {
+            let state = Self::get(resources);
+            if state.current == value {
+                ShouldRun::Yes
+            } else {
+                ShouldRun::No
+            }
+        }
+    }
+
+    pub(crate) fn exit_criteria(value: T) -> impl FnMut(&World, &Resources) -> ShouldRun {
+        move |_world, resources| {
+            let state = Self::get(resources);
+            if state.next.is_some() && state.current == value {
+                ShouldRun::Yes
+            } else {
+                ShouldRun::No
+            }
+        }
+    }
+}
+
+/// The values of a state that have stages, in registration order. Stages are named after the index of their value, so
+/// values with the same [Debug] output still get their own stages.
+pub(crate) struct StateStages<T: StateValue> {
+    values: Vec<T>,
+}
+
+impl<T: StateValue> Default for StateStages<T> {
+    fn default() -> Self {
+        Self { values: Vec::new() }
+    }
+}
+
+impl<T: StateValue> StateStages<T> {
+    fn stage_name(resources: &Resources, kind: &str, value: &T) -> String {
+        let mut stages = resources.get_mut::<StateStages<T>>().unwrap_or_else(|| {
+            panic!(
+                "State does not exist: {}. Call add_state before adding state systems.",
+                type_name::<T>()
+            )
+        });
+        let index = match stages.values.iter().position(|v| v == value) {
+            Some(index) => index,
+            None => {
+                stages.values.push(value.clone());
+                stages.values.len() - 1
+            }
+        };
+        format!("{} {}::{:?} #{}", kind, type_name::<T>(), value, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::State;
+    use crate::App;
+    use bevy_ecs::{IntoQuerySystem, ResMut};
+    use bevy_tasks::{ComputeTaskPool, TaskPool};
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    enum AppState {
+        Menu,
+        InGame,
+    }
+
+    fn menu_enter(mut events: ResMut<Vec<&'static str>>) {
+        events.push("menu_enter");
+    }
+
+    fn menu_update(mut events: ResMut<Vec<&'static str>>, mut state: ResMut<State<AppState>>) {
+        events.push("menu_update");
+        state.set_next(AppState::InGame);
+    }
+
+    fn menu_exit(mut events: ResMut<Vec<&'static str>>) {
+        events.push("menu_exit");
+    }
+
+    fn in_game_enter(mut events: ResMut<Vec<&'static str>>) {
+        events.push("in_game_enter");
+    }
+
+    fn in_game_update(mut events: ResMut<Vec<&'static str>>) {
+        events.push("in_game_update");
+    }
+
+    #[test]
+    fn state_transitions() {
+        let mut app_builder = App::build();
+        app_builder
+            .add_resource(ComputeTaskPool(TaskPool::default()))
+            .add_resource(Vec::<&'static str>::new())
+            .add_state(AppState::Menu)
+            .on_state_enter(AppState::Menu, menu_enter.system())
+            .on_state_update(AppState::Menu, menu_update.system())
+            .on_state_exit(AppState::Menu, menu_exit.system())
+            .on_state_enter(AppState::InGame, in_game_enter.system())
+            .on_state_update(AppState::InGame, in_game_update.system());
+        let mut app = std::mem::take(&mut app_builder.app);
+
+        app.update();
+        assert_eq!(
+            *app.resources.get::<Vec<&'static str>>().unwrap(),
+            vec!["menu_enter", "menu_update"]
+        );
+
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.resources.get::<Vec<&'static str>>().unwrap(),
+            vec![
+                "menu_enter",
+                "menu_update",
+                "menu_exit",
+                "in_game_enter",
+                "in_game_update",
+                "in_game_update"
+            ]
+        );
+        assert_eq!(
+            *app.resources.get::<State<AppState>>().unwrap().current(),
+            AppState::InGame
+        );
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    struct Level(u32);
+
+    // every level has the same debug output
+    impl std::fmt::Debug for Level {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("Level")
+        }
+    }
+
+    fn level_one_update(mut events: ResMut<Vec<&'static str>>) {
+        events.push("level_one_update");
+    }
+
+    fn level_two_update(mut events: ResMut<Vec<&'static str>>) {
+        events.push("level_two_update");
+    }
+
+    #[test]
+    fn same_debug_output() {
+        let mut app_builder = App::build();
+        app_builder
+            .add_resource(ComputeTaskPool(TaskPool::default()))
+            .add_resource(Vec::<&'static str>::new())
+            .add_state(Level(1))
+            .on_state_update(Level(1), level_one_update.system())
+            .on_state_update(Level(2), level_two_update.system());
+        let mut app = std::mem::take(&mut app_builder.app);
+
+        app.update();
+        assert_eq!(
+            *app.resources.get::<Vec<&'static str>>().unwrap(),
+            vec!["level_one_update"]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    app::{App, AppExit},
+    app_builder::AppBuilder,
+    event::{EventReader, Events},
+};
+use bevy_ecs::{Ref, RefMut, Resource, Resources, SerialExecutor, World};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    time::Duration,
+};
+
+/// Replaces the measured time between frames in apps built with [AppBuilder::test_app]. While [SimulatedTime::delta]
+/// is set, bevy_core's `Time` advances by exactly that much each frame, so that timing-dependent systems behave the
+/// same on every run. Other apps do not have this resource.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SimulatedTime {
+    pub delta: Option<Duration>,
+}
+
+/// Steps an [App] one frame at a time, without a runner, so that tests can send events between frames and check the
+/// resulting [World] and [Resources]. An app is tested without a window or GPU by leaving out the plugins that create
+/// them.
+///
+/// # Example
+/// ```
+/// # use bevy_app::{prelude::*, testing::TestApp};
+/// # use bevy_ecs::prelude::*;
+/// # use std::time::Duration;
+/// struct Ping;
+/// #[derive(Clone)]
+/// struct Pong;
+///
+/// fn pong_system(mut reader: Local<EventReader<Ping>>, pings: Res<Events<Ping>>, mut pongs: ResMut<Events<Pong>>) {
+///     for _ in reader.iter(&pings) {
+///         pongs.send(Pong);
+///     }
+/// }
+///
+/// let mut app = App::build()
+///     .add_event::<Ping>()
+///     .add_event::<Pong>()
+///     .add_system(pong_system.system())
+///     .test_app(Duration::from_millis(16));
+///
+/// app.send_event(Ping).send_event(Ping).update();
+/// assert_eq!(app.read_events::<Pong>().len(), 2);
+/// app.step(3);
+/// assert!(app.read_events::<Pong>().is_empty());
+/// ```
+pub struct TestApp {
+    pub app: App,
+    readers: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl AppBuilder {
+    /// Finishes building the app and returns a [TestApp] for it. Systems run one at a time in a fixed order, each frame
+    /// lasts `frame_delta` (see [SimulatedTime]), and startup systems run right away.
+    pub fn test_app(&mut self, frame_delta: Duration) -> TestApp {
+        self.set_executor(SerialExecutor::default())
+            .set_startup_executor(SerialExecutor::without_tracker_clears())
+            .add_resource(SimulatedTime {
+                delta: Some(frame_delta),
+            });
+        let mut app = std::mem::take(&mut self.app);
+        app.startup();
+        TestApp {
+            app,
+            readers: HashMap::new(),
+        }
+    }
+}
+
+impl TestApp {
+    /// Runs the app's schedule once
+    pub fn update(&mut self) -> &mut Self {
+        self.app.update();
+        self
+    }
+
+    /// Runs the app's schedule `frames` times
+    pub fn step(&mut self, frames: usize) -> &mut Self {
+        for _ in 0..frames {
+            self.app.update();
+        }
+        self
+    }
+
+    /// Changes how long the following frames last
+    pub fn set_frame_delta(&mut self, delta: Duration) -> &mut Self {
+        self.resource_mut::<SimulatedTime>().delta = Some(delta);
+        self
+    }
+
+    /// Sends an event that systems receive in the next frame, like input from a window would be
+    ///
+    /// # Panics
+    /// Panics if the event type was not added with [AppBuilder::add_event].
+    pub fn send_event<T: Resource>(&mut self, event: T) -> &mut Self {
+        self.app
+            .resources
+            .get_mut::<Events<T>>()
+            .unwrap_or_else(|| panic!("Event does not exist: {}", std::any::type_name::<T>()))
+            .send(event);
+        self
+    }
+
+    /// Returns the events of type `T` that were sent since the last call to this method. Events are only kept for two
+    /// frames, so events sent before that are missed.
+    ///
+    /// # Panics
+    /// Panics if the event type was not added with [AppBuilder::add_event].
+    pub fn read_events<T: Resource + Clone>(&mut self) -> Vec<T> {
+        let events = self
+            .app
+            .resources
+            .get::<Events<T>>()
+            .unwrap_or_else(|| panic!("Event does not exist: {}", std::any::type_name::<T>()));
+        let reader = self
+            .readers
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(EventReader::<T>::default()))
+            .downcast_mut::<EventReader<T>>()
+            .unwrap();
+        reader.iter(&events).cloned().collect()
+    }
+
+    /// Returns true if a system asked the app to exit by sending [AppExit]
+    pub fn exited(&mut self) -> bool {
+        !self.read_events::<AppExit>().is_empty()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.app.resources
+    }
+
+    /// # Panics
+    /// Panics if the resource does not exist.
+    pub fn resource<T: Resource>(&self) -> Ref<'_, T> {
+        self.app
+            .resources
+            .get::<T>()
+            .unwrap_or_else(|| panic!("Resource does not exist: {}", std::any::type_name::<T>()))
+    }
+
+    /// # Panics
+    /// Panics if the resource does not exist.
+    pub fn resource_mut<T: Resource>(&self) -> RefMut<'_, T> {
+        self.app
+            .resources
+            .get_mut::<T>()
+            .unwrap_or_else(|| panic!("Resource does not exist: {}", std::any::type_name::<T>()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SimulatedTime;
+    use crate::{
+        app::{App, AppExit},
+        event::{EventReader, Events},
+        stage,
+    };
+    use bevy_ecs::{Added, Commands, Entity, IntoQuerySystem, Local, Query, Res, ResMut};
+    use std::time::Duration;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Spawn(u32);
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Spawned(Entity);
+
+    struct Elapsed(Duration);
+
+    fn spawn_system(
+        mut commands: Commands,
+        mut reader: Local<EventReader<Spawn>>,
+        spawns: Res<Events<Spawn>>,
+    ) {
+        for spawn in reader.iter(&spawns) {
+            commands.spawn((spawn.0,));
+        }
+    }
+
+    fn report_system(mut spawned: ResMut<Events<Spawned>>, mut query: Query<(Entity, Added<u32>)>) {
+        for (entity, value) in &mut query.iter() {
+            if *value == 0 {
+                spawned.send(Spawned(entity));
+            }
+        }
+    }
+
+    fn exit_system(
+        simulated_time: Res<SimulatedTime>,
+        mut elapsed: ResMut<Elapsed>,
+        mut exit: ResMut<Events<AppExit>>,
+    ) {
+        elapsed.0 += simulated_time.delta.unwrap();
+        if elapsed.0 >= Duration::from_secs(1) {
+            exit.send(AppExit);
+        }
+    }
+
+    #[test]
+    fn test_app() {
+        let mut app = App::build()
+            .add_event::<Spawn>()
+            .add_event::<Spawned>()
+            .add_resource(Elapsed(Duration::default()))
+            .add_system(spawn_system.system())
+            .add_system_to_stage(stage::POST_UPDATE, report_system.system())
+            .add_system_to_stage(stage::LAST, exit_system.system())
+            .test_app(Duration::from_millis(250));
+
+        app.send_event(Spawn(0)).send_event(Spawn(1)).update();
+        let spawned = app.read_events::<Spawned>();
+        assert_eq!(spawned.len(), 1);
+        assert_eq!(*app.world().get::<u32>(spawned[0].0).unwrap(), 0);
+        assert_eq!(app.world().query::<&u32>().iter().count(), 2);
+
+        app.step(2);
+        assert!(!app.exited());
+        app.set_frame_delta(Duration::from_millis(500)).update();
+        assert!(app.exited());
+        assert_eq!(app.resource::<Elapsed>().0, Duration::from_millis(1250));
+    }
+
+    #[test]
+    #[should_panic(expected = "Event does not exist")]
+    fn missing_event() {
+        App::build()
+            .test_app(Duration::from_millis(16))
+            .send_event(Spawn(0));
+    }
+}
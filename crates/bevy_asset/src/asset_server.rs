@@ -1,14 +1,13 @@
 use crate::{
-    filesystem_watcher::FilesystemWatcher, AssetLoadError, AssetLoadRequestHandler, AssetLoader,
-    Assets, Handle, HandleId, LoadRequest,
+    AssetIo, AssetIoError, AssetLoadError, AssetLoadRequestHandler, AssetLoader, Assets,
+    FileAssetIo, Handle, HandleId, LoadRequest,
 };
 use anyhow::Result;
 use bevy_ecs::{Res, Resource, Resources};
-use bevy_utils::{HashMap, HashSet};
-use crossbeam_channel::TryRecvError;
+use bevy_utils::HashMap;
 use parking_lot::RwLock;
 use std::{
-    env, fs, io,
+    io,
     path::{Path, PathBuf},
     sync::Arc,
     thread,
@@ -38,6 +37,19 @@ pub enum AssetServerError {
     AssetWatchError { path: PathBuf },
 }
 
+impl From<AssetIoError> for AssetServerError {
+    fn from(error: AssetIoError) -> Self {
+        match error {
+            AssetIoError::NotFound(path) => AssetServerError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{}", path.display()),
+            )),
+            AssetIoError::Io(error) => AssetServerError::Io(error),
+            AssetIoError::PathWatchError(path) => AssetServerError::AssetWatchError { path },
+        }
+    }
+}
+
 struct LoaderThread {
     // NOTE: these must remain private. the LoaderThread Arc counters are used to determine thread liveness
     // if there is one reference, the loader thread is dead. if there are two references, the loader thread is active
@@ -70,8 +82,9 @@ impl LoadState {
     }
 }
 
-/// Loads assets from the filesystem on background threads
+/// Loads assets on background threads. Assets are read through an [AssetIo], which is a [FileAssetIo] by default.
 pub struct AssetServer {
+    asset_io: Arc<dyn AssetIo>,
     asset_folders: RwLock<Vec<PathBuf>>,
     loader_threads: RwLock<Vec<LoaderThread>>,
     max_loader_threads: usize,
@@ -82,15 +95,18 @@ pub struct AssetServer {
     extension_to_loader_index: HashMap<String, usize>,
     asset_info: RwLock<HashMap<HandleId, AssetInfo>>,
     asset_info_paths: RwLock<HashMap<PathBuf, HandleId>>,
-    #[cfg(feature = "filesystem_watcher")]
-    filesystem_watcher: Arc<RwLock<Option<FilesystemWatcher>>>,
 }
 
 impl Default for AssetServer {
     fn default() -> Self {
+        AssetServer::new(FileAssetIo::default())
+    }
+}
+
+impl AssetServer {
+    pub fn new<T: AssetIo>(asset_io: T) -> Self {
         AssetServer {
-            #[cfg(feature = "filesystem_watcher")]
-            filesystem_watcher: Arc::new(RwLock::new(None)),
+            asset_io: Arc::new(asset_io),
             max_loader_threads: 4,
             asset_folders: Default::default(),
             loader_threads: Default::default(),
@@ -102,9 +118,19 @@ impl Default for AssetServer {
             asset_info: Default::default(),
         }
     }
-}
 
-impl AssetServer {
+    /// Replaces the [AssetIo] that assets are read through. Assets that are already being loaded are still read
+    /// through the previous one.
+    pub fn set_asset_io<T: AssetIo>(&mut self, asset_io: T) {
+        self.asset_io = Arc::new(asset_io);
+        // new requests go to new loader threads, which use the new AssetIo
+        self.loader_threads.write().clear();
+    }
+
+    pub fn asset_io(&self) -> &dyn AssetIo {
+        &*self.asset_io
+    }
+
     pub fn add_handler<T>(&mut self, asset_handler: T)
     where
         T: AssetLoadRequestHandler,
@@ -135,14 +161,15 @@ impl AssetServer {
         self.loaders.push(resources);
     }
 
+    /// Loads the assets in the folder at `path` and its subfolders. The path is resolved by the [AssetIo], so with a
+    /// [FileAssetIo] relative paths start at the current working directory.
     pub fn load_asset_folder<P: AsRef<Path>>(
         &self,
         path: P,
     ) -> Result<Vec<HandleId>, AssetServerError> {
-        let root_path = self.get_root_path()?;
-        let asset_folder = root_path.join(path);
-        let handle_ids = self.load_assets_in_folder_recursive(&asset_folder)?;
-        self.asset_folders.write().push(asset_folder);
+        let asset_folder = path.as_ref();
+        let handle_ids = self.load_assets_in_folder_recursive(asset_folder)?;
+        self.asset_folders.write().push(asset_folder.to_owned());
         Ok(handle_ids)
     }
 
@@ -153,86 +180,26 @@ impl AssetServer {
             .map(|handle_id| Handle::from(*handle_id))
     }
 
-    #[cfg(feature = "filesystem_watcher")]
-    fn watch_path_for_changes<P: AsRef<Path>>(
-        filesystem_watcher: &mut Option<FilesystemWatcher>,
-        path: P,
-    ) -> Result<(), AssetServerError> {
-        if let Some(watcher) = filesystem_watcher {
-            watcher
-                .watch(&path)
-                .map_err(|_error| AssetServerError::AssetWatchError {
-                    path: path.as_ref().to_owned(),
-                })?;
-        }
-
-        Ok(())
-    }
-
-    #[cfg(feature = "filesystem_watcher")]
+    /// Reloads assets when they change. Requires an [AssetIo] that supports watching, like a [FileAssetIo] with the
+    /// `filesystem_watcher` feature.
     pub fn watch_for_changes(&self) -> Result<(), AssetServerError> {
-        let mut filesystem_watcher = self.filesystem_watcher.write();
-
-        let _ = filesystem_watcher.get_or_insert_with(FilesystemWatcher::default);
+        self.asset_io.watch_for_changes()?;
         // watch current files
         let asset_info_paths = self.asset_info_paths.read();
         for asset_path in asset_info_paths.keys() {
-            Self::watch_path_for_changes(&mut filesystem_watcher, asset_path)?;
+            self.asset_io.watch_path_for_changes(asset_path)?;
         }
 
         Ok(())
     }
 
-    #[cfg(feature = "filesystem_watcher")]
-    pub fn filesystem_watcher_system(asset_server: Res<AssetServer>) {
-        let mut changed = HashSet::default();
-
-        loop {
-            let result = {
-                let rwlock_guard = asset_server.filesystem_watcher.read();
-                if let Some(filesystem_watcher) = rwlock_guard.as_ref() {
-                    filesystem_watcher.receiver.try_recv()
-                } else {
-                    break;
-                }
-            };
-            let event = match result {
-                Ok(result) => result.unwrap(),
-                Err(TryRecvError::Empty) => break,
-                Err(TryRecvError::Disconnected) => panic!("FilesystemWatcher disconnected"),
-            };
-            if let notify::event::Event {
-                kind: notify::event::EventKind::Modify(_),
-                paths,
-                ..
-            } = event
-            {
-                for path in paths.iter() {
-                    if !changed.contains(path) {
-                        let root_path = asset_server.get_root_path().unwrap();
-                        let relative_path = path.strip_prefix(root_path).unwrap();
-                        match asset_server.load_untyped(relative_path) {
-                            Ok(_) => {}
-                            Err(AssetServerError::AssetLoadError(error)) => panic!("{:?}", error),
-                            Err(_) => {}
-                        }
-                    }
-                }
-                changed.extend(paths);
-            }
-        }
-    }
-
-    fn get_root_path(&self) -> Result<PathBuf, AssetServerError> {
-        if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
-            Ok(PathBuf::from(manifest_dir))
-        } else {
-            match env::current_exe() {
-                Ok(exe_path) => exe_path
-                    .parent()
-                    .ok_or(AssetServerError::InvalidRootPath)
-                    .map(|exe_parent_path| exe_parent_path.to_owned()),
-                Err(err) => Err(AssetServerError::Io(err)),
+    /// Reloads the assets that the [AssetIo] reports as changed
+    pub fn changed_assets_system(asset_server: Res<AssetServer>) {
+        for path in asset_server.asset_io.changed_paths() {
+            match asset_server.load_untyped(&path) {
+                Ok(_) => {}
+                Err(AssetServerError::AssetLoadError(error)) => panic!("{:?}", error),
+                Err(_) => {}
             }
         }
     }
@@ -260,7 +227,7 @@ impl AssetServer {
                 let handle_id = HandleId::new();
                 let resources = &self.loaders[*index];
                 let loader = resources.get::<Box<dyn AssetLoader<T>>>().unwrap();
-                let asset = loader.load_from_file(path)?;
+                let asset = loader.load_from_asset_io(&*self.asset_io, path)?;
                 let handle = Handle::from(handle_id);
                 assets.set(handle, asset);
                 Ok(handle)
@@ -318,10 +285,7 @@ impl AssetServer {
                     version: new_version,
                 });
 
-                // TODO: watching each asset explicitly is a simpler implementation, its possible it would be more efficient to watch
-                // folders instead (when possible)
-                #[cfg(feature = "filesystem_watcher")]
-                Self::watch_path_for_changes(&mut self.filesystem_watcher.write(), path)?;
+                self.asset_io.watch_path_for_changes(path)?;
                 Ok(handle_id)
             } else {
                 Err(AssetServerError::MissingAssetHandler)
@@ -375,7 +339,7 @@ impl AssetServer {
             };
             let requests = loader_thread.requests.clone();
             loader_threads.push(loader_thread);
-            Self::start_thread(self.asset_handlers.clone(), requests);
+            Self::start_thread(self.asset_handlers.clone(), self.asset_io.clone(), requests);
         } else {
             let most_free_thread = loader_threads
                 .iter()
@@ -387,6 +351,7 @@ impl AssetServer {
             if Arc::strong_count(&most_free_thread.requests) == 1 {
                 Self::start_thread(
                     self.asset_handlers.clone(),
+                    self.asset_io.clone(),
                     most_free_thread.requests.clone(),
                 );
             }
@@ -395,6 +360,7 @@ impl AssetServer {
 
     fn start_thread(
         request_handlers: Arc<RwLock<Vec<Box<dyn AssetLoadRequestHandler>>>>,
+        asset_io: Arc<dyn AssetIo>,
         requests: Arc<RwLock<Vec<LoadRequest>>>,
     ) {
         thread::spawn(move || {
@@ -411,7 +377,7 @@ impl AssetServer {
 
                 let handlers = request_handlers.read();
                 let request_handler = &handlers[request.handler_index];
-                request_handler.handle_request(&request);
+                request_handler.handle_request(&request, &*asset_io);
             }
         });
     }
@@ -420,26 +386,18 @@ impl AssetServer {
         &self,
         path: &Path,
     ) -> Result<Vec<HandleId>, AssetServerError> {
-        if !path.is_dir() {
+        if !self.asset_io.is_directory(path) {
             return Err(AssetServerError::AssetFolderNotADirectory(
                 path.to_str().unwrap().to_string(),
             ));
         }
 
-        let root_path = self.get_root_path()?;
         let mut handle_ids = Vec::new();
-        for entry in fs::read_dir(path)? {
-            let entry = entry?;
-            let child_path = entry.path();
-            if child_path.is_dir() {
+        for child_path in self.asset_io.read_directory(path)? {
+            if self.asset_io.is_directory(&child_path) {
                 handle_ids.extend(self.load_assets_in_folder_recursive(&child_path)?);
             } else {
-                let relative_child_path = child_path.strip_prefix(&root_path).unwrap();
-                let handle = match self.load_untyped(
-                    relative_child_path
-                        .to_str()
-                        .expect("Path should be a valid string"),
-                ) {
+                let handle = match self.load_untyped(&child_path) {
                     Ok(handle) => handle,
                     Err(AssetServerError::MissingAssetHandler) => continue,
                     Err(err) => return Err(err),
@@ -452,3 +410,162 @@ impl AssetServer {
         Ok(handle_ids)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetServer, AssetServerError};
+    use crate::{
+        AssetChannel, AssetIoError, AssetLoader, AssetResult, Assets, ChannelAssetHandler,
+        MemoryAssetIo,
+    };
+    use bevy_ecs::{IntoQuerySystem, Resources, Schedule, World};
+    use std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    #[derive(Default)]
+    struct TextLoader;
+
+    impl AssetLoader<String> for TextLoader {
+        fn from_bytes(&self, _asset_path: &Path, bytes: Vec<u8>) -> anyhow::Result<String> {
+            Ok(String::from_utf8(bytes)?)
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+    }
+
+    fn asset_server(asset_io: &MemoryAssetIo) -> (AssetServer, AssetChannel<String>) {
+        let mut asset_server = AssetServer::new(asset_io.clone());
+        let channel = AssetChannel::<String>::new();
+        asset_server.add_loader(TextLoader);
+        asset_server.add_handler(ChannelAssetHandler::new(TextLoader, channel.sender.clone()));
+        (asset_server, channel)
+    }
+
+    fn receive(channel: &AssetChannel<String>) -> AssetResult<String> {
+        channel
+            .receiver
+            .recv_timeout(Duration::from_secs(10))
+            .expect("asset should have been loaded")
+    }
+
+    #[test]
+    fn load() {
+        let asset_io = MemoryAssetIo::default();
+        asset_io.insert("assets/greeting.txt", "hello");
+        let (asset_server, channel) = asset_server(&asset_io);
+
+        let handle = asset_server
+            .load::<String, _>("assets/greeting.txt")
+            .unwrap();
+        let result = receive(&channel);
+        assert_eq!(result.handle, handle);
+        assert_eq!(result.path, Path::new("assets/greeting.txt"));
+        assert_eq!(result.result.unwrap(), "hello");
+        assert_eq!(
+            asset_server.get_handle::<String, _>("assets/greeting.txt"),
+            Some(handle)
+        );
+
+        assert!(matches!(
+            asset_server.load::<String, _>("assets/greeting.png"),
+            Err(AssetServerError::MissingAssetHandler)
+        ));
+        asset_server
+            .load::<String, _>("assets/missing.txt")
+            .unwrap();
+        assert!(receive(&channel).result.is_err());
+    }
+
+    #[test]
+    fn load_sync() {
+        let asset_io = MemoryAssetIo::default();
+        asset_io.insert("assets/greeting.txt", "hello");
+        let (asset_server, _channel) = asset_server(&asset_io);
+
+        let mut assets = Assets::<String>::default();
+        let handle = asset_server
+            .load_sync(&mut assets, "assets/greeting.txt")
+            .unwrap();
+        assert_eq!(assets.get(&handle).unwrap(), "hello");
+        assert!(matches!(
+            asset_server.load_sync(&mut assets, "assets/missing.txt"),
+            Err(AssetServerError::AssetLoadError(_))
+        ));
+    }
+
+    #[test]
+    fn load_asset_folder() {
+        let asset_io = MemoryAssetIo::default();
+        asset_io.insert("assets/a.txt", "a");
+        asset_io.insert("assets/nested/b.txt", "b");
+        asset_io.insert("assets/nested/ignored.bin", vec![0u8]);
+        let (asset_server, channel) = asset_server(&asset_io);
+
+        let handle_ids = asset_server.load_asset_folder("assets").unwrap();
+        assert_eq!(handle_ids.len(), 2);
+        let mut loaded = (0..2)
+            .map(|_| {
+                let result = receive(&channel);
+                (result.path, result.result.unwrap())
+            })
+            .collect::<Vec<_>>();
+        loaded.sort();
+        assert_eq!(
+            loaded,
+            vec![
+                (PathBuf::from("assets/a.txt"), "a".to_string()),
+                (PathBuf::from("assets/nested/b.txt"), "b".to_string()),
+            ]
+        );
+
+        assert!(matches!(
+            asset_server.load_asset_folder("assets/a.txt"),
+            Err(AssetServerError::AssetFolderNotADirectory(_))
+        ));
+    }
+
+    #[test]
+    fn reload_changed_assets() {
+        let asset_io = MemoryAssetIo::default();
+        asset_io.insert("assets/greeting.txt", "hello");
+        let (asset_server, channel) = asset_server(&asset_io);
+        let handle = asset_server
+            .load::<String, _>("assets/greeting.txt")
+            .unwrap();
+        assert_eq!(receive(&channel).result.unwrap(), "hello");
+        asset_server.watch_for_changes().unwrap();
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(asset_server);
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", AssetServer::changed_assets_system.system());
+
+        // unchanged assets are not reloaded
+        schedule.run(&mut world, &mut resources);
+        assert!(channel.receiver.is_empty());
+
+        asset_io.insert("assets/greeting.txt", "bye");
+        schedule.run(&mut world, &mut resources);
+        let result = receive(&channel);
+        assert_eq!(result.handle, handle);
+        assert_eq!(result.result.unwrap(), "bye");
+    }
+
+    #[test]
+    fn asset_io_errors() {
+        let error = AssetServerError::from(AssetIoError::PathWatchError(PathBuf::from("assets")));
+        assert!(
+            matches!(error, AssetServerError::AssetWatchError { path } if path == Path::new("assets"))
+        );
+        let error = AssetServerError::from(AssetIoError::NotFound(PathBuf::from("assets")));
+        assert!(
+            matches!(error, AssetServerError::Io(error) if error.kind() == std::io::ErrorKind::NotFound)
+        );
+    }
+}
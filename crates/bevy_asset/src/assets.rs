@@ -1,6 +1,6 @@
 use crate::{
-    update_asset_storage_system, AssetChannel, AssetLoader, AssetServer, ChannelAssetHandler,
-    Handle, HandleId,
+    update_asset_storage_system, AssetChannel, AssetIo, AssetLoader, AssetServer,
+    ChannelAssetHandler, Handle, HandleId,
 };
 use bevy_app::{prelude::Events, AppBuilder};
 use bevy_ecs::{FromResources, IntoQuerySystem, ResMut, Resource};
@@ -119,6 +119,9 @@ pub trait AddAsset {
     where
         TLoader: AssetLoader<TAsset> + FromResources,
         TAsset: Send + Sync + 'static;
+    /// Makes the [AssetServer] read assets through `asset_io`, for example to load them from an archive or from memory.
+    /// Call it before loading any assets.
+    fn set_asset_io<T: AssetIo>(&mut self, asset_io: T) -> &mut Self;
 }
 
 impl AddAsset for AppBuilder {
@@ -165,4 +168,12 @@ impl AddAsset for AppBuilder {
         }
         self
     }
+
+    fn set_asset_io<T: AssetIo>(&mut self, asset_io: T) -> &mut Self {
+        self.resources()
+            .get_mut::<AssetServer>()
+            .expect("AssetServer does not exist. Consider adding it as a resource.")
+            .set_asset_io(asset_io);
+        self
+    }
 }
@@ -0,0 +1,170 @@
+#[cfg(feature = "filesystem_watcher")]
+use crate::filesystem_watcher::FilesystemWatcher;
+use crate::{AssetIo, AssetIoError};
+#[cfg(feature = "filesystem_watcher")]
+use parking_lot::RwLock;
+#[cfg(feature = "filesystem_watcher")]
+use std::env;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Reads assets from the filesystem. Paths are relative to a root folder. The default root is the current working
+/// directory, so relative paths are resolved like [std::fs] resolves them.
+///
+/// Watching for changes requires the `filesystem_watcher` feature.
+pub struct FileAssetIo {
+    root_path: PathBuf,
+    #[cfg(feature = "filesystem_watcher")]
+    filesystem_watcher: RwLock<Option<FilesystemWatcher>>,
+}
+
+impl Default for FileAssetIo {
+    fn default() -> Self {
+        FileAssetIo::new(PathBuf::new())
+    }
+}
+
+impl FileAssetIo {
+    pub fn new<P: AsRef<Path>>(root_path: P) -> Self {
+        FileAssetIo {
+            root_path: root_path.as_ref().to_owned(),
+            #[cfg(feature = "filesystem_watcher")]
+            filesystem_watcher: RwLock::new(None),
+        }
+    }
+
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
+    /// Returns `path` relative to the root folder. The watcher reports absolute paths, which start with the current
+    /// working directory when the root folder is relative.
+    #[cfg(feature = "filesystem_watcher")]
+    fn relative_path(&self, path: &Path) -> PathBuf {
+        let absolute_root_path = env::current_dir()
+            .map(|current_dir| current_dir.join(&self.root_path))
+            .unwrap_or_default();
+        path.strip_prefix(&absolute_root_path)
+            .or_else(|_| path.strip_prefix(&self.root_path))
+            .unwrap_or(path)
+            .to_owned()
+    }
+}
+
+impl AssetIo for FileAssetIo {
+    fn load_path(&self, path: &Path) -> Result<Vec<u8>, AssetIoError> {
+        let full_path = self.root_path.join(path);
+        fs::read(&full_path).map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                AssetIoError::NotFound(full_path)
+            } else {
+                e.into()
+            }
+        })
+    }
+
+    fn read_directory(&self, path: &Path) -> Result<Vec<PathBuf>, AssetIoError> {
+        let mut children = Vec::new();
+        for entry in fs::read_dir(self.root_path.join(path))? {
+            let child_path = entry?.path();
+            children.push(
+                child_path
+                    .strip_prefix(&self.root_path)
+                    .unwrap_or(&child_path)
+                    .to_owned(),
+            );
+        }
+        Ok(children)
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.root_path.join(path).is_dir()
+    }
+
+    #[cfg(feature = "filesystem_watcher")]
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.filesystem_watcher
+            .write()
+            .get_or_insert_with(FilesystemWatcher::default);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "filesystem_watcher"))]
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        Err(AssetIoError::PathWatchError(self.root_path.clone()))
+    }
+
+    // TODO: watching each asset explicitly is a simpler implementation, its possible it would be more efficient to watch
+    // folders instead (when possible)
+    #[cfg(feature = "filesystem_watcher")]
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        if let Some(watcher) = self.filesystem_watcher.write().as_mut() {
+            let full_path = self.root_path.join(path);
+            watcher
+                .watch(&full_path)
+                .map_err(|_error| AssetIoError::PathWatchError(full_path))?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "filesystem_watcher"))]
+    fn watch_path_for_changes(&self, _path: &Path) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+
+    #[cfg(feature = "filesystem_watcher")]
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        let filesystem_watcher = self.filesystem_watcher.read();
+        let filesystem_watcher = match filesystem_watcher.as_ref() {
+            Some(filesystem_watcher) => filesystem_watcher,
+            None => return changed,
+        };
+        for event in filesystem_watcher.receiver.try_iter() {
+            if let notify::event::Event {
+                kind: notify::event::EventKind::Modify(_),
+                paths,
+                ..
+            } = event.unwrap()
+            {
+                for path in paths {
+                    let relative_path = self.relative_path(&path);
+                    if !changed.contains(&relative_path) {
+                        changed.push(relative_path);
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    #[cfg(not(feature = "filesystem_watcher"))]
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileAssetIo;
+    use crate::AssetIo;
+    use std::path::Path;
+
+    #[test]
+    fn relative_to_current_dir() {
+        // tests run in the folder of the crate
+        let asset_io = FileAssetIo::default();
+        let bytes = asset_io.load_path(Path::new("Cargo.toml")).unwrap();
+        assert!(String::from_utf8(bytes)
+            .unwrap()
+            .contains("name = \"bevy_asset\""));
+        assert!(asset_io.is_directory(Path::new("src")));
+        assert!(asset_io
+            .read_directory(Path::new("src/io"))
+            .unwrap()
+            .contains(&Path::new("src/io/mod.rs").to_owned()));
+    }
+}
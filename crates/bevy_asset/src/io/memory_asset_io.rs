@@ -0,0 +1,157 @@
+use crate::{is_directory_from_files, read_directory_from_files, AssetIo, AssetIoError};
+use bevy_utils::{HashMap, HashSet};
+use parking_lot::RwLock;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[derive(Default)]
+struct MemoryAssetIoState {
+    files: HashMap<PathBuf, Vec<u8>>,
+    watching: bool,
+    watched_paths: HashSet<PathBuf>,
+    changed_paths: Vec<PathBuf>,
+}
+
+/// Serves assets from memory, which lets tests load assets without touching the filesystem. Clones share the same
+/// files, so a test can keep a clone to add or change files after handing one to an [AssetServer](crate::AssetServer).
+/// Changing a watched file reports it as changed, like editing a file on disk would.
+///
+/// # Example
+/// ```
+/// # use bevy_asset::{AssetIo, MemoryAssetIo};
+/// # use std::path::Path;
+/// let asset_io = MemoryAssetIo::default();
+/// asset_io.insert("assets/greeting.txt", "hello");
+/// assert_eq!(asset_io.load_path(Path::new("assets/greeting.txt")).unwrap(), b"hello");
+/// assert!(asset_io.is_directory(Path::new("assets")));
+/// ```
+#[derive(Clone, Default)]
+pub struct MemoryAssetIo {
+    state: Arc<RwLock<MemoryAssetIoState>>,
+}
+
+impl MemoryAssetIo {
+    /// Adds or replaces the file at `path`
+    pub fn insert<P: Into<PathBuf>, B: Into<Vec<u8>>>(&self, path: P, bytes: B) {
+        let path = path.into();
+        let mut state = self.state.write();
+        if state.watching && state.watched_paths.contains(&path) {
+            state.changed_paths.push(path.clone());
+        }
+        state.files.insert(path, bytes.into());
+    }
+
+    pub fn remove<P: AsRef<Path>>(&self, path: P) -> Option<Vec<u8>> {
+        self.state.write().files.remove(path.as_ref())
+    }
+}
+
+impl AssetIo for MemoryAssetIo {
+    fn load_path(&self, path: &Path) -> Result<Vec<u8>, AssetIoError> {
+        self.state
+            .read()
+            .files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))
+    }
+
+    fn read_directory(&self, path: &Path) -> Result<Vec<PathBuf>, AssetIoError> {
+        if !self.is_directory(path) {
+            return Err(AssetIoError::NotFound(path.to_owned()));
+        }
+        let state = self.state.read();
+        Ok(read_directory_from_files(
+            state.files.keys().map(|file| file.as_path()),
+            path,
+        ))
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        let state = self.state.read();
+        is_directory_from_files(state.files.keys().map(|file| file.as_path()), path)
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.state.write().watching = true;
+        Ok(())
+    }
+
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        self.state.write().watched_paths.insert(path.to_owned());
+        Ok(())
+    }
+
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.state.write().changed_paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryAssetIo;
+    use crate::{AssetIo, AssetIoError};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn read_directory() {
+        let asset_io = MemoryAssetIo::default();
+        asset_io.insert("assets/b.txt", "b");
+        asset_io.insert("assets/a.txt", "a");
+        asset_io.insert("assets/nested/c.txt", "c");
+        assert_eq!(
+            asset_io.read_directory(Path::new("assets")).unwrap(),
+            vec![
+                PathBuf::from("assets/a.txt"),
+                PathBuf::from("assets/b.txt"),
+                PathBuf::from("assets/nested")
+            ]
+        );
+        assert!(asset_io.is_directory(Path::new("assets/nested")));
+        assert!(!asset_io.is_directory(Path::new("assets/a.txt")));
+        assert!(matches!(
+            asset_io.read_directory(Path::new("missing")),
+            Err(AssetIoError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn remove() {
+        let asset_io = MemoryAssetIo::default();
+        asset_io.insert("assets/a.txt", "a");
+        assert_eq!(asset_io.remove("assets/a.txt"), Some(b"a".to_vec()));
+        assert_eq!(asset_io.remove("assets/a.txt"), None);
+        assert!(matches!(
+            asset_io.load_path(Path::new("assets/a.txt")),
+            Err(AssetIoError::NotFound(_))
+        ));
+        assert!(!asset_io.is_directory(Path::new("assets")));
+    }
+
+    #[test]
+    fn insert_reports_watched_paths_as_changed() {
+        let asset_io = MemoryAssetIo::default();
+        asset_io.insert("assets/a.txt", "a");
+        asset_io
+            .watch_path_for_changes(Path::new("assets/a.txt"))
+            .unwrap();
+        asset_io.insert("assets/a.txt", "changed before watching");
+        assert!(asset_io.changed_paths().is_empty());
+
+        asset_io.watch_for_changes().unwrap();
+        // clones share their files
+        asset_io.clone().insert("assets/a.txt", "changed");
+        asset_io.insert("assets/b.txt", "not watched");
+        assert_eq!(
+            asset_io.changed_paths(),
+            vec![PathBuf::from("assets/a.txt")]
+        );
+        assert!(asset_io.changed_paths().is_empty());
+        assert_eq!(
+            asset_io.load_path(Path::new("assets/a.txt")).unwrap(),
+            b"changed"
+        );
+    }
+}
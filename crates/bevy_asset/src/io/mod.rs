@@ -0,0 +1,79 @@
+mod file_asset_io;
+mod memory_asset_io;
+#[cfg(feature = "zip")]
+mod zip_asset_io;
+
+pub use file_asset_io::*;
+pub use memory_asset_io::*;
+#[cfg(feature = "zip")]
+pub use zip_asset_io::*;
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Errors that occur while reading assets from an [AssetIo]
+#[derive(Error, Debug)]
+pub enum AssetIoError {
+    #[error("Path not found: {0}")]
+    NotFound(PathBuf),
+    #[error("Encountered an io error while reading an asset.")]
+    Io(#[from] io::Error),
+    #[error("Failed to watch path: {0}")]
+    PathWatchError(PathBuf),
+}
+
+/// Reads the bytes of assets for an [AssetServer](crate::AssetServer), lists asset folders and watches assets for
+/// changes. Paths are relative to the root of the assets, for example `"assets/branding/icon.png"`.
+pub trait AssetIo: Send + Sync + 'static {
+    /// Reads the bytes of the asset at `path`
+    fn load_path(&self, path: &Path) -> Result<Vec<u8>, AssetIoError>;
+
+    /// Returns the paths of the files and directories that are directly inside the directory at `path`
+    fn read_directory(&self, path: &Path) -> Result<Vec<PathBuf>, AssetIoError>;
+
+    fn is_directory(&self, path: &Path) -> bool;
+
+    /// Starts watching the paths passed to [AssetIo::watch_path_for_changes]
+    fn watch_for_changes(&self) -> Result<(), AssetIoError>;
+
+    /// Reports changes to the asset at `path` from [AssetIo::changed_paths], once [AssetIo::watch_for_changes] has been
+    /// called
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError>;
+
+    /// Returns the watched paths that changed since this was last called
+    fn changed_paths(&self) -> Vec<PathBuf>;
+}
+
+/// Returns the paths in `paths` that are directly inside the directory at `path`, including the directories that
+/// contain the others. Used by [AssetIo]s that store a flat list of files.
+pub(crate) fn read_directory_from_files<'a>(
+    files: impl Iterator<Item = &'a Path>,
+    path: &Path,
+) -> Vec<PathBuf> {
+    let mut children = Vec::new();
+    for file in files {
+        let relative_path = match file.strip_prefix(path) {
+            Ok(relative_path) => relative_path,
+            Err(_) => continue,
+        };
+        if let Some(name) = relative_path.iter().next() {
+            let child = path.join(name);
+            if !children.contains(&child) {
+                children.push(child);
+            }
+        }
+    }
+    children.sort();
+    children
+}
+
+/// Returns true if one of `files` is inside the directory at `path`
+pub(crate) fn is_directory_from_files<'a>(
+    mut files: impl Iterator<Item = &'a Path>,
+    path: &Path,
+) -> bool {
+    files.any(|file| file != path && file.starts_with(path))
+}
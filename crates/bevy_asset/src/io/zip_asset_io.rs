@@ -0,0 +1,161 @@
+use crate::{is_directory_from_files, read_directory_from_files, AssetIo, AssetIoError};
+use bevy_utils::HashMap;
+use parking_lot::Mutex;
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek},
+    path::{Path, PathBuf},
+};
+use zip::{result::ZipError, ZipArchive};
+
+trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Reads assets from a zip archive, so that a release build can ship all of its assets in a single file. Paths are
+/// relative to the root of the archive. Archives don't change while the app runs, so watching for changes does nothing.
+pub struct ZipAssetIo {
+    archive: Mutex<ZipArchive<Box<dyn ReadSeek>>>,
+    // the names of the files in the archive, by their path
+    files: HashMap<PathBuf, String>,
+}
+
+impl ZipAssetIo {
+    /// Opens the zip archive at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AssetIoError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                AssetIoError::NotFound(path.to_owned())
+            } else {
+                e.into()
+            }
+        })?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Reads a zip archive from `reader`, for example one that is embedded in the executable
+    pub fn from_reader<R: Read + Seek + Send + 'static>(reader: R) -> Result<Self, AssetIoError> {
+        let archive = ZipArchive::new(Box::new(reader) as Box<dyn ReadSeek>)
+            .map_err(|e| AssetIoError::Io(e.into()))?;
+        let files = archive
+            .file_names()
+            .filter(|name| !name.ends_with('/'))
+            .map(|name| (PathBuf::from(name), name.to_string()))
+            .collect();
+        Ok(ZipAssetIo {
+            archive: Mutex::new(archive),
+            files,
+        })
+    }
+}
+
+impl AssetIo for ZipAssetIo {
+    fn load_path(&self, path: &Path) -> Result<Vec<u8>, AssetIoError> {
+        let name = self
+            .files
+            .get(path)
+            .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))?;
+        let mut archive = self.archive.lock();
+        let mut file = archive.by_name(name).map_err(|e| match e {
+            ZipError::FileNotFound => AssetIoError::NotFound(path.to_owned()),
+            e => AssetIoError::Io(e.into()),
+        })?;
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_directory(&self, path: &Path) -> Result<Vec<PathBuf>, AssetIoError> {
+        if !self.is_directory(path) {
+            return Err(AssetIoError::NotFound(path.to_owned()));
+        }
+        Ok(read_directory_from_files(
+            self.files.keys().map(|file| file.as_path()),
+            path,
+        ))
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        is_directory_from_files(self.files.keys().map(|file| file.as_path()), path)
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+
+    fn watch_path_for_changes(&self, _path: &Path) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZipAssetIo;
+    use crate::{AssetIo, AssetIoError, AssetLoader, AssetServer, Assets};
+    use std::{
+        io::{Cursor, Write},
+        path::{Path, PathBuf},
+    };
+    use zip::{write::FileOptions, ZipWriter};
+
+    struct TextLoader;
+
+    impl AssetLoader<String> for TextLoader {
+        fn from_bytes(&self, _asset_path: &Path, bytes: Vec<u8>) -> anyhow::Result<String> {
+            Ok(String::from_utf8(bytes)?)
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+    }
+
+    fn archive(files: &[(&str, &str)]) -> ZipAssetIo {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .add_directory("assets/", FileOptions::default())
+            .unwrap();
+        for (name, contents) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let mut cursor = writer.finish().unwrap();
+        cursor.set_position(0);
+        ZipAssetIo::from_reader(cursor).unwrap()
+    }
+
+    #[test]
+    fn read_archive() {
+        let asset_io = archive(&[("assets/a.txt", "a"), ("assets/nested/b.txt", "b")]);
+        assert_eq!(asset_io.load_path(Path::new("assets/a.txt")).unwrap(), b"a");
+        assert!(matches!(
+            asset_io.load_path(Path::new("assets/missing.txt")),
+            Err(AssetIoError::NotFound(_))
+        ));
+        assert!(asset_io.is_directory(Path::new("assets/nested")));
+        assert!(!asset_io.is_directory(Path::new("assets/a.txt")));
+        assert_eq!(
+            asset_io.read_directory(Path::new("assets")).unwrap(),
+            vec![
+                PathBuf::from("assets/a.txt"),
+                PathBuf::from("assets/nested")
+            ]
+        );
+    }
+
+    #[test]
+    fn load_from_archive() {
+        let mut asset_server = AssetServer::new(archive(&[("assets/nested/b.txt", "b")]));
+        asset_server.add_loader(TextLoader);
+        let mut assets = Assets::<String>::default();
+        let handle = asset_server
+            .load_sync(&mut assets, "assets/nested/b.txt")
+            .unwrap();
+        assert_eq!(assets.get(&handle).unwrap(), "b");
+    }
+}
@@ -3,12 +3,14 @@ mod assets;
 #[cfg(feature = "filesystem_watcher")]
 mod filesystem_watcher;
 mod handle;
+mod io;
 mod load_request;
 mod loader;
 
 pub use asset_server::*;
 pub use assets::*;
 pub use handle::*;
+pub use io::*;
 pub use load_request::*;
 pub use loader::*;
 
@@ -36,12 +38,10 @@ impl Plugin for AssetPlugin {
         app.add_stage_before(bevy_app::stage::PRE_UPDATE, stage::LOAD_ASSETS)
             .add_stage_after(bevy_app::stage::POST_UPDATE, stage::ASSET_EVENTS)
             .init_resource::<AssetServer>()
-            .register_property::<HandleId>();
-
-        #[cfg(feature = "filesystem_watcher")]
-        app.add_system_to_stage(
-            stage::LOAD_ASSETS,
-            AssetServer::filesystem_watcher_system.system(),
-        );
+            .register_property::<HandleId>()
+            .add_system_to_stage(
+                stage::LOAD_ASSETS,
+                AssetServer::changed_assets_system.system(),
+            );
     }
 }
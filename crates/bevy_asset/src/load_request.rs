@@ -1,9 +1,7 @@
-use crate::{AssetLoadError, AssetLoader, AssetResult, AssetVersion, Handle, HandleId};
+use crate::{AssetIo, AssetLoadError, AssetLoader, AssetResult, AssetVersion, Handle, HandleId};
 use anyhow::Result;
 use crossbeam_channel::Sender;
-use fs::File;
-use io::Read;
-use std::{fs, io, path::PathBuf};
+use std::path::PathBuf;
 
 /// A request from an [AssetServer](crate::AssetServer) to load an asset.
 #[derive(Debug)]
@@ -16,7 +14,7 @@ pub struct LoadRequest {
 
 /// Handles load requests from an AssetServer
 pub trait AssetLoadRequestHandler: Send + Sync + 'static {
-    fn handle_request(&self, load_request: &LoadRequest);
+    fn handle_request(&self, load_request: &LoadRequest, asset_io: &dyn AssetIo);
     fn extensions(&self) -> &[&str];
 }
 
@@ -37,19 +35,12 @@ where
         ChannelAssetHandler { sender, loader }
     }
 
-    fn load_asset(&self, load_request: &LoadRequest) -> Result<TAsset, AssetLoadError> {
-        match File::open(&load_request.path) {
-            Ok(mut file) => {
-                let mut bytes = Vec::new();
-                file.read_to_end(&mut bytes)?;
-                let asset = self.loader.from_bytes(&load_request.path, bytes)?;
-                Ok(asset)
-            }
-            Err(e) => Err(AssetLoadError::Io(std::io::Error::new(
-                e.kind(),
-                format!("{}", load_request.path.display()),
-            ))),
-        }
+    fn load_asset(
+        &self,
+        load_request: &LoadRequest,
+        asset_io: &dyn AssetIo,
+    ) -> Result<TAsset, AssetLoadError> {
+        self.loader.load_from_asset_io(asset_io, &load_request.path)
     }
 }
 
@@ -58,8 +49,8 @@ where
     TLoader: AssetLoader<TAsset> + 'static,
     TAsset: Send + 'static,
 {
-    fn handle_request(&self, load_request: &LoadRequest) {
-        let result = self.load_asset(load_request);
+    fn handle_request(&self, load_request: &LoadRequest, asset_io: &dyn AssetIo) {
+        let result = self.load_asset(load_request, asset_io);
         let asset_result = AssetResult {
             handle: Handle::from(load_request.handle_id),
             result,
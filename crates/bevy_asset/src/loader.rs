@@ -1,11 +1,9 @@
-use crate::{AssetServer, AssetVersion, Assets, Handle, LoadState};
+use crate::{AssetIo, AssetIoError, AssetServer, AssetVersion, Assets, Handle, LoadState};
 use anyhow::Result;
 use bevy_ecs::{Res, ResMut, Resource};
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
-use fs::File;
-use io::Read;
 use std::{
-    fs, io,
+    io,
     path::{Path, PathBuf},
 };
 use thiserror::Error;
@@ -15,6 +13,8 @@ use thiserror::Error;
 pub enum AssetLoadError {
     #[error("Encountered an io error while loading asset.")]
     Io(#[from] io::Error),
+    #[error("Failed to read the asset.")]
+    AssetIoError(#[from] AssetIoError),
     #[error("This asset's loader encountered an error while loading.")]
     LoaderError(#[from] anyhow::Error),
 }
@@ -23,10 +23,12 @@ pub enum AssetLoadError {
 pub trait AssetLoader<T>: Send + Sync + 'static {
     fn from_bytes(&self, asset_path: &Path, bytes: Vec<u8>) -> Result<T, anyhow::Error>;
     fn extensions(&self) -> &[&str];
-    fn load_from_file(&self, asset_path: &Path) -> Result<T, AssetLoadError> {
-        let mut file = File::open(asset_path)?;
-        let mut bytes = Vec::new();
-        file.read_to_end(&mut bytes)?;
+    fn load_from_asset_io(
+        &self,
+        asset_io: &dyn AssetIo,
+        asset_path: &Path,
+    ) -> Result<T, AssetLoadError> {
+        let bytes = asset_io.load_path(asset_path)?;
         let asset = self.from_bytes(asset_path, bytes)?;
         Ok(asset)
     }
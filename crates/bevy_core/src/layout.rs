@@ -0,0 +1,332 @@
+use crate::Bytes;
+use bevy_math::{Mat3, Mat4, Vec2, Vec3, Vec4};
+
+pub use bevy_derive::LayoutBytes;
+
+/// A GLSL memory layout for data in shader buffers
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BufferLayout {
+    /// The layout of uniform blocks. Arrays and structs are aligned to 16 bytes.
+    Std140,
+    /// The layout of storage blocks, which packs arrays and structs more tightly than [BufferLayout::Std140]
+    Std430,
+}
+
+/// Writes the implementing type to a buffer using a [BufferLayout], inserting the padding that shaders expect between
+/// fields. Derive it for structs whose fields implement [LayoutBytes] to lay them out like the matching GLSL struct.
+pub trait LayoutBytes {
+    /// The alignment of the type in `layout`
+    fn layout_alignment(layout: BufferLayout) -> usize;
+
+    /// The number of bytes that will be written when calling `write_layout_bytes`
+    fn layout_size(layout: BufferLayout) -> usize;
+
+    /// Writes the type to the start of `buffer` using `layout`
+    fn write_layout_bytes(&self, layout: BufferLayout, buffer: &mut [u8]);
+}
+
+fn round_up(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
+
+/// Computes the offsets of the fields of a struct in a [BufferLayout]. Used by the [LayoutBytes] derive.
+#[derive(Debug, Clone)]
+pub struct StructLayout {
+    layout: BufferLayout,
+    offset: usize,
+    alignment: usize,
+}
+
+impl StructLayout {
+    pub fn new(layout: BufferLayout) -> Self {
+        StructLayout {
+            layout,
+            offset: 0,
+            alignment: match layout {
+                BufferLayout::Std140 => 16,
+                BufferLayout::Std430 => 1,
+            },
+        }
+    }
+
+    /// Adds a field of type `T` after the previous fields and returns its offset
+    pub fn add_field<T: LayoutBytes>(&mut self) -> usize {
+        let alignment = T::layout_alignment(self.layout);
+        let offset = round_up(self.offset, alignment);
+        self.offset = offset + T::layout_size(self.layout);
+        self.alignment = self.alignment.max(alignment);
+        offset
+    }
+
+    /// Zeroes the padding before the next field, then writes `value` as that field
+    pub fn write_field<T: LayoutBytes>(&mut self, value: &T, buffer: &mut [u8]) {
+        let end = self.offset;
+        let offset = self.add_field::<T>();
+        zero(&mut buffer[end..offset]);
+        value.write_layout_bytes(self.layout, &mut buffer[offset..self.offset]);
+    }
+
+    /// Zeroes the padding after the last field
+    pub fn finish(&self, buffer: &mut [u8]) {
+        zero(&mut buffer[self.offset..self.size()]);
+    }
+
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// The size of the struct, including the padding after its last field
+    pub fn size(&self) -> usize {
+        round_up(self.offset, self.alignment)
+    }
+}
+
+fn zero(buffer: &mut [u8]) {
+    for byte in buffer.iter_mut() {
+        *byte = 0;
+    }
+}
+
+macro_rules! impl_layout_bytes {
+    ($ty:ty, $alignment:expr) => {
+        impl LayoutBytes for $ty {
+            fn layout_alignment(_layout: BufferLayout) -> usize {
+                $alignment
+            }
+
+            fn layout_size(_layout: BufferLayout) -> usize {
+                std::mem::size_of::<$ty>()
+            }
+
+            fn write_layout_bytes(&self, layout: BufferLayout, buffer: &mut [u8]) {
+                self.write_bytes(&mut buffer[..Self::layout_size(layout)]);
+            }
+        }
+    };
+}
+
+impl_layout_bytes!(f32, 4);
+impl_layout_bytes!(u32, 4);
+impl_layout_bytes!(i32, 4);
+impl_layout_bytes!(f64, 8);
+impl_layout_bytes!(Vec2, 8);
+impl_layout_bytes!(Vec4, 16);
+
+// NOTE: Vec3 takes up 16 bytes in memory, but only 12 bytes in shader buffers. A following scalar fills the rest.
+impl LayoutBytes for Vec3 {
+    fn layout_alignment(_layout: BufferLayout) -> usize {
+        16
+    }
+
+    fn layout_size(_layout: BufferLayout) -> usize {
+        12
+    }
+
+    fn write_layout_bytes(&self, _layout: BufferLayout, buffer: &mut [u8]) {
+        let array: [f32; 3] = (*self).into();
+        array.write_bytes(&mut buffer[..12]);
+    }
+}
+
+// Matrices are laid out like arrays of their columns
+impl LayoutBytes for Mat3 {
+    fn layout_alignment(layout: BufferLayout) -> usize {
+        <[Vec3; 3]>::layout_alignment(layout)
+    }
+
+    fn layout_size(layout: BufferLayout) -> usize {
+        <[Vec3; 3]>::layout_size(layout)
+    }
+
+    fn write_layout_bytes(&self, layout: BufferLayout, buffer: &mut [u8]) {
+        [self.x_axis(), self.y_axis(), self.z_axis()].write_layout_bytes(layout, buffer);
+    }
+}
+
+impl LayoutBytes for Mat4 {
+    fn layout_alignment(layout: BufferLayout) -> usize {
+        <[Vec4; 4]>::layout_alignment(layout)
+    }
+
+    fn layout_size(layout: BufferLayout) -> usize {
+        <[Vec4; 4]>::layout_size(layout)
+    }
+
+    fn write_layout_bytes(&self, layout: BufferLayout, buffer: &mut [u8]) {
+        [self.x_axis(), self.y_axis(), self.z_axis(), self.w_axis()]
+            .write_layout_bytes(layout, buffer);
+    }
+}
+
+impl<T: LayoutBytes, const N: usize> LayoutBytes for [T; N] {
+    fn layout_alignment(layout: BufferLayout) -> usize {
+        match layout {
+            BufferLayout::Std140 => round_up(T::layout_alignment(layout), 16),
+            BufferLayout::Std430 => T::layout_alignment(layout),
+        }
+    }
+
+    fn layout_size(layout: BufferLayout) -> usize {
+        array_stride::<T>(layout) * N
+    }
+
+    fn write_layout_bytes(&self, layout: BufferLayout, buffer: &mut [u8]) {
+        let stride = array_stride::<T>(layout);
+        let size = T::layout_size(layout);
+        for (value, element) in self.iter().zip(buffer.chunks_exact_mut(stride)) {
+            value.write_layout_bytes(layout, &mut element[..size]);
+            zero(&mut element[size..]);
+        }
+    }
+}
+
+/// The distance between the starts of consecutive elements of an array of `T`
+fn array_stride<T: LayoutBytes>(layout: BufferLayout) -> usize {
+    round_up(T::layout_size(layout), <[T; 1]>::layout_alignment(layout))
+}
+
+/// Wraps a value so that its [Bytes] use the [BufferLayout::Std140] layout
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Std140<T>(pub T);
+
+impl<T: LayoutBytes> Bytes for Std140<T> {
+    fn write_bytes(&self, buffer: &mut [u8]) {
+        self.0.write_layout_bytes(BufferLayout::Std140, buffer);
+    }
+
+    fn byte_len(&self) -> usize {
+        T::layout_size(BufferLayout::Std140)
+    }
+}
+
+/// Wraps a value so that its [Bytes] use the [BufferLayout::Std430] layout
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Std430<T>(pub T);
+
+impl<T: LayoutBytes> Bytes for Std430<T> {
+    fn write_bytes(&self, buffer: &mut [u8]) {
+        self.0.write_layout_bytes(BufferLayout::Std430, buffer);
+    }
+
+    fn byte_len(&self) -> usize {
+        T::layout_size(BufferLayout::Std430)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BufferLayout, LayoutBytes, Std140, Std430};
+    use crate::{AsBytes, Bytes};
+    use bevy_math::{Mat4, Vec2, Vec3, Vec4};
+
+    #[derive(LayoutBytes)]
+    #[as_crate(bevy_core)]
+    struct Inner {
+        position: Vec3,
+        intensity: f32,
+        uv: Vec2,
+    }
+
+    #[derive(LayoutBytes)]
+    #[as_crate(bevy_core)]
+    struct Outer {
+        value: f32,
+        inner: Inner,
+        weights: [f32; 2],
+        normal: Vec3,
+        transform: Mat4,
+    }
+
+    #[derive(LayoutBytes)]
+    #[as_crate(bevy_core)]
+    struct Pair<T>
+    where
+        T: LayoutBytes,
+    {
+        first: T,
+        second: f32,
+    }
+
+    fn to_bytes<T: Bytes>(value: T) -> Vec<u8> {
+        let mut bytes = vec![0xff; value.byte_len()];
+        value.write_bytes(&mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn scalar_after_vec3() {
+        // vec3 position; float intensity; vec2 uv;
+        assert_eq!(Inner::layout_alignment(BufferLayout::Std140), 16);
+        assert_eq!(Inner::layout_alignment(BufferLayout::Std430), 16);
+        assert_eq!(Inner::layout_size(BufferLayout::Std140), 32);
+
+        let inner = Inner {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            intensity: 4.0,
+            uv: Vec2::new(5.0, 6.0),
+        };
+        assert_eq!(
+            to_bytes(Std140(inner)),
+            [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0, 0.0, 0.0].as_bytes()
+        );
+    }
+
+    #[test]
+    fn generic_struct() {
+        // vec2 first; float second; padded to the alignment of vec2
+        assert_eq!(Pair::<Vec2>::layout_alignment(BufferLayout::Std430), 8);
+        assert_eq!(Pair::<Vec2>::layout_size(BufferLayout::Std430), 16);
+
+        let pair = Pair {
+            first: Vec2::new(1.0, 2.0),
+            second: 3.0,
+        };
+        assert_eq!(to_bytes(Std430(pair)), [1.0f32, 2.0, 3.0, 0.0].as_bytes());
+    }
+
+    #[test]
+    fn std140_and_std430() {
+        // value at 0, inner at 16, weights at 48 (stride 16 in std140, 4 in std430), normal at 80/64 and transform at
+        // 96/80
+        assert_eq!(Outer::layout_size(BufferLayout::Std140), 160);
+        assert_eq!(Outer::layout_size(BufferLayout::Std430), 144);
+        assert_eq!(<[f32; 2]>::layout_size(BufferLayout::Std140), 32);
+        assert_eq!(<[f32; 2]>::layout_size(BufferLayout::Std430), 8);
+        assert_eq!(<[Vec3; 2]>::layout_size(BufferLayout::Std430), 32);
+
+        let outer = || Outer {
+            value: 1.0,
+            inner: Inner {
+                position: Vec3::new(2.0, 3.0, 4.0),
+                intensity: 5.0,
+                uv: Vec2::new(6.0, 7.0),
+            },
+            weights: [8.0, 9.0],
+            normal: Vec3::new(10.0, 11.0, 12.0),
+            transform: Mat4::from_cols(
+                Vec4::splat(13.0),
+                Vec4::splat(14.0),
+                Vec4::splat(15.0),
+                Vec4::splat(16.0),
+            ),
+        };
+
+        let mut expected = vec![1.0f32, 0.0, 0.0, 0.0];
+        expected.extend_from_slice(&[2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 0.0, 0.0]);
+        expected.extend_from_slice(&[8.0, 0.0, 0.0, 0.0, 9.0, 0.0, 0.0, 0.0]);
+        expected.extend_from_slice(&[10.0, 11.0, 12.0, 0.0]);
+        for column in 13..=16 {
+            expected.extend_from_slice(&[column as f32; 4]);
+        }
+        assert_eq!(to_bytes(Std140(outer())), expected.as_slice().as_bytes());
+
+        let mut expected = vec![1.0f32, 0.0, 0.0, 0.0];
+        expected.extend_from_slice(&[2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 0.0, 0.0]);
+        expected.extend_from_slice(&[8.0, 9.0, 0.0, 0.0]);
+        expected.extend_from_slice(&[10.0, 11.0, 12.0, 0.0]);
+        for column in 13..=16 {
+            expected.extend_from_slice(&[column as f32; 4]);
+        }
+        assert_eq!(to_bytes(Std430(outer())), expected.as_slice().as_bytes());
+    }
+}
@@ -1,21 +1,31 @@
 mod bytes;
 mod float_ord;
 mod label;
+mod layout;
+mod name;
+mod rng;
 mod time;
 
 pub use bytes::*;
 pub use float_ord::*;
 pub use label::*;
+pub use layout::*;
+pub use name::*;
+pub use rng::*;
 pub use time::*;
 
 pub mod prelude {
-    pub use crate::{EntityLabels, Labels, Time, Timer};
+    pub use crate::{
+        Clock, EntityLabels, FixedTimestep, GlobalRng, Labels, Name, Stopwatch, Time, Timer,
+        TimerFinished, VirtualTime,
+    };
 }
 
-use bevy_app::prelude::*;
+use bevy_app::{prelude::*, startup_stage, ReplayPlugin};
 use bevy_ecs::prelude::*;
 use bevy_math::{Mat3, Mat4, Quat, Vec2, Vec3};
 use bevy_type_registry::RegisterType;
+use std::time::Duration;
 
 /// Adds core functionality to Apps.
 #[derive(Default)]
@@ -23,17 +33,28 @@ pub struct CorePlugin;
 
 impl Plugin for CorePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.init_resource::<Time>()
+        app.add_plugin(ReplayPlugin)
+            .init_resource::<Time>()
+            .init_resource::<VirtualTime>()
             .init_resource::<EntityLabels>()
+            .init_resource::<GlobalRng>()
+            .add_event::<TimerFinished>()
             .register_component::<Timer>()
+            .register_component::<Stopwatch>()
+            .register_component::<Name>()
+            .register_property::<Duration>()
+            .register_property::<Clock>()
             .register_property::<Vec2>()
             .register_property::<Vec3>()
             .register_property::<Mat3>()
             .register_property::<Mat4>()
             .register_property::<Quat>()
             .register_property::<Option<String>>()
+            .add_startup_system_to_stage(startup_stage::STARTUP, seed_global_rng_system.system())
             .add_system_to_stage(stage::FIRST, time_system.system())
+            .add_system_to_stage(stage::FIRST, virtual_time_system.system())
             .add_system_to_stage(stage::FIRST, timer_system.system())
+            .add_system_to_stage(stage::FIRST, stopwatch_system.system())
             .add_system_to_stage(stage::PRE_UPDATE, entity_labels_system.system());
     }
 }
@@ -0,0 +1,52 @@
+use bevy_property::Properties;
+use std::{
+    fmt::{Display, Formatter},
+    ops::Deref,
+};
+
+/// The name of an entity. Unlike [Labels](crate::Labels), which group entities, names are used to address a single
+/// entity, for example by its path in a hierarchy of named entities.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Properties)]
+pub struct Name {
+    name: String,
+}
+
+impl Name {
+    pub fn new(name: impl Into<String>) -> Self {
+        Name { name: name.into() }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Display for Name {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl From<&str> for Name {
+    fn from(name: &str) -> Self {
+        Name::new(name)
+    }
+}
+
+impl From<String> for Name {
+    fn from(name: String) -> Self {
+        Name::new(name)
+    }
+}
@@ -0,0 +1,103 @@
+use bevy_app::{Replay, ReplayMode};
+use bevy_ecs::{Res, ResMut};
+use rand::{Error, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The app-wide source of randomness. When an app records or replays its inputs, it is seeded with [Replay::seed] at
+/// startup, so systems that only draw random numbers from it behave the same way every time a recording is replayed.
+///
+/// `GlobalRng` implements [RngCore], so it can be used with the methods of [rand::Rng]. The generator is ChaCha8, whose
+/// output for a given seed does not depend on the platform.
+pub struct GlobalRng(ChaCha8Rng);
+
+impl Default for GlobalRng {
+    fn default() -> Self {
+        GlobalRng(ChaCha8Rng::from_entropy())
+    }
+}
+
+impl GlobalRng {
+    pub fn from_seed(seed: u64) -> Self {
+        GlobalRng(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl RngCore for GlobalRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+pub(crate) fn seed_global_rng_system(replay: Res<Replay>, mut rng: ResMut<GlobalRng>) {
+    if replay.mode() != ReplayMode::Off {
+        *rng = GlobalRng::from_seed(replay.seed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GlobalRng;
+    use crate::{time::time_system, Time};
+    use bevy_app::{App, Replay, ReplayPlugin};
+    use bevy_ecs::{IntoQuerySystem, Res, ResMut, SerialExecutor};
+    use rand::Rng;
+    use std::time::Duration;
+
+    #[derive(Default, Debug, PartialEq)]
+    struct Trace(Vec<(u32, f64)>);
+
+    fn trace_system(mut rng: ResMut<GlobalRng>, time: Res<Time>, mut trace: ResMut<Trace>) {
+        trace
+            .0
+            .push((rng.gen_range(0, 1000), time.seconds_since_startup));
+    }
+
+    fn run(replay: Replay, frames: usize) -> (Replay, Trace) {
+        let mut app = App::build();
+        app.set_executor(SerialExecutor::default())
+            .set_startup_executor(SerialExecutor::without_tracker_clears())
+            .add_resource(replay)
+            .add_plugin(ReplayPlugin)
+            .init_resource::<Time>()
+            .init_resource::<GlobalRng>()
+            .init_resource::<Trace>()
+            .add_startup_system(super::seed_global_rng_system.system())
+            .add_system_to_stage(bevy_app::stage::FIRST, time_system.system())
+            .add_system(trace_system.system());
+        let mut app = std::mem::take(&mut app.app);
+        app.startup();
+        for _ in 0..frames {
+            std::thread::sleep(Duration::from_millis(1));
+            app.update();
+        }
+        let replay = Replay::clone(&app.resources.get::<Replay>().unwrap());
+        let trace = std::mem::take(&mut *app.resources.get_mut::<Trace>().unwrap());
+        (replay, trace)
+    }
+
+    #[test]
+    fn replay_time_and_randomness() {
+        let (recorded, recorded_trace) = run(Replay::record(7), 4);
+        assert_eq!(recorded.time_deltas().len(), 4);
+        assert!(recorded.time_deltas()[1] >= Duration::from_millis(1));
+
+        let (replayed, replayed_trace) = run(
+            Replay::from_recording(recorded.seed(), recorded.time_deltas().to_vec()),
+            4,
+        );
+        assert!(replayed.is_finished());
+        assert_eq!(recorded_trace, replayed_trace);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::time::Time;
+use bevy_ecs::{Resources, RunCriteria, ShouldRun, World};
+
+/// A [RunCriteria] that runs a stage at a fixed rate, independent of the frame rate. Elapsed [Time] is accumulated
+/// each update and the stage runs once for every full `step` in the accumulator, which means it can run zero or
+/// several times per update.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: f64,
+    accumulator: f64,
+    looping: bool,
+}
+
+impl FixedTimestep {
+    /// Runs the stage every `step` seconds
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is not greater than zero, which would run the stage forever
+    pub fn step(step: f64) -> Self {
+        assert!(
+            step > 0.0,
+            "FixedTimestep step must be greater than zero, but it is {}",
+            step
+        );
+        FixedTimestep {
+            step,
+            accumulator: 0.0,
+            looping: false,
+        }
+    }
+
+    /// Runs the stage `rate` times per second
+    pub fn steps_per_second(rate: f64) -> Self {
+        Self::step(1.0 / rate)
+    }
+
+    /// The fraction of a step that has accumulated but not been run yet. Useful for interpolating between steps.
+    pub fn overstep_percentage(&self) -> f64 {
+        self.accumulator / self.step
+    }
+
+    fn update(&mut self, time: &Time) -> ShouldRun {
+        // the criteria are evaluated again after each step, so only accumulate time at the start of an update
+        if !self.looping {
+            self.accumulator += time.delta_seconds_f64;
+        }
+
+        if self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            self.looping = true;
+            ShouldRun::YesAndLoop
+        } else {
+            self.looping = false;
+            ShouldRun::No
+        }
+    }
+}
+
+impl RunCriteria for FixedTimestep {
+    fn should_run(&mut self, _world: &World, resources: &Resources) -> ShouldRun {
+        let time = resources
+            .get::<Time>()
+            .expect("FixedTimestep requires the Time resource");
+        self.update(&time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedTimestep;
+    use crate::time::Time;
+    use bevy_ecs::ShouldRun;
+
+    fn steps_in_update(fixed_timestep: &mut FixedTimestep, time: &Time) -> usize {
+        let mut steps = 0;
+        while fixed_timestep.update(time) == ShouldRun::YesAndLoop {
+            steps += 1;
+        }
+        steps
+    }
+
+    #[test]
+    fn fixed_timestep() {
+        let mut fixed_timestep = FixedTimestep::step(0.5);
+        let short_frame = Time {
+            delta_seconds_f64: 0.25,
+            ..Default::default()
+        };
+        assert_eq!(steps_in_update(&mut fixed_timestep, &short_frame), 0);
+        assert_eq!(steps_in_update(&mut fixed_timestep, &short_frame), 1);
+
+        let long_frame = Time {
+            delta_seconds_f64: 1.25,
+            ..Default::default()
+        };
+        assert_eq!(steps_in_update(&mut fixed_timestep, &long_frame), 2);
+        assert!((fixed_timestep.overstep_percentage() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    #[should_panic(expected = "FixedTimestep step must be greater than zero")]
+    fn zero_step() {
+        FixedTimestep::step(0.0);
+    }
+}
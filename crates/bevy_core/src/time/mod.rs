@@ -1,6 +1,12 @@
+mod fixed_timestep;
+mod stopwatch;
 #[allow(clippy::module_inception)]
 mod time;
 mod timer;
+mod virtual_time;
 
+pub use fixed_timestep::*;
+pub use stopwatch::*;
 pub use time::*;
 pub use timer::*;
+pub use virtual_time::*;
@@ -0,0 +1,96 @@
+use crate::time::{Clock, Time, VirtualTime};
+use bevy_ecs::prelude::*;
+use bevy_property::Properties;
+use std::time::Duration;
+
+/// Tracks how much time has elapsed since it was started or reset. Stopwatch components are ticked by [Time] unless
+/// another [Clock] is chosen with [Stopwatch::with_clock].
+#[derive(Clone, Debug, Default, Properties)]
+pub struct Stopwatch {
+    elapsed: Duration,
+    paused: bool,
+    clock: Clock,
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Makes the stopwatch tick by `clock`
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// Stops the stopwatch. Ticks have no effect until it is unpaused.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn tick(&mut self, delta: Duration) -> &Self {
+        if !self.paused {
+            self.elapsed += delta;
+        }
+        self
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::from_secs(0);
+    }
+}
+
+pub(crate) fn stopwatch_system(
+    time: Res<Time>,
+    virtual_time: Res<VirtualTime>,
+    mut query: Query<&mut Stopwatch>,
+) {
+    for mut stopwatch in &mut query.iter() {
+        let delta = stopwatch.clock.delta(&time, &virtual_time);
+        stopwatch.tick(delta);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stopwatch;
+    use std::time::Duration;
+
+    #[test]
+    fn stopwatch() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.tick(Duration::from_millis(500));
+        stopwatch.pause();
+        stopwatch.tick(Duration::from_secs(1));
+        assert_eq!(stopwatch.elapsed(), Duration::from_millis(500));
+        stopwatch.unpause();
+        stopwatch.tick(Duration::from_secs(1));
+        assert!((stopwatch.elapsed_secs() - 1.5).abs() < f32::EPSILON);
+        stopwatch.reset();
+        assert_eq!(stopwatch.elapsed(), Duration::from_secs(0));
+    }
+}
@@ -1,4 +1,5 @@
-use bevy_ecs::ResMut;
+use bevy_app::{testing::SimulatedTime, Replay, ReplayMode};
+use bevy_ecs::{Res, ResMut};
 use std::time::{Duration, Instant};
 
 /// Tracks elapsed time since the last update and since the App has started
@@ -38,11 +39,42 @@ impl Time {
         self.instant = Some(now);
     }
 
+    /// Advances the time by `delta` instead of measuring the time since the last update. The time since startup is the
+    /// sum of the deltas, which makes it reproducible.
+    pub fn update_with_delta(&mut self, delta: Duration) {
+        self.delta = delta;
+        self.delta_seconds_f64 = delta.as_secs_f64();
+        self.delta_seconds = delta.as_secs_f32();
+        self.seconds_since_startup += self.delta_seconds_f64;
+        self.instant = Some(Instant::now());
+    }
+
     pub fn time_since_startup(&self) -> Duration {
         Instant::now() - self.startup
     }
 }
 
-pub(crate) fn time_system(mut time: ResMut<Time>) {
-    time.update();
+pub(crate) fn time_system(
+    mut time: ResMut<Time>,
+    mut replay: ResMut<Replay>,
+    simulated_time: Option<Res<SimulatedTime>>,
+) {
+    // only apps built with `AppBuilder::test_app` simulate time
+    let simulated_delta = simulated_time.and_then(|simulated_time| simulated_time.delta);
+    match (replay.mode(), simulated_delta) {
+        (ReplayMode::Off, None) => time.update(),
+        (ReplayMode::Off, Some(delta)) => time.update_with_delta(delta),
+        // measure the delta, but accumulate it like a replay would
+        (ReplayMode::Record, _) | (ReplayMode::Replay, _) => {
+            let delta = replay
+                .replayed_time_delta()
+                .or(simulated_delta)
+                .unwrap_or_else(|| {
+                    time.instant
+                        .map_or(Duration::from_secs(0), |instant| Instant::now() - instant)
+                });
+            replay.record_time_delta(delta);
+            time.update_with_delta(delta);
+        }
+    }
 }
@@ -1,4 +1,5 @@
-use crate::time::Time;
+use crate::time::{Clock, Time, VirtualTime};
+use bevy_app::Events;
 use bevy_ecs::prelude::*;
 use bevy_property::Properties;
 use std::time::Duration;
@@ -7,56 +8,280 @@ use std::time::Duration;
 ///
 /// Non repeating timers will stop tracking and stay in the finished state until reset.
 /// Repeating timers will only be in the finished state on each tick `duration` is reached or exceeded, and can still be reset at any given point.
+/// A repeating timer can finish several times in one long tick, see [Timer::times_finished].
+///
+/// Timers are ticked by [Time] unless another [Clock] is chosen with [Timer::with_clock]. A timer component can also
+/// send a [TimerFinished] event whenever it finishes, see [Timer::with_finished_event].
 #[derive(Clone, Debug, Default, Properties)]
 pub struct Timer {
-    pub elapsed: f32,
-    pub duration: f32,
-    pub finished: bool,
-    /// Will only be true on the tick `duration` is reached or exceeded.
-    pub just_finished: bool,
-    pub repeating: bool,
+    elapsed: Duration,
+    duration: Duration,
+    repeating: bool,
+    paused: bool,
+    finished: bool,
+    times_finished: u32,
+    clock: Clock,
+    finished_event: bool,
 }
 
 impl Timer {
     pub fn new(duration: Duration, repeating: bool) -> Self {
         Timer {
-            duration: duration.as_secs_f32(),
+            duration,
             repeating,
             ..Default::default()
         }
     }
 
     pub fn from_seconds(seconds: f32, repeating: bool) -> Self {
-        Timer {
-            duration: seconds,
-            repeating,
-            ..Default::default()
-        }
+        Self::new(Duration::from_secs_f32(seconds), repeating)
+    }
+
+    /// Makes the timer tick by `clock`
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Makes the timer send a [TimerFinished] event each time it finishes while it is ticked as a component
+    pub fn with_finished_event(mut self) -> Self {
+        self.finished_event = true;
+        self
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+
+    pub fn repeating(&self) -> bool {
+        self.repeating
+    }
+
+    pub fn set_repeating(&mut self, repeating: bool) {
+        self.repeating = repeating;
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// Whether the timer is finished. Repeating timers are only finished on the ticks that reach `duration`.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Will only be true on the tick `duration` is reached or exceeded.
+    pub fn just_finished(&self) -> bool {
+        self.times_finished > 0
+    }
+
+    /// How many times the timer finished during the last tick. This is more than one when a repeating timer wraps
+    /// around several times in one tick.
+    pub fn times_finished(&self) -> u32 {
+        self.times_finished
     }
 
-    pub fn tick(&mut self, delta: f32) {
-        let prev_finished = self.elapsed >= self.duration;
-        if !prev_finished {
-            self.elapsed += delta;
+    /// The fraction of `duration` that has elapsed, from 0.0 to 1.0
+    pub fn percent(&self) -> f32 {
+        if self.duration == Duration::from_secs(0) {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
         }
+    }
+
+    pub fn percent_left(&self) -> f32 {
+        1.0 - self.percent()
+    }
+
+    /// Stops the timer. Ticks have no effect until it is unpaused.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
 
-        self.finished = self.elapsed >= self.duration;
-        self.just_finished = !prev_finished && self.finished;
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn tick(&mut self, delta: Duration) -> &Self {
+        if self.paused || (self.finished && !self.repeating) {
+            self.times_finished = 0;
+            self.finished &= !self.repeating;
+            return self;
+        }
 
-        if self.repeating && self.finished {
-            self.elapsed %= self.duration;
+        self.elapsed += delta;
+        if self.elapsed < self.duration {
+            self.finished = false;
+            self.times_finished = 0;
+        } else if !self.repeating {
+            self.finished = true;
+            self.times_finished = 1;
+            self.elapsed = self.duration;
+        } else if self.duration == Duration::from_secs(0) {
+            self.finished = true;
+            self.times_finished = 1;
+            self.elapsed = Duration::from_secs(0);
+        } else {
+            let elapsed = self.elapsed.as_nanos();
+            let duration = self.duration.as_nanos();
+            self.finished = true;
+            self.times_finished = (elapsed / duration).min(u32::MAX as u128) as u32;
+            self.elapsed = Duration::from_nanos((elapsed % duration) as u64);
         }
+        self
     }
 
     pub fn reset(&mut self) {
         self.finished = false;
-        self.just_finished = false;
-        self.elapsed = 0.0;
+        self.times_finished = 0;
+        self.elapsed = Duration::from_secs(0);
+    }
+}
+
+/// Sent when the [Timer] of an entity finishes, if the timer was created with [Timer::with_finished_event]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TimerFinished {
+    pub entity: Entity,
+    /// How many times the timer finished during the tick, see [Timer::times_finished]
+    pub times_finished: u32,
+}
+
+pub(crate) fn timer_system(
+    time: Res<Time>,
+    virtual_time: Res<VirtualTime>,
+    mut finished_events: ResMut<Events<TimerFinished>>,
+    mut query: Query<(Entity, &mut Timer)>,
+) {
+    for (entity, mut timer) in &mut query.iter() {
+        let delta = timer.clock.delta(&time, &virtual_time);
+        timer.tick(delta);
+        if timer.finished_event && timer.just_finished() {
+            finished_events.send(TimerFinished {
+                entity,
+                times_finished: timer.times_finished,
+            });
+        }
     }
 }
 
-pub(crate) fn timer_system(time: Res<Time>, mut query: Query<&mut Timer>) {
-    for mut timer in &mut query.iter() {
-        timer.tick(time.delta_seconds);
+#[cfg(test)]
+mod tests {
+    use super::{Timer, TimerFinished};
+    use crate::{Clock, CorePlugin, Time, VirtualTime};
+    use bevy_app::{testing::TestApp, App};
+    use bevy_type_registry::TypeRegistryPlugin;
+    use std::time::Duration;
+
+    fn test_app(frame_delta: Duration) -> TestApp {
+        App::build()
+            .add_plugin(TypeRegistryPlugin)
+            .add_plugin(CorePlugin)
+            .test_app(frame_delta)
+    }
+
+    #[test]
+    fn non_repeating_timer() {
+        let mut timer = Timer::from_seconds(1.0, false);
+        timer.tick(Duration::from_millis(750));
+        assert!(!timer.finished());
+        assert!((timer.percent() - 0.75).abs() < f32::EPSILON);
+        timer.pause();
+        timer.tick(Duration::from_secs(1));
+        assert_eq!(timer.elapsed(), Duration::from_millis(750));
+        timer.unpause();
+        timer.tick(Duration::from_secs(3));
+        assert!(timer.finished() && timer.just_finished());
+        assert_eq!(timer.times_finished(), 1);
+        assert_eq!(timer.elapsed(), Duration::from_secs(1));
+        timer.tick(Duration::from_secs(1));
+        assert!(timer.finished() && !timer.just_finished());
+        timer.reset();
+        assert!(!timer.finished());
+    }
+
+    #[test]
+    fn repeating_timer() {
+        let mut timer = Timer::from_seconds(1.0, true);
+        timer.tick(Duration::from_millis(3500));
+        assert!(timer.finished());
+        assert_eq!(timer.times_finished(), 3);
+        assert_eq!(timer.elapsed(), Duration::from_millis(500));
+        timer.tick(Duration::from_millis(250));
+        assert!(!timer.finished() && !timer.just_finished());
+        timer.tick(Duration::from_millis(250));
+        assert_eq!(timer.times_finished(), 1);
+        assert_eq!(timer.elapsed(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn timer_system() {
+        let mut app = test_app(Duration::from_millis(250));
+        let timer = app.world_mut().spawn((Timer::from_seconds(1.0, false),));
+
+        app.step(3);
+        assert!(!app.world().get::<Timer>(timer).unwrap().finished());
+        app.update();
+        assert!(app.world().get::<Timer>(timer).unwrap().just_finished());
+        let time = app.resource::<Time>();
+        assert!((time.seconds_since_startup - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn timer_clocks() {
+        let mut app = test_app(Duration::from_millis(100));
+        let real = app.world_mut().spawn((Timer::from_seconds(10.0, false),));
+        let virtual_ = app
+            .world_mut()
+            .spawn((Timer::from_seconds(10.0, false).with_clock(Clock::Virtual),));
+        let elapsed = |app: &TestApp, entity| app.world().get::<Timer>(entity).unwrap().elapsed();
+
+        app.resource_mut::<VirtualTime>().set_relative_speed(2.0);
+        app.step(2);
+        assert_eq!(elapsed(&app, real), Duration::from_millis(200));
+        assert_eq!(elapsed(&app, virtual_), Duration::from_millis(400));
+
+        app.resource_mut::<VirtualTime>().pause();
+        app.step(2);
+        assert_eq!(elapsed(&app, real), Duration::from_millis(400));
+        assert_eq!(elapsed(&app, virtual_), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn timer_finished_events() {
+        let mut app = test_app(Duration::from_millis(250));
+        let fast = app
+            .world_mut()
+            .spawn((Timer::from_seconds(0.1, true).with_finished_event(),));
+        app.world_mut().spawn((Timer::from_seconds(0.1, true),));
+
+        app.update();
+        assert_eq!(
+            app.read_events::<TimerFinished>(),
+            vec![TimerFinished {
+                entity: fast,
+                times_finished: 2
+            }]
+        );
     }
 }
@@ -0,0 +1,151 @@
+use crate::time::Time;
+use bevy_ecs::{Res, ResMut};
+use bevy_property::impl_property;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A clock that follows [Time], but can be paused, slowed down or sped up. After a long frame it advances by at most
+/// [VirtualTime::max_delta], so that a hitch does not make the game skip ahead.
+///
+/// Gameplay systems read [VirtualTime] so that they stop while the game is paused, while systems that read [Time],
+/// like UI animations, keep running.
+#[derive(Debug, Clone)]
+pub struct VirtualTime {
+    pub delta: Duration,
+    pub delta_seconds_f64: f64,
+    pub delta_seconds: f32,
+    pub seconds_since_startup: f64,
+    paused: bool,
+    relative_speed: f64,
+    max_delta: Duration,
+}
+
+impl Default for VirtualTime {
+    fn default() -> Self {
+        VirtualTime {
+            delta: Duration::from_secs(0),
+            delta_seconds_f64: 0.0,
+            delta_seconds: 0.0,
+            seconds_since_startup: 0.0,
+            paused: false,
+            relative_speed: 1.0,
+            max_delta: Duration::from_millis(250),
+        }
+    }
+}
+
+impl VirtualTime {
+    /// Stops the clock. Its delta is zero until it is unpaused.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// How fast the clock runs compared to [Time], where 0.5 is half speed and 2.0 is double speed
+    pub fn relative_speed(&self) -> f64 {
+        self.relative_speed
+    }
+
+    /// # Panics
+    /// Panics if `relative_speed` is negative or not finite.
+    pub fn set_relative_speed(&mut self, relative_speed: f64) {
+        if !relative_speed.is_finite() || relative_speed < 0.0 {
+            panic!(
+                "Relative speed must be finite and not negative: {}",
+                relative_speed
+            );
+        }
+        self.relative_speed = relative_speed;
+    }
+
+    /// The longest real time delta that the clock advances by in one frame, before it is scaled by
+    /// [VirtualTime::relative_speed]
+    pub fn max_delta(&self) -> Duration {
+        self.max_delta
+    }
+
+    pub fn set_max_delta(&mut self, max_delta: Duration) {
+        self.max_delta = max_delta;
+    }
+
+    /// Advances the clock by a frame that took `real_delta`
+    pub fn advance(&mut self, real_delta: Duration) {
+        self.delta = if self.paused {
+            Duration::from_secs(0)
+        } else {
+            real_delta.min(self.max_delta).mul_f64(self.relative_speed)
+        };
+        self.delta_seconds_f64 = self.delta.as_secs_f64();
+        self.delta_seconds = self.delta.as_secs_f32();
+        self.seconds_since_startup += self.delta_seconds_f64;
+    }
+}
+
+pub(crate) fn virtual_time_system(time: Res<Time>, mut virtual_time: ResMut<VirtualTime>) {
+    virtual_time.advance(time.delta);
+}
+
+/// The clock that a [Timer](crate::Timer) is ticked by
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Clock {
+    /// [Time], which measures real time
+    #[default]
+    Real,
+    /// [VirtualTime], which can be paused and scaled
+    Virtual,
+}
+
+impl Clock {
+    /// The delta of the current frame according to this clock
+    pub fn delta(self, time: &Time, virtual_time: &VirtualTime) -> Duration {
+        match self {
+            Clock::Real => time.delta,
+            Clock::Virtual => virtual_time.delta,
+        }
+    }
+}
+
+impl_property!(Clock);
+
+#[cfg(test)]
+mod tests {
+    use super::VirtualTime;
+    use std::time::Duration;
+
+    #[test]
+    fn virtual_time() {
+        let mut time = VirtualTime::default();
+        time.advance(Duration::from_millis(100));
+        assert_eq!(time.delta, Duration::from_millis(100));
+
+        time.set_relative_speed(0.5);
+        time.advance(Duration::from_millis(100));
+        assert_eq!(time.delta, Duration::from_millis(50));
+
+        // hitches are clamped before the delta is scaled
+        time.advance(Duration::from_secs(2));
+        assert_eq!(time.delta, Duration::from_millis(125));
+
+        time.pause();
+        time.advance(Duration::from_millis(100));
+        assert_eq!(time.delta, Duration::from_secs(0));
+        time.unpause();
+        time.set_max_delta(Duration::from_secs(1));
+        time.advance(Duration::from_secs(2));
+        assert_eq!(time.delta, Duration::from_millis(500));
+        assert!((time.seconds_since_startup - 0.775).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "Relative speed must be finite and not negative")]
+    fn negative_speed() {
+        VirtualTime::default().set_relative_speed(-1.0);
+    }
+}
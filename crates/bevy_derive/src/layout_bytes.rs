@@ -0,0 +1,49 @@
+use crate::modules::{get_modules, get_path};
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Fields};
+
+pub fn derive_layout_bytes(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let fields = match &ast.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => panic!("expected a struct with named fields"),
+    };
+
+    let modules = get_modules(&ast.attrs);
+    let bevy_core_path = get_path(&modules.bevy_core);
+
+    let field_names = fields
+        .iter()
+        .map(|field| field.ident.as_ref().unwrap())
+        .collect::<Vec<_>>();
+    let field_types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+
+    let generics = ast.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let struct_name = &ast.ident;
+
+    TokenStream::from(quote! {
+        impl #impl_generics #bevy_core_path::LayoutBytes for #struct_name#ty_generics #where_clause {
+            fn layout_alignment(layout: #bevy_core_path::BufferLayout) -> usize {
+                let mut struct_layout = #bevy_core_path::StructLayout::new(layout);
+                #(struct_layout.add_field::<#field_types>();)*
+                struct_layout.alignment()
+            }
+            fn layout_size(layout: #bevy_core_path::BufferLayout) -> usize {
+                let mut struct_layout = #bevy_core_path::StructLayout::new(layout);
+                #(struct_layout.add_field::<#field_types>();)*
+                struct_layout.size()
+            }
+            fn write_layout_bytes(&self, layout: #bevy_core_path::BufferLayout, buffer: &mut [u8]) {
+                let mut struct_layout = #bevy_core_path::StructLayout::new(layout);
+                #(struct_layout.write_field(&self.#field_names, buffer);)*
+                struct_layout.finish(buffer);
+            }
+        }
+    })
+}
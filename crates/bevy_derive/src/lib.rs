@@ -3,6 +3,7 @@ extern crate proc_macro;
 mod app_plugin;
 mod as_vertex_buffer_descriptor;
 mod bytes;
+mod layout_bytes;
 mod modules;
 mod render_resource;
 mod render_resources;
@@ -24,6 +25,13 @@ pub fn derive_bytes(input: TokenStream) -> TokenStream {
     bytes::derive_bytes(input)
 }
 
+/// Derives the LayoutBytes trait, which writes the struct with std140 or std430 padding. Each field must also implement
+/// LayoutBytes or this will fail.
+#[proc_macro_derive(LayoutBytes, attributes(as_crate))]
+pub fn derive_layout_bytes(input: TokenStream) -> TokenStream {
+    layout_bytes::derive_layout_bytes(input)
+}
+
 /// Derives the RenderResources trait. Each field must implement RenderResource or this will fail.
 /// You can ignore fields using `#[render_resources(ignore)]`.
 #[proc_macro_derive(RenderResources, attributes(render_resources, as_crate))]
@@ -32,7 +40,8 @@ pub fn derive_render_resources(input: TokenStream) -> TokenStream {
 }
 
 /// Derives the RenderResource trait. The type must also implement `Bytes` or this will fail.
-#[proc_macro_derive(RenderResource, attributes(as_crate))]
+/// Use `#[render_resource(std140)]` or `#[render_resource(std430)]` to write the type with `LayoutBytes` instead.
+#[proc_macro_derive(RenderResource, attributes(render_resource, as_crate))]
 pub fn derive_render_resource(input: TokenStream) -> TokenStream {
     render_resource::derive_render_resource(input)
 }
@@ -48,6 +48,8 @@ pub fn get_modules(attributes: &[Attribute]) -> Modules {
             let value = attribute.tokens.to_string();
             if value[1..value.len() - 1] == modules.bevy_render {
                 modules.bevy_render = "crate".to_string();
+            } else if value[1..value.len() - 1] == modules.bevy_core {
+                modules.bevy_core = "crate".to_string();
             }
         }
     }
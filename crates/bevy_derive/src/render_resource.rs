@@ -1,7 +1,9 @@
 use crate::modules::{get_modules, get_path};
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput, Path};
+use syn::{parse::ParseStream, parse_macro_input, DeriveInput, Path};
+
+static RENDER_RESOURCE_ATTRIBUTE_NAME: &str = "render_resource";
 
 pub fn derive_render_resource(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
@@ -10,20 +12,58 @@ pub fn derive_render_resource(input: TokenStream) -> TokenStream {
     let bevy_render_path: Path = get_path(&modules.bevy_render);
     let bevy_asset_path: Path = get_path(&modules.bevy_asset);
     let bevy_core_path: Path = get_path(&modules.bevy_core);
+    let layout = ast
+        .attrs
+        .iter()
+        .find(|a| *a.path.get_ident().as_ref().unwrap() == RENDER_RESOURCE_ATTRIBUTE_NAME)
+        .map(|a| {
+            syn::custom_keyword!(std140);
+            syn::custom_keyword!(std430);
+            a.parse_args_with(|input: ParseStream| {
+                if input.parse::<Option<std140>>()?.is_some() {
+                    Ok(quote! { Std140 })
+                } else {
+                    input.parse::<std430>()?;
+                    Ok(quote! { Std430 })
+                }
+            })
+            .expect("invalid 'render_resource' attribute format")
+        });
     let struct_name = &ast.ident;
 
+    let (write_buffer_bytes, buffer_byte_len) = match layout {
+        Some(layout) => (
+            quote! {
+                use #bevy_core_path::LayoutBytes;
+                self.write_layout_bytes(#bevy_core_path::BufferLayout::#layout, buffer);
+            },
+            quote! {
+                use #bevy_core_path::LayoutBytes;
+                Some(Self::layout_size(#bevy_core_path::BufferLayout::#layout))
+            },
+        ),
+        None => (
+            quote! {
+                use #bevy_core_path::Bytes;
+                self.write_bytes(buffer);
+            },
+            quote! {
+                use #bevy_core_path::Bytes;
+                Some(self.byte_len())
+            },
+        ),
+    };
+
     TokenStream::from(quote! {
         impl #bevy_render_path::renderer::RenderResource for #struct_name {
             fn resource_type(&self) -> Option<#bevy_render_path::renderer::RenderResourceType> {
                 Some(#bevy_render_path::renderer::RenderResourceType::Buffer)
             }
             fn write_buffer_bytes(&self, buffer: &mut [u8]) {
-                use #bevy_core_path::Bytes;
-                self.write_bytes(buffer);
+                #write_buffer_bytes
             }
             fn buffer_byte_len(&self) -> Option<usize> {
-                use #bevy_core_path::Bytes;
-                Some(self.byte_len())
+                #buffer_byte_len
             }
             fn texture(&self) -> Option<#bevy_asset_path::Handle<#bevy_render_path::texture::Texture>> {
                 None
@@ -69,8 +69,8 @@ impl PrintDiagnosticsPlugin {
         time: Res<Time>,
         diagnostics: Res<Diagnostics>,
     ) {
-        state.timer.tick(time.delta_seconds);
-        if state.timer.finished {
+        state.timer.tick(time.delta);
+        if state.timer.finished() {
             println!("Diagnostics:");
             println!("{}", "-".repeat(93));
             if let Some(ref filter) = state.filter {
@@ -90,8 +90,8 @@ impl PrintDiagnosticsPlugin {
         time: Res<Time>,
         diagnostics: Res<Diagnostics>,
     ) {
-        state.timer.tick(time.delta_seconds);
-        if state.timer.finished {
+        state.timer.tick(time.delta);
+        if state.timer.finished() {
             println!("Diagnostics (Debug):");
             println!("{}", "-".repeat(93));
             if let Some(ref filter) = state.filter {
@@ -26,8 +26,11 @@ use syn::{parse_macro_input, DeriveInput, Path};
 ///
 /// Using derived `Bundle` impls improves spawn performance and can be convenient when combined with
 /// other derives like `serde::Deserialize`.
+///
+/// Fields marked with `#[bundle]` must be bundles themselves, and their components are included
+/// inline. Spawning a bundle that contains the same component type more than once panics.
 #[allow(clippy::cognitive_complexity)]
-#[proc_macro_derive(Bundle)]
+#[proc_macro_derive(Bundle, attributes(bundle))]
 pub fn derive_bundle(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     if !input.generics.params.is_empty() {
@@ -44,7 +47,17 @@ pub fn derive_bundle(input: TokenStream) -> TokenStream {
         }
     };
     let ident = input.ident;
-    let (tys, fields) = struct_fields(&data.fields);
+    let (bundle_fields, component_fields): (Vec<_>, Vec<_>) = struct_fields(&data.fields)
+        .into_iter()
+        .partition(|(_, _, is_bundle)| *is_bundle);
+    let (tys, fields): (Vec<_>, Vec<_>) = component_fields
+        .into_iter()
+        .map(|(ty, field, _)| (ty, field))
+        .unzip();
+    let (bundle_tys, bundle_fields): (Vec<_>, Vec<_>) = bundle_fields
+        .into_iter()
+        .map(|(ty, field, _)| (ty, field))
+        .unzip();
     let path_str = if crate_name("bevy").is_ok() {
         "bevy::ecs"
     } else if crate_name("bevy_ecs").is_ok() {
@@ -55,10 +68,28 @@ pub fn derive_bundle(input: TokenStream) -> TokenStream {
 
     let path: Path = syn::parse(path_str.parse::<TokenStream>().unwrap()).unwrap();
 
-    let n = tys.len();
+    let vars = (0..fields.len())
+        .map(|i| syn::Ident::new(&format!("component_{}", i), Span::call_site()))
+        .collect::<Vec<_>>();
+    let bundle_vars = (0..bundle_fields.len())
+        .map(|i| syn::Ident::new(&format!("bundle_{}", i), Span::call_site()))
+        .collect::<Vec<_>>();
+    // nested bundles read their components as they are fetched, so all components are checked for
+    // before any of them are read
+    let check_components = if bundle_tys.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            for info in <Self as #path::Bundle>::static_type_info() {
+                if f(info.id(), info.layout().size()).is_none() {
+                    return Err(#path::MissingComponent::from_type_info(&info));
+                }
+            }
+        }
+    };
     let code = quote! {
         impl #path::DynamicBundle for #ident {
-            fn with_ids<T>(&self, f: impl FnOnce(&[std::any::TypeId]) -> T) -> T {
+            fn with_ids<T>(&self, f: impl FnOnce(&[#path::ComponentId]) -> T) -> T {
                 Self::with_static_ids(f)
             }
 
@@ -66,37 +97,34 @@ pub fn derive_bundle(input: TokenStream) -> TokenStream {
                 Self::static_type_info()
             }
 
-            unsafe fn put(mut self, mut f: impl FnMut(*mut u8, std::any::TypeId, usize) -> bool) {
+            unsafe fn put(mut self, mut f: impl FnMut(*mut u8, #path::ComponentId, usize) -> bool) {
                 #(
-                    if f((&mut self.#fields as *mut #tys).cast::<u8>(), std::any::TypeId::of::<#tys>(), std::mem::size_of::<#tys>()) {
+                    if f((&mut self.#fields as *mut #tys).cast::<u8>(), #path::ComponentId::of::<#tys>(), std::mem::size_of::<#tys>()) {
                         #[allow(clippy::forget_copy)]
                         std::mem::forget(self.#fields);
                     }
                 )*
+                #(
+                    #path::DynamicBundle::put(self.#bundle_fields, &mut f);
+                )*
             }
         }
 
         impl #path::Bundle for #ident {
-            fn with_static_ids<T>(f: impl FnOnce(&[std::any::TypeId]) -> T) -> T {
-                use std::any::TypeId;
-                use std::mem;
+            fn with_static_ids<T>(f: impl FnOnce(&[#path::ComponentId]) -> T) -> T {
+                use #path::ComponentId;
 
                 #path::lazy_static::lazy_static! {
-                    static ref ELEMENTS: [TypeId; #n] = {
+                    static ref ELEMENTS: Vec<ComponentId> = {
+                        // sorted by descending alignment, then id
+                        let info = <#ident as #path::Bundle>::static_type_info();
                         let mut dedup = #path::bevy_utils::HashSet::default();
-                        for &(ty, name) in [#((std::any::TypeId::of::<#tys>(), std::any::type_name::<#tys>())),*].iter() {
-                            if !dedup.insert(ty) {
-                                panic!("{} has multiple {} fields; each type must occur at most once!", stringify!(#ident), name);
+                        for info in info.iter() {
+                            if !dedup.insert(info.id()) {
+                                panic!("{} has multiple {} components; each type must occur at most once!", stringify!(#ident), info.type_name().unwrap_or("external"));
                             }
                         }
-
-                        let mut tys = [#((mem::align_of::<#tys>(), TypeId::of::<#tys>())),*];
-                        tys.sort_unstable_by(|x, y| x.0.cmp(&y.0).reverse().then(x.1.cmp(&y.1)));
-                        let mut ids = [TypeId::of::<()>(); #n];
-                        for (id, info) in ids.iter_mut().zip(tys.iter()) {
-                            *id = info.1;
-                        }
-                        ids
+                        info.iter().map(|info| info.id()).collect()
                     };
                 }
 
@@ -104,40 +132,66 @@ pub fn derive_bundle(input: TokenStream) -> TokenStream {
             }
 
             fn static_type_info() -> Vec<#path::TypeInfo> {
+                #[allow(unused_mut)]
                 let mut info = vec![#(#path::TypeInfo::of::<#tys>()),*];
+                #(
+                    info.extend(<#bundle_tys as #path::Bundle>::static_type_info());
+                )*
                 info.sort_unstable();
                 info
             }
 
             unsafe fn get(
-                mut f: impl FnMut(std::any::TypeId, usize) -> Option<std::ptr::NonNull<u8>>,
+                mut f: impl FnMut(#path::ComponentId, usize) -> Option<std::ptr::NonNull<u8>>,
             ) -> Result<Self, #path::MissingComponent> {
+                #check_components
                 #(
-                    let #fields = f(std::any::TypeId::of::<#tys>(), std::mem::size_of::<#tys>())
+                    let #vars = f(#path::ComponentId::of::<#tys>(), std::mem::size_of::<#tys>())
                             .ok_or_else(#path::MissingComponent::new::<#tys>)?
                             .cast::<#tys>()
                         .as_ptr();
                 )*
-                Ok(Self { #( #fields: #fields.read(), )* })
+                #(
+                    let #bundle_vars = <#bundle_tys as #path::Bundle>::get(&mut f)?;
+                )*
+                Ok(Self { #( #fields: #vars.read(), )* #( #bundle_fields: #bundle_vars, )* })
             }
         }
     };
     TokenStream::from(code)
 }
 
-fn struct_fields(fields: &syn::Fields) -> (Vec<&syn::Type>, Vec<syn::Ident>) {
+/// Returns the type and name of each field, and whether it is marked with `#[bundle]`
+fn struct_fields(fields: &syn::Fields) -> Vec<(&syn::Type, syn::Member, bool)> {
+    let is_bundle =
+        |field: &syn::Field| field.attrs.iter().any(|attr| attr.path.is_ident("bundle"));
     match fields {
         syn::Fields::Named(ref fields) => fields
             .named
             .iter()
-            .map(|f| (&f.ty, f.ident.clone().unwrap()))
-            .unzip(),
+            .map(|f| {
+                (
+                    &f.ty,
+                    syn::Member::Named(f.ident.clone().unwrap()),
+                    is_bundle(f),
+                )
+            })
+            .collect(),
         syn::Fields::Unnamed(ref fields) => fields
             .unnamed
             .iter()
             .enumerate()
-            .map(|(i, f)| (&f.ty, syn::Ident::new(&i.to_string(), Span::call_site())))
-            .unzip(),
-        syn::Fields::Unit => (Vec::new(), Vec::new()),
+            .map(|(i, f)| {
+                (
+                    &f.ty,
+                    syn::Member::Unnamed(syn::Index {
+                        index: i as u32,
+                        span: Span::call_site(),
+                    }),
+                    is_bundle(f),
+                )
+            })
+            .collect(),
+        syn::Fields::Unit => Vec::new(),
     }
 }
@@ -28,7 +28,10 @@ use core::{
     ptr::{self, NonNull},
 };
 
-use crate::{borrow::AtomicBorrow, query::Fetch, Access, Component, Query};
+use crate::{
+    borrow::AtomicBorrow, check_tick, oldest_tick, query::Fetch, sparse_set::ComponentSparseSet,
+    Access, Component, Entity, Query,
+};
 
 /// A collection of entities having the same component types
 ///
@@ -36,9 +39,10 @@ use crate::{borrow::AtomicBorrow, query::Fetch, Access, Component, Query};
 /// go through the `World`.
 pub struct Archetype {
     types: Vec<TypeInfo>,
-    state: HashMap<TypeId, TypeState>,
+    state: HashMap<ComponentId, TypeState>,
+    sparse: HashMap<ComponentId, SparseTypeState>,
     len: u32,
-    entities: Box<[u128]>,
+    entities: Box<[Entity]>,
     // UnsafeCell allows unique references into `data` to be constructed while shared references
     // containing the `Archetype` exist
     data: UnsafeCell<NonNull<u8>>,
@@ -64,6 +68,7 @@ impl Archetype {
         }
         Self {
             state,
+            sparse: HashMap::default(),
             types,
             entities: Box::new([]),
             len: 0,
@@ -74,15 +79,21 @@ impl Archetype {
     }
 
     pub(crate) fn clear(&mut self) {
+        unsafe { self.clear_with(|_, removed, ty| ty.drop(removed)) }
+    }
+
+    /// Removes all entities, passing each of their components to `drop`
+    ///
+    /// # Safety
+    /// `drop` must move the component out of the pointer or drop it
+    pub(crate) unsafe fn clear_with(&mut self, mut drop: impl FnMut(Entity, *mut u8, &TypeInfo)) {
         for ty in &self.types {
             for index in 0..self.len {
-                unsafe {
-                    let removed = self
-                        .get_dynamic(ty.id, ty.layout.size(), index)
-                        .unwrap()
-                        .as_ptr();
-                    (ty.drop)(removed);
-                }
+                let removed = self
+                    .get_dynamic(ty.id, ty.layout.size(), index)
+                    .unwrap()
+                    .as_ptr();
+                drop(self.entities[index as usize], removed, ty);
             }
         }
         self.len = 0;
@@ -91,17 +102,98 @@ impl Archetype {
     #[allow(missing_docs)]
     #[inline]
     pub fn has<T: Component>(&self) -> bool {
-        self.has_dynamic(TypeId::of::<T>())
+        self.has_dynamic(ComponentId::of::<T>())
     }
 
-    pub(crate) fn has_dynamic(&self, id: TypeId) -> bool {
+    /// Whether this archetype stores the component identified by `id` in its tables
+    #[inline]
+    pub fn has_dynamic(&self, id: ComponentId) -> bool {
         self.state.contains_key(&id)
     }
 
+    /// Whether entities in this archetype may have a `T` component stored in a sparse set
+    #[inline]
+    pub fn has_sparse<T: Component>(&self) -> bool {
+        self.sparse.contains_key(&ComponentId::of::<T>())
+    }
+
+    /// Whether entities in this archetype may have the component identified by `id` stored in a
+    /// sparse set
+    #[inline]
+    pub fn has_sparse_dynamic(&self, id: ComponentId) -> bool {
+        self.sparse.contains_key(&id)
+    }
+
+    /// Makes the sparse set storing `ty` components accessible through this archetype
+    pub(crate) fn add_sparse(&mut self, ty: ComponentId, set: NonNull<ComponentSparseSet>) {
+        self.sparse.insert(
+            ty,
+            SparseTypeState {
+                set,
+                borrow: AtomicBorrow::new(),
+            },
+        );
+    }
+
+    pub(crate) fn remove_sparse(&mut self, ty: ComponentId) {
+        self.sparse.remove(&ty);
+    }
+
+    #[inline]
+    pub(crate) fn get_sparse<T: Component>(&self) -> Option<NonNull<ComponentSparseSet>> {
+        self.get_sparse_dynamic(ComponentId::of::<T>())
+    }
+
+    #[inline]
+    pub(crate) fn get_sparse_dynamic(
+        &self,
+        id: ComponentId,
+    ) -> Option<NonNull<ComponentSparseSet>> {
+        self.sparse.get(&id).map(|state| state.set)
+    }
+
+    /// Returns pointers to the first component identified by `id` and to its "added" and "mutated"
+    /// ticks
+    pub(crate) fn get_dynamic_with_added_and_mutated(
+        &self,
+        id: ComponentId,
+    ) -> Option<(NonNull<u8>, NonNull<u32>, NonNull<u32>)> {
+        let state = self.state.get(&id)?;
+        Some(unsafe {
+            (
+                NonNull::new_unchecked((*self.data.get()).as_ptr().add(state.offset)),
+                NonNull::new_unchecked(state.added_ticks.as_ptr() as *mut u32),
+                NonNull::new_unchecked(state.mutated_ticks.as_ptr() as *mut u32),
+            )
+        })
+    }
+
+    /// Returns pointers to the `T` component of the entity at `index` and its "mutated" tick,
+    /// whether it is stored in this archetype or in a sparse set
+    ///
+    /// # Safety
+    /// `index` must be in-bounds
+    pub(crate) unsafe fn get_component<T: Component>(
+        &self,
+        index: u32,
+    ) -> Option<(NonNull<T>, NonNull<u32>)> {
+        if let Some((components, mutated)) = self.get_with_mutated::<T>() {
+            Some((
+                NonNull::new_unchecked(components.as_ptr().add(index as usize)),
+                NonNull::new_unchecked(mutated.as_ptr().add(index as usize)),
+            ))
+        } else {
+            let set = self.get_sparse::<T>()?;
+            let (component, _added, mutated) =
+                set.as_ref().get_with_trackers(self.entity_id(index))?;
+            Some((component.cast::<T>(), mutated))
+        }
+    }
+
     #[allow(missing_docs)]
     #[inline]
     pub fn get<T: Component>(&self) -> Option<NonNull<T>> {
-        let state = self.state.get(&TypeId::of::<T>())?;
+        let state = self.state.get(&ComponentId::of::<T>())?;
         Some(unsafe {
             NonNull::new_unchecked(
                 (*self.data.get()).as_ptr().add(state.offset).cast::<T>() as *mut T
@@ -111,28 +203,28 @@ impl Archetype {
 
     #[allow(missing_docs)]
     #[inline]
-    pub fn get_with_added<T: Component>(&self) -> Option<(NonNull<T>, NonNull<bool>)> {
-        let state = self.state.get(&TypeId::of::<T>())?;
+    pub fn get_with_added<T: Component>(&self) -> Option<(NonNull<T>, NonNull<u32>)> {
+        let state = self.state.get(&ComponentId::of::<T>())?;
         Some(unsafe {
             (
                 NonNull::new_unchecked(
                     (*self.data.get()).as_ptr().add(state.offset).cast::<T>() as *mut T
                 ),
-                NonNull::new_unchecked(state.added_entities.as_ptr() as *mut bool),
+                NonNull::new_unchecked(state.added_ticks.as_ptr() as *mut u32),
             )
         })
     }
 
     #[allow(missing_docs)]
     #[inline]
-    pub fn get_with_mutated<T: Component>(&self) -> Option<(NonNull<T>, NonNull<bool>)> {
-        let state = self.state.get(&TypeId::of::<T>())?;
+    pub fn get_with_mutated<T: Component>(&self) -> Option<(NonNull<T>, NonNull<u32>)> {
+        let state = self.state.get(&ComponentId::of::<T>())?;
         Some(unsafe {
             (
                 NonNull::new_unchecked(
                     (*self.data.get()).as_ptr().add(state.offset).cast::<T>() as *mut T
                 ),
-                NonNull::new_unchecked(state.mutated_entities.as_ptr() as *mut bool),
+                NonNull::new_unchecked(state.mutated_ticks.as_ptr() as *mut u32),
             )
         })
     }
@@ -141,35 +233,35 @@ impl Archetype {
     #[inline]
     pub fn get_with_added_and_mutated<T: Component>(
         &self,
-    ) -> Option<(NonNull<T>, NonNull<bool>, NonNull<bool>)> {
-        let state = self.state.get(&TypeId::of::<T>())?;
+    ) -> Option<(NonNull<T>, NonNull<u32>, NonNull<u32>)> {
+        let state = self.state.get(&ComponentId::of::<T>())?;
         Some(unsafe {
             (
                 NonNull::new_unchecked(
                     (*self.data.get()).as_ptr().add(state.offset).cast::<T>() as *mut T
                 ),
-                NonNull::new_unchecked(state.added_entities.as_ptr() as *mut bool),
-                NonNull::new_unchecked(state.mutated_entities.as_ptr() as *mut bool),
+                NonNull::new_unchecked(state.added_ticks.as_ptr() as *mut u32),
+                NonNull::new_unchecked(state.mutated_ticks.as_ptr() as *mut u32),
             )
         })
     }
 
     #[allow(missing_docs)]
     #[inline]
-    pub fn get_mutated<T: Component>(&self) -> Option<NonNull<bool>> {
-        let state = self.state.get(&TypeId::of::<T>())?;
-        Some(unsafe { NonNull::new_unchecked(state.mutated_entities.as_ptr() as *mut bool) })
+    pub fn get_mutated<T: Component>(&self) -> Option<NonNull<u32>> {
+        let state = self.state.get(&ComponentId::of::<T>())?;
+        Some(unsafe { NonNull::new_unchecked(state.mutated_ticks.as_ptr() as *mut u32) })
     }
 
     #[allow(missing_docs)]
     #[inline]
-    pub fn get_added<T: Component>(&self) -> Option<NonNull<bool>> {
-        let state = self.state.get(&TypeId::of::<T>())?;
-        Some(unsafe { NonNull::new_unchecked(state.added_entities.as_ptr() as *mut bool) })
+    pub fn get_added<T: Component>(&self) -> Option<NonNull<u32>> {
+        let state = self.state.get(&ComponentId::of::<T>())?;
+        Some(unsafe { NonNull::new_unchecked(state.added_ticks.as_ptr() as *mut u32) })
     }
 
     #[allow(missing_docs)]
-    pub fn get_type_state_mut(&mut self, ty: TypeId) -> Option<&mut TypeState> {
+    pub fn get_type_state_mut(&mut self, ty: ComponentId) -> Option<&mut TypeState> {
         self.state.get_mut(&ty)
     }
 
@@ -177,9 +269,8 @@ impl Archetype {
     #[inline]
     pub fn borrow<T: Component>(&self) {
         if self
-            .state
-            .get(&TypeId::of::<T>())
-            .map_or(false, |x| !x.borrow.borrow())
+            .get_borrow(ComponentId::of::<T>())
+            .map_or(false, |x| !x.borrow())
         {
             panic!("{} already borrowed uniquely", type_name::<T>());
         }
@@ -189,9 +280,8 @@ impl Archetype {
     #[inline]
     pub fn borrow_mut<T: Component>(&self) {
         if self
-            .state
-            .get(&TypeId::of::<T>())
-            .map_or(false, |x| !x.borrow.borrow_mut())
+            .get_borrow(ComponentId::of::<T>())
+            .map_or(false, |x| !x.borrow_mut())
         {
             panic!("{} already borrowed", type_name::<T>());
         }
@@ -200,16 +290,58 @@ impl Archetype {
     #[allow(missing_docs)]
     #[inline]
     pub fn release<T: Component>(&self) {
-        if let Some(x) = self.state.get(&TypeId::of::<T>()) {
-            x.borrow.release();
+        if let Some(x) = self.get_borrow(ComponentId::of::<T>()) {
+            x.release();
         }
     }
 
     #[allow(missing_docs)]
     #[inline]
     pub fn release_mut<T: Component>(&self) {
-        if let Some(x) = self.state.get(&TypeId::of::<T>()) {
-            x.borrow.release_mut();
+        if let Some(x) = self.get_borrow(ComponentId::of::<T>()) {
+            x.release_mut();
+        }
+    }
+
+    /// Like `borrow`, for the component identified by `id`
+    #[inline]
+    pub fn borrow_dynamic(&self, id: ComponentId) {
+        if self.get_borrow(id).map(|x| x.borrow()) == Some(false) {
+            panic!("{:?} already borrowed uniquely", id);
+        }
+    }
+
+    /// Like `borrow_mut`, for the component identified by `id`
+    #[inline]
+    pub fn borrow_mut_dynamic(&self, id: ComponentId) {
+        if self.get_borrow(id).map(|x| x.borrow_mut()) == Some(false) {
+            panic!("{:?} already borrowed", id);
+        }
+    }
+
+    /// Like `release`, for the component identified by `id`
+    #[inline]
+    pub fn release_dynamic(&self, id: ComponentId) {
+        if let Some(x) = self.get_borrow(id) {
+            x.release();
+        }
+    }
+
+    /// Like `release_mut`, for the component identified by `id`
+    #[inline]
+    pub fn release_mut_dynamic(&self, id: ComponentId) {
+        if let Some(x) = self.get_borrow(id) {
+            x.release_mut();
+        }
+    }
+
+    /// Sparse set components are borrowed per archetype, just like table components, because
+    /// entities in different archetypes never share a component value
+    #[inline]
+    fn get_borrow(&self, ty: ComponentId) -> Option<&AtomicBorrow> {
+        match self.state.get(&ty) {
+            Some(state) => Some(&state.borrow),
+            None => self.sparse.get(&ty).map(|state| &state.borrow),
         }
     }
 
@@ -226,16 +358,16 @@ impl Archetype {
     }
 
     #[allow(missing_docs)]
-    pub fn iter_entities(&self) -> impl Iterator<Item = &u128> {
+    pub fn iter_entities(&self) -> impl Iterator<Item = &Entity> {
         self.entities.iter().take(self.len as usize)
     }
 
     #[inline]
-    pub(crate) fn entities(&self) -> NonNull<u128> {
+    pub(crate) fn entities(&self) -> NonNull<Entity> {
         unsafe { NonNull::new_unchecked(self.entities.as_ptr() as *mut _) }
     }
 
-    pub(crate) fn entity_id(&self, index: u32) -> u128 {
+    pub(crate) fn entity_id(&self, index: u32) -> Entity {
         self.entities[index as usize]
     }
 
@@ -244,11 +376,13 @@ impl Archetype {
         &self.types
     }
 
+    /// Returns a pointer to the component identified by `ty` of the entity at `index`
+    ///
     /// # Safety
-    /// `index` must be in-bounds
-    pub(crate) unsafe fn get_dynamic(
+    /// `index` must be in-bounds and `size` must be the size of the component
+    pub unsafe fn get_dynamic(
         &self,
-        ty: TypeId,
+        ty: ComponentId,
         size: usize,
         index: u32,
     ) -> Option<NonNull<u8>> {
@@ -263,7 +397,7 @@ impl Archetype {
 
     /// # Safety
     /// Every type must be written immediately after this call
-    pub unsafe fn allocate(&mut self, id: u128) -> u32 {
+    pub unsafe fn allocate(&mut self, id: Entity) -> u32 {
         if self.len as usize == self.entities.len() {
             self.grow(self.len.max(self.grow_size));
         }
@@ -283,10 +417,17 @@ impl Archetype {
         self.entities.len() as u32
     }
 
-    #[allow(missing_docs)]
-    pub fn clear_trackers(&mut self) {
+    /// Clamps the "added" and "mutated" ticks of all components so they don't appear new again
+    /// once the world's change tick wraps around. See [check_tick].
+    pub fn check_change_ticks(&mut self, change_tick: u32) {
+        let len = self.len as usize;
         for type_state in self.state.values_mut() {
-            type_state.clear_trackers();
+            for tick in type_state.added_ticks[..len]
+                .iter_mut()
+                .chain(type_state.mutated_ticks[..len].iter_mut())
+            {
+                check_tick(tick, change_tick);
+            }
         }
     }
 
@@ -294,13 +435,13 @@ impl Archetype {
         unsafe {
             let old_count = self.len as usize;
             let count = old_count + increment as usize;
-            let mut new_entities = vec![!0; count].into_boxed_slice();
+            let mut new_entities = vec![Entity::new(u32::MAX); count].into_boxed_slice();
             new_entities[0..old_count].copy_from_slice(&self.entities[0..old_count]);
             self.entities = new_entities;
 
             for type_state in self.state.values_mut() {
-                type_state.mutated_entities.resize_with(count, || false);
-                type_state.added_entities.resize_with(count, || false);
+                type_state.mutated_ticks.resize(count, 0);
+                type_state.added_ticks.resize(count, 0);
             }
 
             let old_data_size = mem::replace(&mut self.data_size, 0);
@@ -341,14 +482,20 @@ impl Archetype {
     }
 
     /// Returns the ID of the entity moved into `index`, if any
-    pub(crate) unsafe fn remove(&mut self, index: u32) -> Option<u128> {
+    ///
+    /// `drop` must move the component out of the pointer or drop it
+    pub(crate) unsafe fn remove(
+        &mut self,
+        index: u32,
+        mut drop: impl FnMut(*mut u8, &TypeInfo),
+    ) -> Option<Entity> {
         let last = self.len - 1;
         for ty in &self.types {
             let removed = self
                 .get_dynamic(ty.id, ty.layout.size(), index)
                 .unwrap()
                 .as_ptr();
-            (ty.drop)(removed);
+            drop(removed, ty);
             if index != last {
                 // TODO: copy component tracker state here
                 ptr::copy_nonoverlapping(
@@ -360,10 +507,8 @@ impl Archetype {
                 );
 
                 let type_state = self.state.get_mut(&ty.id).unwrap();
-                type_state.mutated_entities[index as usize] =
-                    type_state.mutated_entities[last as usize];
-                type_state.added_entities[index as usize] =
-                    type_state.added_entities[last as usize];
+                type_state.mutated_ticks[index as usize] = type_state.mutated_ticks[last as usize];
+                type_state.added_ticks[index as usize] = type_state.added_ticks[last as usize];
             }
         }
         self.len = last;
@@ -379,8 +524,8 @@ impl Archetype {
     pub(crate) unsafe fn move_to(
         &mut self,
         index: u32,
-        mut f: impl FnMut(*mut u8, TypeId, usize, bool, bool),
-    ) -> Option<u128> {
+        mut f: impl FnMut(*mut u8, ComponentId, usize, u32, u32),
+    ) -> Option<Entity> {
         let last = self.len - 1;
         for ty in &self.types {
             let moved = self
@@ -388,9 +533,9 @@ impl Archetype {
                 .unwrap()
                 .as_ptr();
             let type_state = self.state.get(&ty.id).unwrap();
-            let is_added = type_state.added_entities[index as usize];
-            let is_mutated = type_state.mutated_entities[index as usize];
-            f(moved, ty.id(), ty.layout().size(), is_added, is_mutated);
+            let added_tick = type_state.added_ticks[index as usize];
+            let mutated_tick = type_state.mutated_ticks[index as usize];
+            f(moved, ty.id(), ty.layout().size(), added_tick, mutated_tick);
             if index != last {
                 ptr::copy_nonoverlapping(
                     self.get_dynamic(ty.id, ty.layout.size(), last)
@@ -400,10 +545,8 @@ impl Archetype {
                     ty.layout.size(),
                 );
                 let type_state = self.state.get_mut(&ty.id).unwrap();
-                type_state.added_entities[index as usize] =
-                    type_state.added_entities[last as usize];
-                type_state.mutated_entities[index as usize] =
-                    type_state.mutated_entities[last as usize];
+                type_state.added_ticks[index as usize] = type_state.added_ticks[last as usize];
+                type_state.mutated_ticks[index as usize] = type_state.mutated_ticks[last as usize];
             }
         }
         self.len -= 1;
@@ -422,23 +565,28 @@ impl Archetype {
     ///  - `index` must be in-bound
     ///  - `size` must be the size of the component
     ///  - the storage array must be big enough
+    ///
+    /// If `added_tick` is set, the component is marked as added at that tick and as never mutated.
+    /// Returns a pointer to the stored component.
     pub unsafe fn put_dynamic(
         &mut self,
         component: *mut u8,
-        ty: TypeId,
+        ty: ComponentId,
         size: usize,
         index: u32,
-        added: bool,
-    ) {
+        added_tick: Option<u32>,
+    ) -> *mut u8 {
         let state = self.state.get_mut(&ty).unwrap();
-        if added {
-            state.added_entities[index as usize] = true;
+        if let Some(tick) = added_tick {
+            state.added_ticks[index as usize] = tick;
+            state.mutated_ticks[index as usize] = oldest_tick(tick);
         }
         let ptr = (*self.data.get())
             .as_ptr()
             .add(state.offset + size * index as usize)
             .cast::<u8>();
         ptr::copy_nonoverlapping(component, ptr, size);
+        ptr
     }
 
     /// How, if at all, `Q` will access entities in this archetype
@@ -464,11 +612,17 @@ impl Drop for Archetype {
     }
 }
 
+/// Borrow state of a sparse set component for the entities of one archetype
+struct SparseTypeState {
+    set: NonNull<ComponentSparseSet>,
+    borrow: AtomicBorrow,
+}
+
 pub struct TypeState {
     offset: usize,
     borrow: AtomicBorrow,
-    pub mutated_entities: Vec<bool>,
-    pub added_entities: Vec<bool>,
+    pub mutated_ticks: Vec<u32>,
+    pub added_ticks: Vec<u32>,
 }
 
 impl TypeState {
@@ -476,28 +630,43 @@ impl TypeState {
         Self {
             offset: 0,
             borrow: AtomicBorrow::new(),
-            mutated_entities: Vec::new(),
-            added_entities: Vec::new(),
+            mutated_ticks: Vec::new(),
+            added_ticks: Vec::new(),
         }
     }
+}
 
-    fn clear_trackers(&mut self) {
-        for mutated in self.mutated_entities.iter_mut() {
-            *mutated = false;
-        }
+/// Identifies a kind of component stored in a `World`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum ComponentId {
+    /// A component with a Rust type
+    RustTypeId(TypeId),
+    /// A component defined at runtime, e.g. by a scripting language. The id is chosen by whoever
+    /// defines the component and must be unique among external components of a `World`.
+    ExternalId(u64),
+}
 
-        for added in self.added_entities.iter_mut() {
-            *added = false;
-        }
+impl ComponentId {
+    /// The id of the Rust component type `T`
+    #[inline]
+    pub fn of<T: 'static>() -> Self {
+        ComponentId::RustTypeId(TypeId::of::<T>())
+    }
+}
+
+impl From<TypeId> for ComponentId {
+    fn from(id: TypeId) -> Self {
+        ComponentId::RustTypeId(id)
     }
 }
 
 /// Metadata required to store a component
 #[derive(Debug, Copy, Clone)]
 pub struct TypeInfo {
-    id: TypeId,
+    id: ComponentId,
     layout: Layout,
     drop: unsafe fn(*mut u8),
+    type_name: Option<&'static str>,
 }
 
 impl TypeInfo {
@@ -508,15 +677,30 @@ impl TypeInfo {
         }
 
         Self {
-            id: TypeId::of::<T>(),
+            id: ComponentId::of::<T>(),
             layout: Layout::new::<T>(),
             drop: drop_ptr::<T>,
+            type_name: Some(core::any::type_name::<T>()),
+        }
+    }
+
+    /// Metadata for a component defined at runtime, whose values have the given `layout` and are
+    /// dropped in place by `drop`
+    ///
+    /// Values of external components are moved around as plain bytes, like Rust components, so they
+    /// must not rely on their address staying the same.
+    pub fn external(id: u64, layout: Layout, drop: unsafe fn(*mut u8)) -> Self {
+        Self {
+            id: ComponentId::ExternalId(id),
+            layout,
+            drop,
+            type_name: None,
         }
     }
 
     #[allow(missing_docs)]
     #[inline]
-    pub fn id(&self) -> TypeId {
+    pub fn id(&self) -> ComponentId {
         self.id
     }
 
@@ -526,6 +710,12 @@ impl TypeInfo {
         self.layout
     }
 
+    /// The name of the Rust type of the component, or `None` for external components
+    #[inline]
+    pub fn type_name(&self) -> Option<&'static str> {
+        self.type_name
+    }
+
     pub(crate) unsafe fn drop(&self, data: *mut u8) {
         (self.drop)(data)
     }
@@ -538,7 +728,7 @@ impl PartialOrd for TypeInfo {
 }
 
 impl Ord for TypeInfo {
-    /// Order by alignment, descending. Ties broken with TypeId.
+    /// Order by alignment, descending. Ties broken with ComponentId.
     fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.layout
             .align()
@@ -23,6 +23,7 @@ use core::{
 
 use crate::{archetype::Archetype, Component, MissingComponent};
 
+#[derive(Default)]
 pub struct AtomicBorrow(AtomicUsize);
 
 impl AtomicBorrow {
@@ -78,13 +79,9 @@ impl<'a, T: Component> Ref<'a, T> {
     ///
     /// - the index of the component must be valid
     pub unsafe fn new(archetype: &'a Archetype, index: u32) -> Result<Self, MissingComponent> {
-        let target = NonNull::new_unchecked(
-            archetype
-                .get::<T>()
-                .ok_or_else(MissingComponent::new::<T>)?
-                .as_ptr()
-                .add(index as usize),
-        );
+        let (target, _) = archetype
+            .get_component::<T>(index)
+            .ok_or_else(MissingComponent::new::<T>)?;
         archetype.borrow::<T>();
         Ok(Self { archetype, target })
     }
@@ -120,33 +117,31 @@ where
 pub struct RefMut<'a, T: Component> {
     archetype: &'a Archetype,
     target: NonNull<T>,
-    modified: &'a mut bool,
+    mutated_tick: &'a mut u32,
+    change_tick: u32,
 }
 
 impl<'a, T: Component> RefMut<'a, T> {
-    /// Creates a new entity component mutable borrow
+    /// Creates a new entity component mutable borrow. Mutating the component through it marks the
+    /// component as mutated at `change_tick`.
     ///
     /// # Safety
     ///
     /// - the index of the component must be valid
-    pub unsafe fn new(archetype: &'a Archetype, index: u32) -> Result<Self, MissingComponent> {
-        let target = NonNull::new_unchecked(
-            archetype
-                .get::<T>()
-                .ok_or_else(MissingComponent::new::<T>)?
-                .as_ptr()
-                .add(index as usize),
-        );
+    pub unsafe fn new(
+        archetype: &'a Archetype,
+        index: u32,
+        change_tick: u32,
+    ) -> Result<Self, MissingComponent> {
+        let (target, mutated_tick) = archetype
+            .get_component::<T>(index)
+            .ok_or_else(MissingComponent::new::<T>)?;
         archetype.borrow_mut::<T>();
-        let modified = archetype
-            .get_mutated::<T>()
-            .unwrap()
-            .as_ptr()
-            .add(index as usize);
         Ok(Self {
             archetype,
             target,
-            modified: &mut *modified,
+            mutated_tick: &mut *mutated_tick.as_ptr(),
+            change_tick,
         })
     }
 }
@@ -170,7 +165,7 @@ impl<'a, T: Component> Deref for RefMut<'a, T> {
 
 impl<'a, T: Component> DerefMut for RefMut<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        *self.modified = true;
+        *self.mutated_tick = self.change_tick;
         unsafe { self.target.as_mut() }
     }
 }
@@ -189,21 +184,15 @@ where
 pub struct EntityRef<'a> {
     archetype: Option<&'a Archetype>,
     index: u32,
+    change_tick: u32,
 }
 
 impl<'a> EntityRef<'a> {
-    /// Construct a `Ref` for an entity with no components
-    pub(crate) fn empty() -> Self {
-        Self {
-            archetype: None,
-            index: 0,
-        }
-    }
-
-    pub(crate) unsafe fn new(archetype: &'a Archetype, index: u32) -> Self {
+    pub(crate) unsafe fn new(archetype: &'a Archetype, index: u32, change_tick: u32) -> Self {
         Self {
             archetype: Some(archetype),
             index,
+            change_tick,
         }
     }
 
@@ -219,7 +208,7 @@ impl<'a> EntityRef<'a> {
     ///
     /// Panics if the component is already borrowed from another entity with the same components.
     pub fn get_mut<T: Component>(&self) -> Option<RefMut<'a, T>> {
-        Some(unsafe { RefMut::new(self.archetype?, self.index).ok()? })
+        Some(unsafe { RefMut::new(self.archetype?, self.index, self.change_tick).ok()? })
     }
 }
 
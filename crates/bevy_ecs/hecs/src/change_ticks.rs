@@ -0,0 +1,121 @@
+// modified by Bevy contributors
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// How many ticks may pass before stored ticks must be checked with [check_tick] to stay
+/// comparable
+pub const CHECK_TICK_THRESHOLD: u32 = 518_400_000;
+
+/// The maximum age a stored tick can have before it is clamped. Changes older than this are
+/// treated as if they happened this many ticks ago.
+pub const MAX_CHANGE_AGE: u32 = u32::MAX - (2 * CHECK_TICK_THRESHOLD - 1);
+
+/// A change tick counter. Every `World` owns one, and so does every `Resources`. It is advanced
+/// every time a system runs and every time trackers are cleared, so comparing against it orders the
+/// changes made to its owner.
+#[derive(Debug)]
+pub struct ChangeTickCounter(AtomicU32);
+
+impl Default for ChangeTickCounter {
+    fn default() -> Self {
+        Self(AtomicU32::new(1))
+    }
+}
+
+impl ChangeTickCounter {
+    /// Returns the current change tick
+    #[inline]
+    pub fn read(&self) -> u32 {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Advances the change tick, returning its previous value
+    #[inline]
+    pub fn increment(&self) -> u32 {
+        self.0.fetch_add(1, Ordering::AcqRel)
+    }
+}
+
+/// The ticks change detection compares against: the tick of the last run of the current system
+/// and the tick of its current run
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ChangeTicks {
+    /// The tick at which the system last ran. Changes made after it are new to the system.
+    pub last_change_tick: u32,
+    /// The tick of the current run. Changes made by the system are stamped with it.
+    pub change_tick: u32,
+}
+
+impl ChangeTicks {
+    #[allow(missing_docs)]
+    pub fn new(last_change_tick: u32, change_tick: u32) -> Self {
+        Self {
+            last_change_tick,
+            change_tick,
+        }
+    }
+
+    /// Returns true if a change stamped with `tick` happened after `last_change_tick`
+    #[inline]
+    pub fn is_newer(&self, tick: u32) -> bool {
+        let ticks_since_insert = self.change_tick.wrapping_sub(tick).min(MAX_CHANGE_AGE);
+        let ticks_since_system = self
+            .change_tick
+            .wrapping_sub(self.last_change_tick)
+            .min(MAX_CHANGE_AGE);
+        ticks_since_system > ticks_since_insert
+    }
+}
+
+/// A tick that is older than any tick a system could have last run at. Used as the "mutated" tick
+/// of newly added components and as the last tick of systems that never ran.
+#[inline]
+pub fn oldest_tick(change_tick: u32) -> u32 {
+    change_tick.wrapping_sub(MAX_CHANGE_AGE)
+}
+
+/// Clamps `tick` to [MAX_CHANGE_AGE] so it doesn't wrap around and appear new again. Has to be
+/// called on every stored tick at least once every [CHECK_TICK_THRESHOLD] ticks.
+#[inline]
+pub fn check_tick(tick: &mut u32, change_tick: u32) {
+    if change_tick.wrapping_sub(*tick) > MAX_CHANGE_AGE {
+        *tick = oldest_tick(change_tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_ticks() {
+        let ticks = ChangeTicks::new(10, 20);
+        assert!(ticks.is_newer(15));
+        assert!(ticks.is_newer(20));
+        assert!(!ticks.is_newer(10));
+        assert!(!ticks.is_newer(5));
+        assert!(!ticks.is_newer(oldest_tick(20)));
+    }
+
+    #[test]
+    fn counter() {
+        let counter = ChangeTickCounter::default();
+        let other = ChangeTickCounter::default();
+        assert_eq!(counter.increment(), 1);
+        assert_eq!(counter.read(), 2);
+        assert_eq!(other.read(), 1);
+    }
+
+    #[test]
+    fn wrapping_ticks() {
+        let ticks = ChangeTicks::new(u32::MAX - 5, 5);
+        assert!(ticks.is_newer(u32::MAX - 2));
+        assert!(ticks.is_newer(2));
+        assert!(!ticks.is_newer(u32::MAX - 10));
+
+        let mut tick = 5u32;
+        let change_tick = tick.wrapping_add(MAX_CHANGE_AGE + 100);
+        check_tick(&mut tick, change_tick);
+        assert_eq!(change_tick.wrapping_sub(tick), MAX_CHANGE_AGE);
+    }
+}
@@ -0,0 +1,356 @@
+// modified by Bevy contributors
+
+use crate::{
+    alloc::vec::Vec, sparse_set::ComponentSparseSet, Access, Archetype, ChangeTicks, ComponentId,
+    Entity,
+};
+use core::ptr::NonNull;
+
+/// A query over components that are identified at runtime by their [ComponentId]
+///
+/// Components added with `read` and `write` are fetched for every matching entity, in the order
+/// they were added. Components added with `with` and `without` only filter entities.
+///
+/// # Example
+/// ```
+/// # use bevy_hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((123, true));
+/// world.spawn((456,));
+/// let query = DynamicQuery::new()
+///     .write(ComponentId::of::<i32>())
+///     .with(ComponentId::of::<bool>());
+/// for mut item in world.query_dynamic(&query).iter() {
+///     assert_eq!(item.entity(), a);
+///     unsafe { *item.get_mut(0).cast::<i32>().as_mut() += 1 };
+/// }
+/// assert_eq!(*world.get::<i32>(a).unwrap(), 124);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DynamicQuery {
+    fetch: Vec<(ComponentId, Access)>,
+    with: Vec<ComponentId>,
+    without: Vec<ComponentId>,
+}
+
+impl DynamicQuery {
+    /// Create a query that matches all entities without fetching any component
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch a shared pointer to the component identified by `id`
+    pub fn read(mut self, id: ComponentId) -> Self {
+        self.fetch.push((id, Access::Read));
+        self
+    }
+
+    /// Fetch a unique pointer to the component identified by `id`
+    pub fn write(mut self, id: ComponentId) -> Self {
+        self.fetch.push((id, Access::Write));
+        self
+    }
+
+    /// Only match entities that have the component identified by `id`
+    pub fn with(mut self, id: ComponentId) -> Self {
+        self.with.push(id);
+        self
+    }
+
+    /// Skip entities that have the component identified by `id`
+    pub fn without(mut self, id: ComponentId) -> Self {
+        self.without.push(id);
+        self
+    }
+
+    /// How, if at all, the query will access entities in `archetype`
+    pub fn access(&self, archetype: &Archetype) -> Option<Access> {
+        let has = |id| archetype.has_dynamic(id) || archetype.has_sparse_dynamic(id);
+        if !self
+            .fetch
+            .iter()
+            .map(|(id, _)| *id)
+            .chain(self.with.iter().copied())
+            .all(has)
+            || self.without.iter().any(|&id| archetype.has_dynamic(id))
+        {
+            return None;
+        }
+        Some(
+            self.fetch
+                .iter()
+                .map(|(_, access)| *access)
+                .max()
+                .unwrap_or(Access::Iterate),
+        )
+    }
+}
+
+/// Where a component fetched by a `DynamicQuery` is stored
+#[derive(Copy, Clone)]
+enum Column {
+    Table {
+        data: NonNull<u8>,
+        added: NonNull<u32>,
+        mutated: NonNull<u32>,
+    },
+    Sparse(NonNull<ComponentSparseSet>),
+}
+
+#[derive(Copy, Clone)]
+struct FetchColumn {
+    column: Column,
+    size: usize,
+    access: Access,
+}
+
+/// The columns of an archetype matched by a `DynamicQuery`
+struct ArchetypeMatch {
+    archetype: u32,
+    fetch: Vec<FetchColumn>,
+    // sparse set components are only known per entity
+    with_sparse: Vec<NonNull<ComponentSparseSet>>,
+    without_sparse: Vec<NonNull<ComponentSparseSet>>,
+}
+
+/// A borrow of a `World` sufficient to execute a `DynamicQuery`
+///
+/// Note that borrows are not released until this object is dropped.
+pub struct DynamicQueryBorrow<'w> {
+    archetypes: &'w [Archetype],
+    query: &'w DynamicQuery,
+    ticks: ChangeTicks,
+    matches: Vec<ArchetypeMatch>,
+    borrowed: bool,
+}
+
+impl<'w> DynamicQueryBorrow<'w> {
+    pub(crate) fn new(
+        archetypes: &'w [Archetype],
+        query: &'w DynamicQuery,
+        ticks: ChangeTicks,
+    ) -> Self {
+        Self {
+            archetypes,
+            query,
+            ticks,
+            matches: Vec::new(),
+            borrowed: false,
+        }
+    }
+
+    /// Execute the query
+    ///
+    /// Must be called only once per query.
+    pub fn iter<'q>(&'q mut self) -> DynamicQueryIter<'q> {
+        self.borrow();
+        DynamicQueryIter {
+            archetypes: self.archetypes,
+            matches: &self.matches,
+            ticks: self.ticks,
+            match_index: 0,
+            index: 0,
+        }
+    }
+
+    fn borrow(&mut self) {
+        if self.borrowed {
+            panic!(
+                "called DynamicQueryBorrow::iter twice on the same borrow; construct a new query instead"
+            );
+        }
+        for (archetype_index, archetype) in self.archetypes.iter().enumerate() {
+            if self.query.access(archetype).is_none() {
+                continue;
+            }
+            let mut fetch = Vec::with_capacity(self.query.fetch.len());
+            for &(id, access) in self.query.fetch.iter() {
+                if access == Access::Write {
+                    archetype.borrow_mut_dynamic(id);
+                } else {
+                    archetype.borrow_dynamic(id);
+                }
+                let (column, size) = match archetype.get_dynamic_with_added_and_mutated(id) {
+                    Some((data, added, mutated)) => {
+                        let size = archetype
+                            .types()
+                            .iter()
+                            .find(|ty| ty.id() == id)
+                            .unwrap()
+                            .layout()
+                            .size();
+                        (
+                            Column::Table {
+                                data,
+                                added,
+                                mutated,
+                            },
+                            size,
+                        )
+                    }
+                    None => {
+                        let set = archetype.get_sparse_dynamic(id).unwrap();
+                        let size = unsafe { set.as_ref().type_info().layout().size() };
+                        (Column::Sparse(set), size)
+                    }
+                };
+                fetch.push(FetchColumn {
+                    column,
+                    size,
+                    access,
+                });
+            }
+            let with_sparse = self
+                .query
+                .with
+                .iter()
+                .filter(|&&id| !archetype.has_dynamic(id))
+                .filter_map(|&id| archetype.get_sparse_dynamic(id))
+                .collect();
+            let without_sparse = self
+                .query
+                .without
+                .iter()
+                .filter_map(|&id| archetype.get_sparse_dynamic(id))
+                .collect();
+            self.matches.push(ArchetypeMatch {
+                archetype: archetype_index as u32,
+                fetch,
+                with_sparse,
+                without_sparse,
+            });
+        }
+        self.borrowed = true;
+    }
+}
+
+unsafe impl Send for DynamicQueryBorrow<'_> {}
+unsafe impl Sync for DynamicQueryBorrow<'_> {}
+
+impl Drop for DynamicQueryBorrow<'_> {
+    fn drop(&mut self) {
+        for archetype_match in self.matches.iter() {
+            let archetype = &self.archetypes[archetype_match.archetype as usize];
+            for &(id, access) in self.query.fetch.iter() {
+                if access == Access::Write {
+                    archetype.release_mut_dynamic(id);
+                } else {
+                    archetype.release_dynamic(id);
+                }
+            }
+        }
+    }
+}
+
+/// Iterator over the entities matched by a `DynamicQuery`
+pub struct DynamicQueryIter<'q> {
+    archetypes: &'q [Archetype],
+    matches: &'q [ArchetypeMatch],
+    ticks: ChangeTicks,
+    match_index: usize,
+    index: u32,
+}
+
+impl<'q> Iterator for DynamicQueryIter<'q> {
+    type Item = DynamicItem<'q>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let archetype_match = self.matches.get(self.match_index)?;
+            let archetype = &self.archetypes[archetype_match.archetype as usize];
+            if self.index >= archetype.len() {
+                self.match_index += 1;
+                self.index = 0;
+                continue;
+            }
+            let index = self.index;
+            self.index += 1;
+            let entity = archetype.entity_id(index);
+            let has = |set: &NonNull<ComponentSparseSet>| unsafe { set.as_ref() }.contains(entity);
+            let matches = archetype_match.fetch.iter().all(|x| match x.column {
+                Column::Table { .. } => true,
+                Column::Sparse(ref set) => has(set),
+            }) && archetype_match.with_sparse.iter().all(has)
+                && !archetype_match.without_sparse.iter().any(has);
+            if matches {
+                return Some(DynamicItem {
+                    entity,
+                    index,
+                    columns: &archetype_match.fetch,
+                    ticks: self.ticks,
+                });
+            }
+        }
+    }
+}
+
+/// The components of one entity matched by a `DynamicQuery`
+pub struct DynamicItem<'q> {
+    entity: Entity,
+    index: u32,
+    columns: &'q [FetchColumn],
+    ticks: ChangeTicks,
+}
+
+impl DynamicItem<'_> {
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Returns a pointer to the `i`th fetched component, which may only be read from
+    ///
+    /// Panics if the query fetches fewer than `i + 1` components.
+    pub fn get(&self, i: usize) -> NonNull<u8> {
+        self.component(i).0
+    }
+
+    /// Returns a pointer to the `i`th fetched component and marks it as mutated
+    ///
+    /// Panics if the query fetches fewer than `i + 1` components or if the `i`th component was
+    /// added with `read`.
+    pub fn get_mut(&mut self, i: usize) -> NonNull<u8> {
+        assert_eq!(
+            self.columns[i].access,
+            Access::Write,
+            "component {} of the dynamic query is read-only",
+            i
+        );
+        let (component, _added, mutated) = self.component(i);
+        unsafe { *mutated.as_ptr() = self.ticks.change_tick };
+        component
+    }
+
+    /// Whether the `i`th fetched component was added since the last time the trackers were cleared
+    pub fn added(&self, i: usize) -> bool {
+        let (_component, added, _mutated) = self.component(i);
+        self.ticks.is_newer(unsafe { *added.as_ptr() })
+    }
+
+    /// Whether the `i`th fetched component was mutated since the last time the trackers were
+    /// cleared
+    pub fn mutated(&self, i: usize) -> bool {
+        let (_component, _added, mutated) = self.component(i);
+        self.ticks.is_newer(unsafe { *mutated.as_ptr() })
+    }
+
+    fn component(&self, i: usize) -> (NonNull<u8>, NonNull<u32>, NonNull<u32>) {
+        let column = &self.columns[i];
+        let index = self.index as usize;
+        unsafe {
+            match column.column {
+                Column::Table {
+                    data,
+                    added,
+                    mutated,
+                } => (
+                    NonNull::new_unchecked(data.as_ptr().add(column.size * index)),
+                    NonNull::new_unchecked(added.as_ptr().add(index)),
+                    NonNull::new_unchecked(mutated.as_ptr().add(index)),
+                ),
+                Column::Sparse(set) => set.as_ref().get_with_trackers(self.entity).unwrap(),
+            }
+        }
+    }
+}
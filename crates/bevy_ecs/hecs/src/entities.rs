@@ -1,27 +1,64 @@
 // modified by Bevy contributors
 
-use bevy_utils::HashMap;
-use core::fmt;
+use crate::{
+    alloc::{sync::Arc, vec::Vec},
+    borrow::AtomicBorrow,
+};
+use core::{
+    cell::UnsafeCell,
+    convert::TryFrom,
+    fmt, hint, mem,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicI64, Ordering},
+};
 #[cfg(feature = "std")]
 use std::error::Error;
 
 /// Lightweight unique ID of an entity
 ///
 /// Obtained from `World::spawn`. Can be stored to refer to an entity in the future.
-#[derive(Debug, Clone, Copy, Hash, Eq, Ord, PartialEq, PartialOrd)]
-pub struct Entity(u128);
+///
+/// An entity is a dense index paired with a generation. When an entity is despawned its index is
+/// reused by later entities with a new generation, so a stale `Entity` never refers to a different
+/// live entity.
+#[derive(Clone, Copy, Hash, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Entity {
+    pub(crate) generation: u32,
+    pub(crate) id: u32,
+}
 
-#[allow(clippy::new_without_default)]
 impl Entity {
-    #[allow(missing_docs)]
-    pub fn new() -> Self {
-        Self(rand::random::<u128>())
+    /// Creates a new entity reference with a generation of 0
+    ///
+    /// Only useful for placeholder values and for referring to entities created with
+    /// `World::spawn_as_entity`; ordinary entities should be obtained from `World::spawn`.
+    pub fn new(id: u32) -> Entity {
+        Self { id, generation: 0 }
     }
 
-    #[allow(missing_docs)]
-    #[inline]
-    pub fn from_id(id: u128) -> Self {
-        Self(id)
+    /// Creates an entity reference from the values returned by `id` and `generation`
+    pub fn with_generation(id: u32, generation: u32) -> Entity {
+        Self { id, generation }
+    }
+
+    /// Convert to a form convenient for passing outside of Rust
+    ///
+    /// Only useful for identifying entities within the same instance of an application. Do not use
+    /// for serialization between runs.
+    ///
+    /// No particular structure is guaranteed for the returned bits.
+    pub fn to_bits(self) -> u64 {
+        u64::from(self.generation) << 32 | u64::from(self.id)
+    }
+
+    /// Reconstruct an `Entity` previously destructured with `to_bits`
+    ///
+    /// Only useful when applied to results from `to_bits` in the same instance of an application.
+    pub fn from_bits(bits: u64) -> Self {
+        Self {
+            generation: (bits >> 32) as u32,
+            id: bits as u32,
+        }
     }
 
     /// Extract a transiently unique identifier
@@ -30,62 +67,372 @@ impl Entity {
     /// with both live and dead entities. Useful for compactly representing entities within a
     /// specific snapshot of the world, such as when serializing.
     #[inline]
-    pub fn id(self) -> u128 {
-        self.0
+    pub fn id(self) -> u32 {
+        self.id
+    }
+
+    /// Returns the generation of this entity's id. The generation is incremented each time an
+    /// entity with a given id is despawned.
+    #[inline]
+    pub fn generation(self) -> u32 {
+        self.generation
     }
 }
 
-#[derive(Default)]
+impl fmt::Debug for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}v{}", self.id, self.generation)
+    }
+}
+
+/// Reserves entity ids in a [World](crate::World) from any thread
+///
+/// Obtained from `World::get_entity_reserver`. Reserved entities are spawned without components
+/// the next time the world is flushed. A reserver can outlive its world, but the entities it
+/// reserves after that are never spawned.
+#[derive(Debug, Clone)]
+pub struct EntityReserver {
+    shared: Arc<SharedEntities>,
+}
+
+impl EntityReserver {
+    /// Reserves an entity id. See `World::reserve_entity`.
+    pub fn reserve_entity(&self) -> Entity {
+        self.shared.reserve_entity()
+    }
+}
+
+/// Allocates entity ids and tracks where each live entity is stored
+///
+/// Ids can be reserved through a shared reference with `reserve_entity`, which makes it possible
+/// to hand out ids while systems are running. Reserved ids become live entities the next time the
+/// `World` is flushed.
+#[derive(Debug, Default)]
 pub(crate) struct Entities {
-    pub entity_locations: HashMap<Entity, Location>,
+    pub meta: Vec<EntityMeta>,
+    // the state needed to reserve ids, which is shared with every `EntityReserver`
+    shared: Arc<SharedEntities>,
+    len: u32,
 }
 
 impl Entities {
-    /// Destroy an entity, allowing it to be reused
+    /// Reserve an entity id concurrently
     ///
-    /// Must not be called on reserved entities prior to `flush`.
-    pub fn free(&mut self, entity: Entity) -> Result<Location, NoSuchEntity> {
-        if let Some(location) = self.entity_locations.remove(&entity) {
-            Ok(location)
+    /// Storage for the entity is allocated by the next call to `flush`.
+    pub fn reserve_entity(&self) -> Entity {
+        self.shared.reserve_entity()
+    }
+
+    pub fn reserver(&self) -> EntityReserver {
+        EntityReserver {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Allocate an entity id directly
+    ///
+    /// Must not be called while reserved entities are awaiting `flush`.
+    pub fn alloc(&mut self) -> Entity {
+        let mut pending = self.shared.lock();
+        pending.verify_flushed();
+
+        self.len += 1;
+        if let Some(entity) = pending.entities.pop() {
+            pending.reset_free_cursor();
+            entity
         } else {
-            Err(NoSuchEntity)
+            let id = u32::try_from(self.meta.len()).expect("too many entities");
+            self.meta.push(EntityMeta::EMPTY);
+            pending.meta_len = self.meta.len();
+            Entity { generation: 0, id }
+        }
+    }
+
+    /// Allocate a specific entity id, overwriting its generation
+    ///
+    /// Returns the location of the entity currently using the given id, if any. Must not be
+    /// called while reserved entities are awaiting `flush`.
+    pub fn alloc_at(&mut self, entity: Entity) -> Option<Location> {
+        let mut pending = self.shared.lock();
+        pending.verify_flushed();
+
+        let loc = if entity.id as usize >= self.meta.len() {
+            let free_ids = (self.meta.len() as u32)..entity.id;
+            pending
+                .entities
+                .extend(free_ids.map(|id| Entity { generation: 0, id }));
+            pending.reset_free_cursor();
+            self.meta.resize(entity.id as usize + 1, EntityMeta::EMPTY);
+            pending.meta_len = self.meta.len();
+            self.len += 1;
+            None
+        } else if let Some(index) = pending
+            .entities
+            .iter()
+            .position(|item| item.id == entity.id)
+        {
+            pending.entities.swap_remove(index);
+            pending.reset_free_cursor();
+            self.len += 1;
+            None
+        } else {
+            Some(mem::replace(
+                &mut self.meta[entity.id as usize].location,
+                EntityMeta::EMPTY.location,
+            ))
+        };
+
+        self.meta[entity.id as usize].generation = entity.generation;
+
+        loc
+    }
+
+    /// Destroy an entity, allowing its id to be reused
+    ///
+    /// Must not be called while reserved entities are awaiting `flush`.
+    pub fn free(&mut self, entity: Entity) -> Result<Location, NoSuchEntity> {
+        let mut pending = self.shared.lock();
+        pending.verify_flushed();
+
+        let meta = self.meta.get_mut(entity.id as usize).ok_or(NoSuchEntity)?;
+        if meta.generation != entity.generation || meta.location.index == u32::MAX {
+            return Err(NoSuchEntity);
         }
+        meta.generation = meta.generation.wrapping_add(1);
+        let loc = mem::replace(&mut meta.location, EntityMeta::EMPTY.location);
+
+        pending.entities.push(Entity {
+            generation: meta.generation,
+            id: entity.id,
+        });
+        pending.reset_free_cursor();
+        self.len -= 1;
+        Ok(loc)
     }
 
-    /// Ensure `n` at least allocations can succeed without reallocating
+    /// Ensure at least `additional` allocations can succeed without reallocating
     pub fn reserve(&mut self, additional: u32) {
-        self.entity_locations.reserve(additional as usize)
+        let pending = self.shared.lock();
+        pending.verify_flushed();
+
+        let shortfall = additional as i64 - pending.entities.len() as i64;
+        if shortfall > 0 {
+            self.meta.reserve(shortfall as usize);
+        }
     }
 
     pub fn contains(&self, entity: Entity) -> bool {
-        self.entity_locations.contains_key(&entity)
+        self.get(entity).is_ok()
     }
 
+    /// Destroy all entities, bumping the generation of every id so existing `Entity` values are
+    /// invalidated
     pub fn clear(&mut self) {
-        self.entity_locations.clear();
+        let mut pending = self.shared.lock();
+        pending.verify_flushed();
+
+        pending.entities.clear();
+        for (id, meta) in self.meta.iter_mut().enumerate() {
+            if meta.location.index != u32::MAX {
+                meta.generation = meta.generation.wrapping_add(1);
+            }
+            meta.location = EntityMeta::EMPTY.location;
+            pending.entities.push(Entity {
+                generation: meta.generation,
+                id: id as u32,
+            });
+        }
+        pending.reset_free_cursor();
+        self.len = 0;
     }
 
     /// Access the location storage of an entity
+    ///
+    /// Must not be called on reserved entities prior to `flush`.
     pub fn get_mut(&mut self, entity: Entity) -> Result<&mut Location, NoSuchEntity> {
-        self.entity_locations
-            .get_mut(&entity)
-            .ok_or_else(|| NoSuchEntity)
+        let meta = self.meta.get_mut(entity.id as usize).ok_or(NoSuchEntity)?;
+        if meta.generation != entity.generation || meta.location.index == u32::MAX {
+            return Err(NoSuchEntity);
+        }
+        Ok(&mut meta.location)
     }
 
-    /// Access the location storage of an entity
-    pub fn insert(&mut self, entity: Entity, location: Location) {
-        self.entity_locations.insert(entity, location);
+    /// Returns the location of an entity. Reserved entities are not found until after `flush`.
+    pub fn get(&self, entity: Entity) -> Result<Location, NoSuchEntity> {
+        let meta = self.meta.get(entity.id as usize).ok_or(NoSuchEntity)?;
+        if meta.generation != entity.generation || meta.location.index == u32::MAX {
+            return Err(NoSuchEntity);
+        }
+        Ok(meta.location)
     }
 
-    pub fn get(&self, entity: Entity) -> Result<Location, NoSuchEntity> {
-        self.entity_locations
-            .get(&entity)
-            .cloned()
-            .ok_or_else(|| NoSuchEntity)
+    /// Allocates space for entities previously reserved with `reserve_entity`, then initializes
+    /// each one using the supplied function.
+    pub fn flush(&mut self, mut init: impl FnMut(Entity, &mut Location)) {
+        let mut pending = self.shared.lock();
+        let free_cursor = pending.free_cursor();
+
+        let new_free_cursor = if free_cursor >= 0 {
+            free_cursor as usize
+        } else {
+            let old_meta_len = self.meta.len();
+            let new_meta_len = old_meta_len + -free_cursor as usize;
+            self.meta.resize(new_meta_len, EntityMeta::EMPTY);
+            pending.meta_len = self.meta.len();
+
+            self.len += -free_cursor as u32;
+            for (id, meta) in self.meta.iter_mut().enumerate().skip(old_meta_len) {
+                init(
+                    Entity {
+                        generation: meta.generation,
+                        id: id as u32,
+                    },
+                    &mut meta.location,
+                );
+            }
+
+            0
+        };
+
+        self.len += (pending.entities.len() - new_free_cursor) as u32;
+        for entity in pending.entities.drain(new_free_cursor..) {
+            init(entity, &mut self.meta[entity.id as usize].location);
+        }
+        pending.reset_free_cursor();
+    }
+
+    /// The number of live entities
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+}
+
+/// The state of [Entities] that reservations read, shared with every [EntityReserver]
+///
+/// `borrow` is a spin lock around the pending entities. Reservations hold it shared and [Entities]
+/// holds it uniquely while it changes them, so reservations never observe them half-updated. Each
+/// side only holds it for a few instructions, or for a `flush`, so waiting for it is short.
+#[derive(Default)]
+struct SharedEntities {
+    borrow: AtomicBorrow,
+    pending: UnsafeCell<PendingEntities>,
+    // `pending.entities[..free_cursor]` have not been reserved yet. When negative, the free list
+    // ran out and `-free_cursor` brand new ids past `pending.meta_len` were reserved.
+    free_cursor: AtomicI64,
+}
+
+// SAFE: `pending` is only accessed while `borrow` is held
+unsafe impl Sync for SharedEntities {}
+
+impl fmt::Debug for SharedEntities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedEntities")
+            .field("free_cursor", &self.free_cursor)
+            .finish()
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Default)]
+struct PendingEntities {
+    // freed entities, with the generation they will be reused with
+    entities: Vec<Entity>,
+    // the length of `Entities::meta`
+    meta_len: usize,
+}
+
+impl SharedEntities {
+    fn reserve_entity(&self) -> Entity {
+        while !self.borrow.borrow() {
+            hint::spin_loop();
+        }
+        // SAFE: the shared borrow is held until the end of this function
+        let pending = unsafe { &*self.pending.get() };
+        let n = self.free_cursor.fetch_sub(1, Ordering::Relaxed);
+        let entity = if n > 0 {
+            // reuse a freed id
+            pending.entities[(n - 1) as usize]
+        } else {
+            // allocate a new id past the end of `meta`. `n` is negative once the free list runs out.
+            Entity {
+                generation: 0,
+                id: u32::try_from(pending.meta_len as i64 - n).expect("too many entities"),
+            }
+        };
+        self.borrow.release();
+        entity
+    }
+
+    fn lock(&self) -> PendingEntitiesMut<'_> {
+        while !self.borrow.borrow_mut() {
+            hint::spin_loop();
+        }
+        PendingEntitiesMut { shared: self }
+    }
+}
+
+/// Unique access to the pending entities of [SharedEntities]
+struct PendingEntitiesMut<'a> {
+    shared: &'a SharedEntities,
+}
+
+impl PendingEntitiesMut<'_> {
+    fn free_cursor(&self) -> i64 {
+        self.shared.free_cursor.load(Ordering::Relaxed)
+    }
+
+    /// Marks every pending entity as unreserved
+    fn reset_free_cursor(&mut self) {
+        let len = self.entities.len() as i64;
+        self.shared.free_cursor.store(len, Ordering::Relaxed);
+    }
+
+    fn verify_flushed(&self) {
+        debug_assert!(
+            self.free_cursor() == self.entities.len() as i64,
+            "flush() needs to be called before this operation is legal"
+        );
+    }
+}
+
+impl Deref for PendingEntitiesMut<'_> {
+    type Target = PendingEntities;
+
+    fn deref(&self) -> &PendingEntities {
+        // SAFE: the unique borrow is held until this is dropped
+        unsafe { &*self.shared.pending.get() }
+    }
+}
+
+impl DerefMut for PendingEntitiesMut<'_> {
+    fn deref_mut(&mut self) -> &mut PendingEntities {
+        // SAFE: the unique borrow is held until this is dropped
+        unsafe { &mut *self.shared.pending.get() }
+    }
+}
+
+impl Drop for PendingEntitiesMut<'_> {
+    fn drop(&mut self) {
+        self.shared.borrow.release_mut();
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct EntityMeta {
+    pub generation: u32,
+    pub location: Location,
+}
+
+impl EntityMeta {
+    const EMPTY: EntityMeta = EntityMeta {
+        generation: 0,
+        location: Location {
+            archetype: 0,
+            index: u32::MAX, // dummy value, to be filled in
+        },
+    };
+}
+
+#[derive(Copy, Clone, Debug)]
 #[allow(missing_docs)]
 pub struct Location {
     pub archetype: u32,
@@ -104,3 +451,41 @@ impl fmt::Display for NoSuchEntity {
 
 #[cfg(feature = "std")]
 impl Error for NoSuchEntity {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entity_bits_roundtrip() {
+        let e = Entity {
+            generation: 0xDEADBEEF,
+            id: 0xBAADF00D,
+        };
+        assert_eq!(Entity::from_bits(e.to_bits()), e);
+    }
+
+    #[test]
+    fn reserve_entity_len() {
+        let mut e = Entities::default();
+        e.reserve_entity();
+        e.flush(|_, _| {});
+        assert_eq!(e.len(), 1);
+    }
+
+    #[test]
+    fn freed_ids_are_reused_with_new_generation() {
+        let mut e = Entities::default();
+        let a = e.alloc();
+        e.meta[a.id() as usize].location.index = 0;
+        e.free(a).unwrap();
+        assert!(!e.contains(a));
+
+        let b = e.reserve_entity();
+        assert_eq!(b.id(), a.id());
+        assert_ne!(b.generation(), a.generation());
+        e.flush(|_, location| location.index = 0);
+        assert!(e.contains(b));
+        assert!(!e.contains(a));
+    }
+}
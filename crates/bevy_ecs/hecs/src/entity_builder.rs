@@ -23,12 +23,11 @@ use crate::alloc::{
 
 use bevy_utils::HashSet;
 use core::{
-    any::TypeId,
     mem::{self, MaybeUninit},
     ptr,
 };
 
-use crate::{archetype::TypeInfo, Component, DynamicBundle};
+use crate::{archetype::TypeInfo, Component, ComponentId, DynamicBundle};
 
 /// Helper for incrementally constructing a bundle of components with dynamic component types
 ///
@@ -47,8 +46,8 @@ pub struct EntityBuilder {
     storage: Box<[MaybeUninit<u8>]>,
     cursor: usize,
     info: Vec<(TypeInfo, usize)>,
-    ids: Vec<TypeId>,
-    id_set: HashSet<TypeId>,
+    ids: Vec<ComponentId>,
+    id_set: HashSet<ComponentId>,
 }
 
 impl EntityBuilder {
@@ -65,7 +64,7 @@ impl EntityBuilder {
 
     /// Add `component` to the entity
     pub fn add<T: Component>(&mut self, component: T) -> &mut Self {
-        if !self.id_set.insert(TypeId::of::<T>()) {
+        if !self.id_set.insert(ComponentId::of::<T>()) {
             return self;
         }
         let end = self.cursor + mem::size_of::<T>();
@@ -86,6 +85,54 @@ impl EntityBuilder {
         self
     }
 
+    /// Add the component at `component`, described by `info`, to the entity
+    ///
+    /// Used for components that are only known at runtime, see `TypeInfo::external`. The builder
+    /// takes ownership of the component. If the entity already has a component with the same id,
+    /// `component` is dropped instead.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value with the layout of `info`, which must not be used
+    /// afterwards
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_hecs::*;
+    /// # use std::alloc::Layout;
+    /// let info = TypeInfo::external(1, Layout::new::<[u8; 4]>(), |_| {});
+    /// let mut world = World::new();
+    /// let mut builder = EntityBuilder::new();
+    /// let mut value = [1u8, 2, 3, 4];
+    /// unsafe { builder.add_dynamic(info, value.as_mut_ptr()) };
+    /// let e = world.spawn(builder.build());
+    /// let query = DynamicQuery::new().read(info.id());
+    /// let values = world
+    ///     .query_dynamic(&query)
+    ///     .iter()
+    ///     .map(|item| unsafe { *item.get(0).cast::<[u8; 4]>().as_ref() })
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(values, &[[1, 2, 3, 4]]);
+    /// ```
+    pub unsafe fn add_dynamic(&mut self, info: TypeInfo, component: *mut u8) -> &mut Self {
+        if !self.id_set.insert(info.id()) {
+            info.drop(component);
+            return self;
+        }
+        let size = info.layout().size();
+        let end = self.cursor + size;
+        if end > self.storage.len() {
+            self.grow(end);
+        }
+        ptr::copy_nonoverlapping(
+            component,
+            self.storage.as_mut_ptr().add(self.cursor).cast::<u8>(),
+            size,
+        );
+        self.info.push((info, self.cursor));
+        self.cursor += size;
+        self
+    }
+
     fn grow(&mut self, min_size: usize) {
         let new_len = min_size.next_power_of_two().max(64);
         let mut new_storage = vec![MaybeUninit::uninit(); new_len].into_boxed_slice();
@@ -166,7 +213,7 @@ pub struct BuiltEntity<'a> {
 }
 
 impl DynamicBundle for BuiltEntity<'_> {
-    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+    fn with_ids<T>(&self, f: impl FnOnce(&[ComponentId]) -> T) -> T {
         f(&self.builder.ids)
     }
 
@@ -175,7 +222,7 @@ impl DynamicBundle for BuiltEntity<'_> {
         self.builder.info.iter().map(|x| x.0).collect()
     }
 
-    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeId, usize) -> bool) {
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, ComponentId, usize) -> bool) {
         for (ty, offset) in self.builder.info.drain(..) {
             let ptr = self.builder.storage.as_mut_ptr().add(offset).cast();
             if !f(ptr, ty.id(), ty.layout().size()) {
@@ -0,0 +1,241 @@
+// modified by Bevy contributors
+
+use crate::{
+    alloc::{
+        alloc::{alloc, dealloc, handle_alloc_error},
+        boxed::Box,
+        vec::Vec,
+    },
+    archetype::TypeInfo,
+    Component, ComponentId, Entity,
+};
+use bevy_utils::HashMap;
+use core::{any::Any, mem, ptr, ptr::NonNull};
+
+type Hook = Box<dyn FnMut(Entity, *const u8) + Send + Sync>;
+
+/// Lifecycle callbacks and removed values of one component type
+struct ComponentHooks {
+    on_insert: Vec<Hook>,
+    on_remove: Vec<Hook>,
+    removed_values: Option<RemovedValues>,
+}
+
+/// Type-erased `Vec<(Entity, C)>` of removed components that would otherwise have been dropped
+struct RemovedValues {
+    values: Box<dyn Any + Send + Sync>,
+    push: unsafe fn(&mut (dyn Any + Send + Sync), Entity, *mut u8),
+    clear: fn(&mut (dyn Any + Send + Sync)),
+}
+
+impl RemovedValues {
+    fn new<C: Component>() -> Self {
+        unsafe fn push<C: Component>(
+            values: &mut (dyn Any + Send + Sync),
+            entity: Entity,
+            x: *mut u8,
+        ) {
+            values
+                .downcast_mut::<Vec<(Entity, C)>>()
+                .unwrap()
+                .push((entity, ptr::read(x.cast::<C>())));
+        }
+
+        fn clear<C: Component>(values: &mut (dyn Any + Send + Sync)) {
+            values.downcast_mut::<Vec<(Entity, C)>>().unwrap().clear();
+        }
+
+        Self {
+            values: Box::new(Vec::<(Entity, C)>::new()),
+            push: push::<C>,
+            clear: clear::<C>,
+        }
+    }
+}
+
+/// A hook call postponed until the world is consistent again
+enum PendingHook {
+    /// The component at the pointer was just added to the entity. It stays valid until the world is
+    /// mutated again.
+    Inserted(Entity, ComponentId, *const u8),
+    /// The component was removed from the entity and moved into its own allocation, which is owned
+    /// by the pending hook
+    Dropped(Entity, TypeInfo, NonNull<u8>),
+}
+
+/// The component lifecycle hooks registered on a `World`
+#[derive(Default)]
+pub(crate) struct Hooks {
+    components: HashMap<ComponentId, ComponentHooks>,
+    pending: Vec<PendingHook>,
+}
+
+impl Hooks {
+    fn get_or_insert<C: Component>(&mut self) -> &mut ComponentHooks {
+        self.components
+            .entry(ComponentId::of::<C>())
+            .or_insert_with(|| ComponentHooks {
+                on_insert: Vec::new(),
+                on_remove: Vec::new(),
+                removed_values: None,
+            })
+    }
+
+    pub(crate) fn add_on_insert<C: Component>(
+        &mut self,
+        mut hook: impl FnMut(Entity, &C) + Send + Sync + 'static,
+    ) {
+        self.get_or_insert::<C>()
+            .on_insert
+            .push(Box::new(move |entity, x| {
+                hook(entity, unsafe { &*x.cast::<C>() })
+            }));
+    }
+
+    pub(crate) fn add_on_remove<C: Component>(
+        &mut self,
+        mut hook: impl FnMut(Entity, &C) + Send + Sync + 'static,
+    ) {
+        self.get_or_insert::<C>()
+            .on_remove
+            .push(Box::new(move |entity, x| {
+                hook(entity, unsafe { &*x.cast::<C>() })
+            }));
+    }
+
+    pub(crate) fn track_removed_values<C: Component>(&mut self) {
+        let hooks = self.get_or_insert::<C>();
+        if hooks.removed_values.is_none() {
+            hooks.removed_values = Some(RemovedValues::new::<C>());
+        }
+    }
+
+    pub(crate) fn removed_values<C: Component>(&self) -> &[(Entity, C)] {
+        self.components
+            .get(&ComponentId::of::<C>())
+            .and_then(|hooks| hooks.removed_values.as_ref())
+            .map_or(&[], |removed| {
+                removed
+                    .values
+                    .downcast_ref::<Vec<(Entity, C)>>()
+                    .unwrap()
+                    .as_slice()
+            })
+    }
+
+    /// Drops the hooks and removed values of the component type `id`
+    pub(crate) fn remove(&mut self, id: ComponentId) {
+        self.components.remove(&id);
+    }
+
+    /// Drops the removed values of all component types
+    pub(crate) fn clear_removed_values(&mut self) {
+        for hooks in self.components.values_mut() {
+            if let Some(removed) = hooks.removed_values.as_mut() {
+                (removed.clear)(&mut *removed.values);
+            }
+        }
+    }
+
+    pub(crate) fn drain_removed_values<C: Component>(&mut self) -> Vec<(Entity, C)> {
+        self.components
+            .get_mut(&ComponentId::of::<C>())
+            .and_then(|hooks| hooks.removed_values.as_mut())
+            .map_or_else(Vec::new, |removed| {
+                core::mem::take(removed.values.downcast_mut::<Vec<(Entity, C)>>().unwrap())
+            })
+    }
+
+    /// Queues the insert hooks of `ty` for the component at `component`, which was just added to
+    /// `entity`. They run in `run_pending`.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value of type `ty` until `run_pending` is called
+    #[inline]
+    pub(crate) unsafe fn inserted(
+        &mut self,
+        entity: Entity,
+        ty: ComponentId,
+        component: *const u8,
+    ) {
+        if let Some(hooks) = self.components.get(&ty) {
+            if !hooks.on_insert.is_empty() {
+                self.pending
+                    .push(PendingHook::Inserted(entity, ty, component));
+            }
+        }
+    }
+
+    /// Runs the remove hooks of `ty` for the component at `component`, which is about to be moved
+    /// out of `entity`. Has to be called before the world is mutated.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value of type `ty`
+    #[inline]
+    pub(crate) unsafe fn removed(&mut self, entity: Entity, ty: ComponentId, component: *const u8) {
+        if let Some(hooks) = self.components.get_mut(&ty) {
+            for hook in hooks.on_remove.iter_mut() {
+                hook(entity, component);
+            }
+        }
+    }
+
+    /// Takes ownership of the component at `component`, which is being removed from `entity` by the
+    /// world. If `ty` has hooks or removed values, the component is moved out and its remove hooks
+    /// run in `run_pending`, after which it is either kept as a removed value or dropped. Otherwise
+    /// it is dropped right away.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value of type `ty`, which must not be used afterwards
+    #[inline]
+    pub(crate) unsafe fn dropped(&mut self, entity: Entity, ty: &TypeInfo, component: *mut u8) {
+        if !self.components.contains_key(&ty.id()) {
+            ty.drop(component);
+            return;
+        }
+
+        let layout = ty.layout();
+        let moved = if layout.size() == 0 {
+            NonNull::new_unchecked(layout.align() as *mut u8)
+        } else {
+            NonNull::new(alloc(layout)).unwrap_or_else(|| handle_alloc_error(layout))
+        };
+        ptr::copy_nonoverlapping(component, moved.as_ptr(), layout.size());
+        self.pending.push(PendingHook::Dropped(entity, *ty, moved));
+    }
+
+    /// Runs the hooks queued by `inserted` and `dropped`. The world calls this at the end of every
+    /// operation, once its archetypes and sparse sets are consistent again.
+    pub(crate) fn run_pending(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        // if a hook panics, the remaining pending components are leaked rather than dropped twice
+        for pending in mem::take(&mut self.pending) {
+            match pending {
+                PendingHook::Inserted(entity, ty, component) => {
+                    let hooks = self.components.get_mut(&ty).unwrap();
+                    for hook in hooks.on_insert.iter_mut() {
+                        hook(entity, component);
+                    }
+                }
+                PendingHook::Dropped(entity, ty, component) => unsafe {
+                    let hooks = self.components.get_mut(&ty.id()).unwrap();
+                    for hook in hooks.on_remove.iter_mut() {
+                        hook(entity, component.as_ptr());
+                    }
+                    match hooks.removed_values.as_mut() {
+                        Some(removed) => {
+                            (removed.push)(&mut *removed.values, entity, component.as_ptr())
+                        }
+                        None => ty.drop(component.as_ptr()),
+                    }
+                    if ty.layout().size() != 0 {
+                        dealloc(component.as_ptr(), ty.layout());
+                    }
+                },
+            }
+        }
+    }
+}
@@ -67,30 +67,37 @@ macro_rules! smaller_tuples_too {
 mod archetype;
 mod borrow;
 mod bundle;
+mod change_ticks;
+mod dynamic_query;
 mod entities;
 mod entity_builder;
+mod hooks;
 mod query;
 mod query_one;
 #[cfg(feature = "serde")]
 mod serde;
+mod sparse_set;
 mod world;
 
-pub use archetype::Archetype;
+pub use archetype::{Archetype, ComponentId, TypeInfo};
 pub use borrow::{EntityRef, Ref, RefMut};
 pub use bundle::{Bundle, DynamicBundle, MissingComponent};
-pub use entities::{Entity, Location, NoSuchEntity};
+pub use change_ticks::{
+    check_tick, oldest_tick, ChangeTickCounter, ChangeTicks, CHECK_TICK_THRESHOLD, MAX_CHANGE_AGE,
+};
+pub use dynamic_query::{DynamicItem, DynamicQuery, DynamicQueryBorrow, DynamicQueryIter};
+pub use entities::{Entity, EntityReserver, Location, NoSuchEntity};
 pub use entity_builder::{BuiltEntity, EntityBuilder};
 pub use query::{
     Access, Added, BatchedIter, Changed, Mut, Mutated, Or, Query, QueryBorrow, QueryIter, With,
     Without,
 };
 pub use query_one::QueryOne;
+pub use sparse_set::{ComponentSparseSet, ComponentStorage};
 pub use world::{ArchetypesGeneration, Component, ComponentError, Iter, SpawnBatchIter, World};
 
 // Unstable implementation details needed by the macros
 #[doc(hidden)]
-pub use archetype::TypeInfo;
-#[doc(hidden)]
 pub use bevy_utils;
 #[cfg(feature = "macros")]
 #[doc(hidden)]
@@ -15,12 +15,16 @@
 // modified by Bevy contributors
 
 use core::{
+    any::{type_name, TypeId},
     marker::PhantomData,
     ops::{Deref, DerefMut},
     ptr::NonNull,
 };
 
-use crate::{archetype::Archetype, Component, Entity};
+use crate::{
+    archetype::Archetype, entities::Entities, sparse_set::ComponentSparseSet, ChangeTicks,
+    Component, Entity,
+};
 
 /// A collection of component types to fetch from a `World`
 pub trait Query {
@@ -36,24 +40,54 @@ pub trait Fetch<'a>: Sized {
     /// How this query will access `archetype`, if at all
     fn access(archetype: &Archetype) -> Option<Access>;
 
+    /// Calls `f` with the type id, type name and access of every component this query reads or
+    /// writes, whichever archetype it is in. Filters like `With` and `Without` are not taken into
+    /// account.
+    fn component_access(_f: &mut dyn FnMut(TypeId, &'static str, Access)) {}
+
     /// Acquire dynamic borrows from `archetype`
     fn borrow(archetype: &Archetype);
-    /// Construct a `Fetch` for `archetype` if it should be traversed
+    /// Construct a `Fetch` for `archetype` if it should be traversed. Change detection compares
+    /// against `ticks`.
     ///
     /// # Safety
     /// `offset` must be in bounds of `archetype`
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self>;
+    unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self>;
     /// Release dynamic borrows acquired by `borrow`
     fn release(archetype: &Archetype);
 
-    /// if this returns true, the current item will be skipped during iteration
+    /// The sparse set of a component this query requires, if any. Only the entities in that set
+    /// can match, so `QueryIter` visits them instead of every entity of every archetype. Every
+    /// archetype can access every sparse set, so any archetype of the world can be passed.
+    fn sparse_set(_archetype: &Archetype) -> Option<NonNull<ComponentSparseSet>> {
+        None
+    }
+
+    /// if this returns true, the current entity doesn't match the query, for example because it
+    /// lacks a component that is stored in a sparse set
     ///
     /// # Safety
     /// shouldn't be called if there is no current item
+    unsafe fn is_missing(&self) -> bool {
+        false
+    }
+
+    /// if this returns true, the current item will be skipped during iteration
+    ///
+    /// # Safety
+    /// shouldn't be called if there is no current item or if `is_missing` returns true
     unsafe fn should_skip(&self) -> bool {
         false
     }
 
+    /// Move past the current item without accessing it
+    ///
+    /// # Safety
+    /// shouldn't be called if there is no current item
+    unsafe fn skip(&mut self) {
+        self.next();
+    }
+
     /// Access the next item in this archetype without bounds checking
     ///
     /// # Safety
@@ -76,7 +110,7 @@ pub enum Access {
 }
 
 #[derive(Copy, Clone, Debug)]
-pub struct EntityFetch(NonNull<u128>);
+pub struct EntityFetch(NonNull<Entity>);
 
 impl Query for Entity {
     type Fetch = EntityFetch;
@@ -94,7 +128,7 @@ impl<'a> Fetch<'a> for EntityFetch {
     fn borrow(_archetype: &Archetype) {}
 
     #[inline]
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
+    unsafe fn get(archetype: &'a Archetype, offset: usize, _ticks: ChangeTicks) -> Option<Self> {
         Some(EntityFetch(NonNull::new_unchecked(
             archetype.entities().as_ptr().add(offset),
         )))
@@ -107,7 +141,7 @@ impl<'a> Fetch<'a> for EntityFetch {
     unsafe fn next(&mut self) -> Self::Item {
         let id = self.0.as_ptr();
         self.0 = NonNull::new_unchecked(id.add(1));
-        Entity::from_id(*id)
+        *id
     }
 }
 
@@ -115,39 +149,168 @@ impl<'a, T: Component> Query for &'a T {
     type Fetch = FetchRead<T>;
 }
 
+/// Cursor over the entities of an archetype that looks up their components in a sparse set
+#[derive(Copy, Clone)]
+struct SparseCursor {
+    entity: NonNull<Entity>,
+    set: NonNull<ComponentSparseSet>,
+}
+
+impl SparseCursor {
+    unsafe fn get<T: Component>(archetype: &Archetype, offset: usize) -> Option<Self> {
+        archetype.get_sparse::<T>().map(|set| Self {
+            entity: NonNull::new_unchecked(archetype.entities().as_ptr().add(offset)),
+            set,
+        })
+    }
+
+    #[inline]
+    unsafe fn current(&self) -> Option<(NonNull<u8>, NonNull<u32>, NonNull<u32>)> {
+        self.set.as_ref().get_with_trackers(*self.entity.as_ptr())
+    }
+
+    #[inline]
+    unsafe fn advance(&mut self) {
+        self.entity = NonNull::new_unchecked(self.entity.as_ptr().add(1));
+    }
+}
+
+/// Returns the sparse set with fewer entities
+fn smallest_sparse_set(
+    a: Option<NonNull<ComponentSparseSet>>,
+    b: Option<NonNull<ComponentSparseSet>>,
+) -> Option<NonNull<ComponentSparseSet>> {
+    match (a, b) {
+        // SAFE: sparse sets outlive the archetypes that can access them
+        (Some(a), Some(b)) if unsafe { b.as_ref().len() < a.as_ref().len() } => Some(b),
+        (Some(a), _) => Some(a),
+        (None, b) => b,
+    }
+}
+
+/// Component pointers of a `Fetch`, which point either into an archetype's table or to the current
+/// entity's entry in a sparse set
+enum ComponentColumn<T> {
+    Table {
+        value: NonNull<T>,
+        added: NonNull<u32>,
+        mutated: NonNull<u32>,
+    },
+    Sparse(SparseCursor),
+}
+
+impl<T: Component> ComponentColumn<T> {
+    fn has(archetype: &Archetype) -> bool {
+        archetype.has::<T>() || archetype.has_sparse::<T>()
+    }
+
+    unsafe fn get(archetype: &Archetype, offset: usize) -> Option<Self> {
+        if let Some((value, added, mutated)) = archetype.get_with_added_and_mutated::<T>() {
+            Some(ComponentColumn::Table {
+                value: NonNull::new_unchecked(value.as_ptr().add(offset)),
+                added: NonNull::new_unchecked(added.as_ptr().add(offset)),
+                mutated: NonNull::new_unchecked(mutated.as_ptr().add(offset)),
+            })
+        } else {
+            SparseCursor::get::<T>(archetype, offset).map(ComponentColumn::Sparse)
+        }
+    }
+
+    #[inline]
+    unsafe fn is_missing(&self) -> bool {
+        match self {
+            ComponentColumn::Table { .. } => false,
+            ComponentColumn::Sparse(cursor) => cursor.current().is_none(),
+        }
+    }
+
+    /// Returns the current component and its "added" and "mutated" ticks
+    #[inline]
+    unsafe fn current(&self) -> (NonNull<T>, NonNull<u32>, NonNull<u32>) {
+        match self {
+            ComponentColumn::Table {
+                value,
+                added,
+                mutated,
+            } => (*value, *added, *mutated),
+            ComponentColumn::Sparse(cursor) => {
+                let (value, added, mutated) = cursor
+                    .current()
+                    .expect("sparse set component accessed for an entity that doesn't have it");
+                (value.cast::<T>(), added, mutated)
+            }
+        }
+    }
+
+    #[inline]
+    unsafe fn advance(&mut self) {
+        match self {
+            ComponentColumn::Table {
+                value,
+                added,
+                mutated,
+            } => {
+                *value = NonNull::new_unchecked(value.as_ptr().add(1));
+                *added = NonNull::new_unchecked(added.as_ptr().add(1));
+                *mutated = NonNull::new_unchecked(mutated.as_ptr().add(1));
+            }
+            ComponentColumn::Sparse(cursor) => cursor.advance(),
+        }
+    }
+
+    #[inline]
+    unsafe fn next(&mut self) -> (NonNull<T>, NonNull<u32>, NonNull<u32>) {
+        let current = self.current();
+        self.advance();
+        current
+    }
+}
+
 #[doc(hidden)]
-pub struct FetchRead<T>(NonNull<T>);
+pub struct FetchRead<T>(ComponentColumn<T>);
 
 impl<'a, T: Component> Fetch<'a> for FetchRead<T> {
     type Item = &'a T;
 
     fn access(archetype: &Archetype) -> Option<Access> {
-        if archetype.has::<T>() {
+        if ComponentColumn::<T>::has(archetype) {
             Some(Access::Read)
         } else {
             None
         }
     }
 
+    fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+        f(TypeId::of::<T>(), type_name::<T>(), Access::Read);
+    }
+
     fn borrow(archetype: &Archetype) {
         archetype.borrow::<T>();
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        archetype
-            .get::<T>()
-            .map(|x| Self(NonNull::new_unchecked(x.as_ptr().add(offset))))
+    unsafe fn get(archetype: &'a Archetype, offset: usize, _ticks: ChangeTicks) -> Option<Self> {
+        ComponentColumn::get(archetype, offset).map(Self)
     }
 
     fn release(archetype: &Archetype) {
         archetype.release::<T>();
     }
 
+    fn sparse_set(archetype: &Archetype) -> Option<NonNull<ComponentSparseSet>> {
+        archetype.get_sparse::<T>()
+    }
+
+    unsafe fn is_missing(&self) -> bool {
+        self.0.is_missing()
+    }
+
+    unsafe fn skip(&mut self) {
+        self.0.advance();
+    }
+
     #[inline]
     unsafe fn next(&mut self) -> &'a T {
-        let x = self.0.as_ptr();
-        self.0 = NonNull::new_unchecked(x.add(1));
-        &*x
+        &*self.0.next().0.as_ptr()
     }
 }
 
@@ -162,7 +325,8 @@ impl<T: Query> Query for Option<T> {
 /// Unique borrow of an entity's component
 pub struct Mut<'a, T: Component> {
     value: &'a mut T,
-    mutated: &'a mut bool,
+    mutated_tick: &'a mut u32,
+    change_tick: u32,
 }
 
 unsafe impl<T: Component> Send for Mut<'_, T> {}
@@ -180,7 +344,7 @@ impl<'a, T: Component> Deref for Mut<'a, T> {
 impl<'a, T: Component> DerefMut for Mut<'a, T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut T {
-        *self.mutated = true;
+        *self.mutated_tick = self.change_tick;
         self.value
     }
 }
@@ -195,47 +359,54 @@ impl<'a, T: Component> Query for Mut<'a, T> {
     type Fetch = FetchMut<T>;
 }
 #[doc(hidden)]
-pub struct FetchMut<T>(NonNull<T>, NonNull<bool>);
+pub struct FetchMut<T>(ComponentColumn<T>, u32);
 
 impl<'a, T: Component> Fetch<'a> for FetchMut<T> {
     type Item = Mut<'a, T>;
 
     fn access(archetype: &Archetype) -> Option<Access> {
-        if archetype.has::<T>() {
+        if ComponentColumn::<T>::has(archetype) {
             Some(Access::Write)
         } else {
             None
         }
     }
 
+    fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+        f(TypeId::of::<T>(), type_name::<T>(), Access::Write);
+    }
+
     fn borrow(archetype: &Archetype) {
         archetype.borrow_mut::<T>();
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        archetype
-            .get_with_mutated::<T>()
-            .map(|(components, mutated)| {
-                Self(
-                    NonNull::new_unchecked(components.as_ptr().add(offset)),
-                    NonNull::new_unchecked(mutated.as_ptr().add(offset)),
-                )
-            })
+    unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
+        ComponentColumn::get(archetype, offset).map(|column| Self(column, ticks.change_tick))
     }
 
     fn release(archetype: &Archetype) {
         archetype.release_mut::<T>();
     }
 
+    fn sparse_set(archetype: &Archetype) -> Option<NonNull<ComponentSparseSet>> {
+        archetype.get_sparse::<T>()
+    }
+
+    unsafe fn is_missing(&self) -> bool {
+        self.0.is_missing()
+    }
+
+    unsafe fn skip(&mut self) {
+        self.0.advance();
+    }
+
     #[inline]
     unsafe fn next(&mut self) -> Mut<'a, T> {
-        let component = self.0.as_ptr();
-        let mutated = self.1.as_ptr();
-        self.0 = NonNull::new_unchecked(component.add(1));
-        self.1 = NonNull::new_unchecked(mutated.add(1));
+        let (component, _added, mutated) = self.0.next();
         Mut {
-            value: &mut *component,
-            mutated: &mut *mutated,
+            value: &mut *component.as_ptr(),
+            mutated_tick: &mut *mutated.as_ptr(),
+            change_tick: self.1,
         }
     }
 }
@@ -257,14 +428,20 @@ macro_rules! impl_or_query {
                 max_access
             }
 
+            fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+                $(
+                    $T::component_access(f);
+                )+
+            }
+
             fn borrow(archetype: &Archetype) {
                 $(
                     $T::borrow(archetype);
                  )+
             }
 
-            unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-                Some(Self(( $( $T::get(archetype, offset)?),+ )))
+            unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
+                Some(Self(( $( $T::get(archetype, offset, ticks)?),+ )))
             }
 
             fn release(archetype: &Archetype) {
@@ -279,11 +456,23 @@ macro_rules! impl_or_query {
                 ($( $T.next() ),+)
             }
 
+            #[allow(non_snake_case)]
+            unsafe fn is_missing(&self) -> bool {
+                let ($( $T ),+) = &self.0;
+                false $( || $T.is_missing() )+
+            }
+
              #[allow(non_snake_case)]
             unsafe fn should_skip(&self) -> bool {
                 let ($( $T ),+) = &self.0;
                 true $( && $T.should_skip() )+
             }
+
+            #[allow(non_snake_case)]
+            unsafe fn skip(&mut self) {
+                let ($( $T ),+) = &mut self.0;
+                $( $T.skip(); )+
+            }
         }
     };
 }
@@ -343,49 +532,57 @@ impl<'a, T: Component> Query for Mutated<'a, T> {
 }
 
 #[doc(hidden)]
-pub struct FetchMutated<T>(NonNull<T>, NonNull<bool>);
+pub struct FetchMutated<T>(ComponentColumn<T>, ChangeTicks);
 
 impl<'a, T: Component> Fetch<'a> for FetchMutated<T> {
     type Item = Mutated<'a, T>;
 
     fn access(archetype: &Archetype) -> Option<Access> {
-        if archetype.has::<T>() {
+        if ComponentColumn::<T>::has(archetype) {
             Some(Access::Read)
         } else {
             None
         }
     }
 
+    fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+        f(TypeId::of::<T>(), type_name::<T>(), Access::Read);
+    }
+
     fn borrow(archetype: &Archetype) {
         archetype.borrow::<T>();
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        archetype
-            .get_with_mutated::<T>()
-            .map(|(components, mutated)| {
-                Self(
-                    NonNull::new_unchecked(components.as_ptr().add(offset)),
-                    NonNull::new_unchecked(mutated.as_ptr().add(offset)),
-                )
-            })
+    unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
+        ComponentColumn::get(archetype, offset).map(|column| Self(column, ticks))
     }
 
     fn release(archetype: &Archetype) {
         archetype.release::<T>();
     }
 
+    fn sparse_set(archetype: &Archetype) -> Option<NonNull<ComponentSparseSet>> {
+        archetype.get_sparse::<T>()
+    }
+
+    unsafe fn is_missing(&self) -> bool {
+        self.0.is_missing()
+    }
+
     unsafe fn should_skip(&self) -> bool {
-        // skip if the current item wasn't mutated
-        !*self.1.as_ref()
+        // skip if the current item wasn't mutated since the last run
+        !self.1.is_newer(*self.0.current().2.as_ref())
+    }
+
+    unsafe fn skip(&mut self) {
+        self.0.advance();
     }
 
     #[inline]
     unsafe fn next(&mut self) -> Self::Item {
-        self.1 = NonNull::new_unchecked(self.1.as_ptr().add(1));
-        let value = self.0.as_ptr();
-        self.0 = NonNull::new_unchecked(value.add(1));
-        Mutated { value: &*value }
+        Mutated {
+            value: &*self.0.next().0.as_ptr(),
+        }
     }
 }
 
@@ -408,47 +605,57 @@ impl<'a, T: Component> Query for Added<'a, T> {
 }
 
 #[doc(hidden)]
-pub struct FetchAdded<T>(NonNull<T>, NonNull<bool>);
+pub struct FetchAdded<T>(ComponentColumn<T>, ChangeTicks);
 
 impl<'a, T: Component> Fetch<'a> for FetchAdded<T> {
     type Item = Added<'a, T>;
 
     fn access(archetype: &Archetype) -> Option<Access> {
-        if archetype.has::<T>() {
+        if ComponentColumn::<T>::has(archetype) {
             Some(Access::Read)
         } else {
             None
         }
     }
 
+    fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+        f(TypeId::of::<T>(), type_name::<T>(), Access::Read);
+    }
+
     fn borrow(archetype: &Archetype) {
         archetype.borrow::<T>();
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        archetype.get_with_added::<T>().map(|(components, added)| {
-            Self(
-                NonNull::new_unchecked(components.as_ptr().add(offset)),
-                NonNull::new_unchecked(added.as_ptr().add(offset)),
-            )
-        })
+    unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
+        ComponentColumn::get(archetype, offset).map(|column| Self(column, ticks))
     }
 
     fn release(archetype: &Archetype) {
         archetype.release::<T>();
     }
 
+    fn sparse_set(archetype: &Archetype) -> Option<NonNull<ComponentSparseSet>> {
+        archetype.get_sparse::<T>()
+    }
+
+    unsafe fn is_missing(&self) -> bool {
+        self.0.is_missing()
+    }
+
     unsafe fn should_skip(&self) -> bool {
-        // skip if the current item wasn't added
-        !*self.1.as_ref()
+        // skip if the current item wasn't added since the last run
+        !self.1.is_newer(*self.0.current().1.as_ref())
+    }
+
+    unsafe fn skip(&mut self) {
+        self.0.advance();
     }
 
     #[inline]
     unsafe fn next(&mut self) -> Self::Item {
-        self.1 = NonNull::new_unchecked(self.1.as_ptr().add(1));
-        let value = self.0.as_ptr();
-        self.0 = NonNull::new_unchecked(value.add(1));
-        Added { value: &*value }
+        Added {
+            value: &*self.0.next().0.as_ptr(),
+        }
     }
 }
 
@@ -472,51 +679,58 @@ impl<'a, T: Component> Query for Changed<'a, T> {
 }
 
 #[doc(hidden)]
-pub struct FetchChanged<T>(NonNull<T>, NonNull<bool>, NonNull<bool>);
+pub struct FetchChanged<T>(ComponentColumn<T>, ChangeTicks);
 
 impl<'a, T: Component> Fetch<'a> for FetchChanged<T> {
     type Item = Changed<'a, T>;
 
     fn access(archetype: &Archetype) -> Option<Access> {
-        if archetype.has::<T>() {
+        if ComponentColumn::<T>::has(archetype) {
             Some(Access::Read)
         } else {
             None
         }
     }
 
+    fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+        f(TypeId::of::<T>(), type_name::<T>(), Access::Read);
+    }
+
     fn borrow(archetype: &Archetype) {
         archetype.borrow::<T>();
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        archetype
-            .get_with_added_and_mutated::<T>()
-            .map(|(components, added, mutated)| {
-                Self(
-                    NonNull::new_unchecked(components.as_ptr().add(offset)),
-                    NonNull::new_unchecked(added.as_ptr().add(offset)),
-                    NonNull::new_unchecked(mutated.as_ptr().add(offset)),
-                )
-            })
+    unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
+        ComponentColumn::get(archetype, offset).map(|column| Self(column, ticks))
     }
 
     fn release(archetype: &Archetype) {
         archetype.release::<T>();
     }
 
+    fn sparse_set(archetype: &Archetype) -> Option<NonNull<ComponentSparseSet>> {
+        archetype.get_sparse::<T>()
+    }
+
+    unsafe fn is_missing(&self) -> bool {
+        self.0.is_missing()
+    }
+
     unsafe fn should_skip(&self) -> bool {
-        // skip if the current item wasn't added or mutated
-        !*self.1.as_ref() && !self.2.as_ref()
+        // skip if the current item wasn't added or mutated since the last run
+        let (_value, added, mutated) = self.0.current();
+        !self.1.is_newer(*added.as_ref()) && !self.1.is_newer(*mutated.as_ref())
+    }
+
+    unsafe fn skip(&mut self) {
+        self.0.advance();
     }
 
     #[inline]
     unsafe fn next(&mut self) -> Self::Item {
-        self.1 = NonNull::new_unchecked(self.1.as_ptr().add(1));
-        self.2 = NonNull::new_unchecked(self.2.as_ptr().add(1));
-        let value = self.0.as_ptr();
-        self.0 = NonNull::new_unchecked(value.add(1));
-        Changed { value: &*value }
+        Changed {
+            value: &*self.0.next().0.as_ptr(),
+        }
     }
 }
 
@@ -530,12 +744,16 @@ impl<'a, T: Fetch<'a>> Fetch<'a> for TryFetch<T> {
         Some(T::access(archetype).unwrap_or(Access::Iterate))
     }
 
+    fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+        T::component_access(f)
+    }
+
     fn borrow(archetype: &Archetype) {
         T::borrow(archetype)
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        Some(Self(T::get(archetype, offset)))
+    unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
+        Some(Self(T::get(archetype, offset, ticks)))
     }
 
     fn release(archetype: &Archetype) {
@@ -543,11 +761,25 @@ impl<'a, T: Fetch<'a>> Fetch<'a> for TryFetch<T> {
     }
 
     unsafe fn next(&mut self) -> Option<T::Item> {
-        Some(self.0.as_mut()?.next())
+        let fetch = self.0.as_mut()?;
+        if fetch.is_missing() {
+            fetch.skip();
+            None
+        } else {
+            Some(fetch.next())
+        }
     }
 
     unsafe fn should_skip(&self) -> bool {
-        self.0.as_ref().map_or(false, |fetch| fetch.should_skip())
+        self.0
+            .as_ref()
+            .is_some_and(|fetch| !fetch.is_missing() && fetch.should_skip())
+    }
+
+    unsafe fn skip(&mut self) {
+        if let Some(fetch) = self.0.as_mut() {
+            fetch.skip();
+        }
     }
 }
 
@@ -575,7 +807,7 @@ impl<T: Component, Q: Query> Query for Without<T, Q> {
 }
 
 #[doc(hidden)]
-pub struct FetchWithout<T, F>(F, PhantomData<fn(T)>);
+pub struct FetchWithout<T, F>(F, Option<SparseCursor>, PhantomData<fn(T)>);
 
 impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchWithout<T, F> {
     type Item = F::Item;
@@ -588,15 +820,23 @@ impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchWithout<T, F> {
         }
     }
 
+    fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+        F::component_access(f)
+    }
+
     fn borrow(archetype: &Archetype) {
         F::borrow(archetype)
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
+    unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
         if archetype.has::<T>() {
             return None;
         }
-        Some(Self(F::get(archetype, offset)?, PhantomData))
+        Some(Self(
+            F::get(archetype, offset, ticks)?,
+            SparseCursor::get::<T>(archetype, offset),
+            PhantomData,
+        ))
     }
 
     fn release(archetype: &Archetype) {
@@ -604,12 +844,26 @@ impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchWithout<T, F> {
     }
 
     unsafe fn next(&mut self) -> F::Item {
+        if let Some(cursor) = self.1.as_mut() {
+            cursor.advance();
+        }
         self.0.next()
     }
 
+    unsafe fn is_missing(&self) -> bool {
+        matches!(self.1, Some(cursor) if cursor.current().is_some()) || self.0.is_missing()
+    }
+
     unsafe fn should_skip(&self) -> bool {
         self.0.should_skip()
     }
+
+    unsafe fn skip(&mut self) {
+        if let Some(cursor) = self.1.as_mut() {
+            cursor.advance();
+        }
+        self.0.skip()
+    }
 }
 
 /// Query transformer skipping entities that do not have a `T` component
@@ -638,56 +892,88 @@ impl<T: Component, Q: Query> Query for With<T, Q> {
 }
 
 #[doc(hidden)]
-pub struct FetchWith<T, F>(F, PhantomData<fn(T)>);
+pub struct FetchWith<T, F>(F, Option<SparseCursor>, PhantomData<fn(T)>);
 
 impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchWith<T, F> {
     type Item = F::Item;
 
     fn access(archetype: &Archetype) -> Option<Access> {
-        if archetype.has::<T>() {
+        if archetype.has::<T>() || archetype.has_sparse::<T>() {
             F::access(archetype)
         } else {
             None
         }
     }
 
+    fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+        F::component_access(f)
+    }
+
     fn borrow(archetype: &Archetype) {
         F::borrow(archetype)
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        if !archetype.has::<T>() {
-            return None;
-        }
-        Some(Self(F::get(archetype, offset)?, PhantomData))
+    unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
+        let cursor = if archetype.has::<T>() {
+            None
+        } else {
+            Some(SparseCursor::get::<T>(archetype, offset)?)
+        };
+        Some(Self(F::get(archetype, offset, ticks)?, cursor, PhantomData))
     }
 
     fn release(archetype: &Archetype) {
         F::release(archetype)
     }
 
+    fn sparse_set(archetype: &Archetype) -> Option<NonNull<ComponentSparseSet>> {
+        smallest_sparse_set(archetype.get_sparse::<T>(), F::sparse_set(archetype))
+    }
+
     unsafe fn next(&mut self) -> F::Item {
+        if let Some(cursor) = self.1.as_mut() {
+            cursor.advance();
+        }
         self.0.next()
     }
 
+    unsafe fn is_missing(&self) -> bool {
+        matches!(self.1, Some(cursor) if cursor.current().is_none()) || self.0.is_missing()
+    }
+
     unsafe fn should_skip(&self) -> bool {
         self.0.should_skip()
     }
+
+    unsafe fn skip(&mut self) {
+        if let Some(cursor) = self.1.as_mut() {
+            cursor.advance();
+        }
+        self.0.skip()
+    }
 }
 
 /// A borrow of a `World` sufficient to execute the query `Q`
 ///
 /// Note that borrows are not released until this object is dropped.
 pub struct QueryBorrow<'w, Q: Query> {
+    entities: &'w Entities,
     archetypes: &'w [Archetype],
+    ticks: ChangeTicks,
     borrowed: bool,
     _marker: PhantomData<Q>,
 }
 
 impl<'w, Q: Query> QueryBorrow<'w, Q> {
-    pub(crate) fn new(archetypes: &'w [Archetype]) -> Self {
+    pub(crate) fn new(
+        entities: &'w Entities,
+        archetypes: &'w [Archetype],
+        ticks: ChangeTicks,
+    ) -> Self {
         Self {
+            entities,
             archetypes,
+            ticks,
             borrowed: false,
             _marker: PhantomData,
         }
@@ -699,12 +985,32 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
     pub fn iter<'q>(&'q mut self) -> QueryIter<'q, 'w, Q> {
         self.borrow();
         QueryIter {
+            sparse_entities: self.sparse_entities(),
+            sparse_index: 0,
             borrow: self,
             archetype_index: 0,
             iter: None,
         }
     }
 
+    /// The entities of the sparse set required by `Q`, if any
+    fn sparse_entities(&self) -> Option<&'w [Entity]> {
+        let set = Q::Fetch::sparse_set(self.archetypes.first()?)?;
+        // SAFE: sparse sets live as long as the world, and can't change while it is borrowed
+        Some(unsafe { &*(set.as_ref().entities() as *const [Entity]) })
+    }
+
+    /// Fetches the components of `entity`, if it is in an archetype that matches the query
+    ///
+    /// # Safety
+    /// Must only be called after `borrow`
+    unsafe fn get_entity(&self, entity: Entity) -> Option<ChunkIter<Q>> {
+        let location = self.entities.get(entity).ok()?;
+        let archetype = &self.archetypes[location.archetype as usize];
+        Q::Fetch::get(archetype, location.index as usize, self.ticks)
+            .map(|fetch| ChunkIter { fetch, len: 1 })
+    }
+
     /// Like `iter`, but returns child iterators of at most `batch_size` elements
     ///
     /// Useful for distributing work over a threadpool.
@@ -784,7 +1090,9 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
     /// Helper to change the type of the query
     fn transform<R: Query>(mut self) -> QueryBorrow<'w, R> {
         let x = QueryBorrow {
+            entities: self.entities,
             archetypes: self.archetypes,
+            ticks: self.ticks,
             borrowed: self.borrowed,
             _marker: PhantomData,
         };
@@ -823,6 +1131,10 @@ pub struct QueryIter<'q, 'w, Q: Query> {
     borrow: &'q mut QueryBorrow<'w, Q>,
     archetype_index: u32,
     iter: Option<ChunkIter<Q>>,
+    // when `Q` requires a component stored in a sparse set, the entities of that set are visited
+    // instead of the archetypes
+    sparse_entities: Option<&'w [Entity]>,
+    sparse_index: usize,
 }
 
 unsafe impl<'q, 'w, Q: Query> Send for QueryIter<'q, 'w, Q> {}
@@ -833,16 +1145,29 @@ impl<'q, 'w, Q: Query> Iterator for QueryIter<'q, 'w, Q> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(entities) = self.sparse_entities {
+            while let Some(entity) = entities.get(self.sparse_index) {
+                self.sparse_index += 1;
+                if let Some(mut iter) = unsafe { self.borrow.get_entity(*entity) } {
+                    if let Some(components) = unsafe { iter.next() } {
+                        return Some(components);
+                    }
+                }
+            }
+            return None;
+        }
+
         loop {
             match self.iter {
                 None => {
                     let archetype = self.borrow.archetypes.get(self.archetype_index as usize)?;
                     self.archetype_index += 1;
                     unsafe {
-                        self.iter = Q::Fetch::get(archetype, 0).map(|fetch| ChunkIter {
-                            fetch,
-                            len: archetype.len(),
-                        });
+                        self.iter =
+                            Q::Fetch::get(archetype, 0, self.borrow.ticks).map(|fetch| ChunkIter {
+                                fetch,
+                                len: archetype.len(),
+                            });
                     }
                 }
                 Some(ref mut iter) => match unsafe { iter.next() } {
@@ -866,6 +1191,17 @@ impl<'q, 'w, Q: Query> Iterator for QueryIter<'q, 'w, Q> {
 
 impl<'q, 'w, Q: Query> ExactSizeIterator for QueryIter<'q, 'w, Q> {
     fn len(&self) -> usize {
+        if let Some(entities) = self.sparse_entities {
+            return entities
+                .iter()
+                .filter(|entity| unsafe {
+                    self.borrow
+                        .get_entity(**entity)
+                        .is_some_and(|iter| iter.matches())
+                })
+                .count();
+        }
+
         self.borrow
             .archetypes
             .iter()
@@ -888,15 +1224,20 @@ impl<Q: Query> ChunkIter<Q> {
             }
 
             self.len -= 1;
-            if self.fetch.should_skip() {
+            if !self.matches() {
                 // we still need to progress the iterator
-                let _ = self.fetch.next();
+                self.fetch.skip();
                 continue;
             }
 
             break Some(self.fetch.next());
         }
     }
+
+    /// Whether the current entity matches the query
+    unsafe fn matches(&self) -> bool {
+        !self.fetch.is_missing() && !self.fetch.should_skip()
+    }
 }
 
 /// Batched version of `QueryIter`
@@ -922,7 +1263,9 @@ impl<'q, 'w, Q: Query> Iterator for BatchedIter<'q, 'w, Q> {
                 self.batch = 0;
                 continue;
             }
-            if let Some(fetch) = unsafe { Q::Fetch::get(archetype, offset as usize) } {
+            if let Some(fetch) =
+                unsafe { Q::Fetch::get(archetype, offset as usize, self.borrow.ticks) }
+            {
                 self.batch += 1;
                 return Some(Batch {
                     _marker: PhantomData,
@@ -975,19 +1318,31 @@ macro_rules! tuple_impl {
                 Some(access)
             }
 
+            #[allow(unused_variables)]
+            fn component_access(f: &mut dyn FnMut(TypeId, &'static str, Access)) {
+                $($name::component_access(f);)*
+            }
+
             #[allow(unused_variables)]
             fn borrow(archetype: &Archetype) {
                 $($name::borrow(archetype);)*
             }
             #[allow(unused_variables)]
-            unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-                Some(($($name::get(archetype, offset)?,)*))
+            unsafe fn get(archetype: &'a Archetype, offset: usize, ticks: ChangeTicks) -> Option<Self> {
+                Some(($($name::get(archetype, offset, ticks)?,)*))
             }
             #[allow(unused_variables)]
             fn release(archetype: &Archetype) {
                 $($name::release(archetype);)*
             }
 
+            #[allow(unused_mut, unused_variables)]
+            fn sparse_set(archetype: &Archetype) -> Option<NonNull<ComponentSparseSet>> {
+                let mut set = None;
+                $(set = smallest_sparse_set(set, $name::sparse_set(archetype));)*
+                set
+            }
+
             #[allow(unused_variables)]
             unsafe fn next(&mut self) -> Self::Item {
                 #[allow(non_snake_case)]
@@ -995,11 +1350,24 @@ macro_rules! tuple_impl {
                 ($($name.next(),)*)
             }
 
+            unsafe fn is_missing(&self) -> bool {
+                #[allow(non_snake_case)]
+                let ($($name,)*) = self;
+                $($name.is_missing()||)* false
+            }
+
             unsafe fn should_skip(&self) -> bool {
                 #[allow(non_snake_case)]
                 let ($($name,)*) = self;
                 $($name.should_skip()||)* false
             }
+
+            #[allow(unused_variables)]
+            unsafe fn skip(&mut self) {
+                #[allow(non_snake_case)]
+                let ($($name,)*) = self;
+                $($name.skip();)*
+            }
         }
 
         impl<$($name: Query),*> Query for ($($name,)*) {
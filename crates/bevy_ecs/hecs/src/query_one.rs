@@ -4,27 +4,30 @@ use core::marker::PhantomData;
 
 use crate::{
     query::{Fetch, With, Without},
-    Archetype, Component, Query,
+    Archetype, ChangeTicks, Component, Query,
 };
 
 /// A borrow of a `World` sufficient to execute the query `Q` on a single entity
 pub struct QueryOne<'a, Q: Query> {
     archetype: &'a Archetype,
     index: u32,
+    ticks: ChangeTicks,
     borrowed: bool,
     _marker: PhantomData<Q>,
 }
 
 impl<'a, Q: Query> QueryOne<'a, Q> {
-    /// Construct a query accessing the entity in `archetype` at `index`
+    /// Construct a query accessing the entity in `archetype` at `index`, detecting changes made
+    /// after `ticks.last_change_tick`
     ///
     /// # Safety
     ///
     /// `index` must be in-bounds for `archetype`
-    pub(crate) unsafe fn new(archetype: &'a Archetype, index: u32) -> Self {
+    pub unsafe fn new(archetype: &'a Archetype, index: u32, ticks: ChangeTicks) -> Self {
         Self {
             archetype,
             index,
+            ticks,
             borrowed: false,
             _marker: PhantomData,
         }
@@ -41,7 +44,10 @@ impl<'a, Q: Query> QueryOne<'a, Q> {
             panic!("called QueryOnce::get twice; construct a new query instead");
         }
         unsafe {
-            let mut fetch = Q::Fetch::get(self.archetype, self.index as usize)?;
+            let mut fetch = Q::Fetch::get(self.archetype, self.index as usize, self.ticks)?;
+            if fetch.is_missing() {
+                return None;
+            }
             self.borrowed = true;
             Q::Fetch::borrow(self.archetype);
             Some(fetch.next())
@@ -67,6 +73,7 @@ impl<'a, Q: Query> QueryOne<'a, Q> {
         let x = QueryOne {
             archetype: self.archetype,
             index: self.index,
+            ticks: self.ticks,
             borrowed: self.borrowed,
             _marker: PhantomData,
         };
@@ -8,6 +8,6 @@ impl Serialize for Entity {
     where
         S: Serializer,
     {
-        serializer.serialize_u128(self.id())
+        serializer.serialize_u64(self.to_bits())
     }
 }
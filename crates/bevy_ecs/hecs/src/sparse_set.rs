@@ -0,0 +1,261 @@
+// modified by Bevy contributors
+
+use crate::{
+    alloc::{
+        alloc::{alloc, dealloc, Layout},
+        vec::Vec,
+    },
+    archetype::TypeInfo,
+    check_tick, oldest_tick, Entity,
+};
+use core::ptr::{self, NonNull};
+
+/// How the components of a type are stored in a `World`
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum ComponentStorage {
+    /// Components are stored in the tables of their entity's archetype. This is the default and
+    /// gives the fastest iteration, but adding or removing the component moves the entity to another
+    /// archetype.
+    #[default]
+    Table,
+    /// Components are stored in a sparse set outside of the archetypes. Adding and removing the
+    /// component is cheap, but iterating over it is slower. Good for marker components that are
+    /// toggled frequently.
+    SparseSet,
+}
+
+/// Stores the components of a single type that use [ComponentStorage::SparseSet]
+///
+/// Components are packed densely and looked up through an array indexed by entity id.
+pub struct ComponentSparseSet {
+    ty: TypeInfo,
+    data: NonNull<u8>,
+    capacity: usize,
+    entities: Vec<Entity>,
+    sparse: Vec<u32>,
+    added_ticks: Vec<u32>,
+    mutated_ticks: Vec<u32>,
+}
+
+impl ComponentSparseSet {
+    pub(crate) fn new(ty: TypeInfo) -> Self {
+        Self {
+            ty,
+            // a dangling pointer with the right alignment, which is all zero sized types need
+            data: unsafe { NonNull::new_unchecked(ty.layout().align() as *mut u8) },
+            capacity: 0,
+            entities: Vec::new(),
+            sparse: Vec::new(),
+            added_ticks: Vec::new(),
+            mutated_ticks: Vec::new(),
+        }
+    }
+
+    #[allow(missing_docs)]
+    pub fn type_info(&self) -> TypeInfo {
+        self.ty
+    }
+
+    /// The number of entities that have this component
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    #[allow(missing_docs)]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Whether `entity` has this component
+    #[inline]
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.dense_index(entity).is_some()
+    }
+
+    #[allow(missing_docs)]
+    pub fn iter_entities(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.iter()
+    }
+
+    /// The entities that have this component
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    #[inline]
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        let index = *self.sparse.get(entity.id() as usize)? as usize;
+        match self.entities.get(index) {
+            Some(stored) if *stored == entity => Some(index),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    unsafe fn component_ptr(&self, index: usize) -> *mut u8 {
+        self.data.as_ptr().add(index * self.ty.layout().size())
+    }
+
+    /// Returns pointers to the component of `entity` and to its "added" and "mutated" ticks
+    #[inline]
+    pub(crate) fn get_with_trackers(
+        &self,
+        entity: Entity,
+    ) -> Option<(NonNull<u8>, NonNull<u32>, NonNull<u32>)> {
+        let index = self.dense_index(entity)?;
+        unsafe {
+            Some((
+                NonNull::new_unchecked(self.component_ptr(index)),
+                NonNull::new_unchecked(self.added_ticks.as_ptr().add(index) as *mut u32),
+                NonNull::new_unchecked(self.mutated_ticks.as_ptr().add(index) as *mut u32),
+            ))
+        }
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub(crate) fn get(&self, entity: Entity) -> Option<NonNull<u8>> {
+        let index = self.dense_index(entity)?;
+        unsafe { Some(NonNull::new_unchecked(self.component_ptr(index))) }
+    }
+
+    /// Moves the component at `component` into the set. An existing component of `entity` is passed
+    /// to `replaced` and overwritten, otherwise the component is marked as added at `change_tick`.
+    /// Returns a pointer to the stored component.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value of this set's component type, which must not be used
+    /// afterwards. `replaced` must move the existing component out of the pointer or drop it.
+    pub(crate) unsafe fn insert(
+        &mut self,
+        entity: Entity,
+        component: *mut u8,
+        change_tick: u32,
+        replaced: impl FnOnce(*mut u8),
+    ) -> *mut u8 {
+        let size = self.ty.layout().size();
+        if let Some(index) = self.dense_index(entity) {
+            let dst = self.component_ptr(index);
+            replaced(dst);
+            ptr::copy_nonoverlapping(component, dst, size);
+            return dst;
+        }
+
+        if self.entities.len() == self.capacity {
+            self.grow();
+        }
+
+        let index = self.entities.len();
+        ptr::copy_nonoverlapping(component, self.component_ptr(index), size);
+        self.entities.push(entity);
+        self.added_ticks.push(change_tick);
+        self.mutated_ticks.push(oldest_tick(change_tick));
+
+        let id = entity.id() as usize;
+        if id >= self.sparse.len() {
+            self.sparse.resize(id + 1, u32::MAX);
+        }
+        self.sparse[id] = index as u32;
+        self.component_ptr(index)
+    }
+
+    /// Removes the component of `entity` without dropping it, passing a pointer to it to `f` first.
+    /// Returns false if `entity` doesn't have the component.
+    ///
+    /// # Safety
+    /// `f` must move the component out of the pointer or drop it
+    pub(crate) unsafe fn remove(&mut self, entity: Entity, f: impl FnOnce(*mut u8)) -> bool {
+        let index = match self.dense_index(entity) {
+            Some(index) => index,
+            None => return false,
+        };
+        f(self.component_ptr(index));
+
+        let last = self.entities.len() - 1;
+        if index != last {
+            ptr::copy_nonoverlapping(
+                self.component_ptr(last),
+                self.component_ptr(index),
+                self.ty.layout().size(),
+            );
+            let moved = self.entities[last];
+            self.sparse[moved.id() as usize] = index as u32;
+        }
+        self.entities.swap_remove(index);
+        self.added_ticks.swap_remove(index);
+        self.mutated_ticks.swap_remove(index);
+        self.sparse[entity.id() as usize] = u32::MAX;
+        true
+    }
+
+    pub(crate) fn clear(&mut self) {
+        let ty = self.ty;
+        unsafe { self.clear_with(|_, component| ty.drop(component)) }
+    }
+
+    /// Removes all components, passing each of them to `drop`
+    ///
+    /// # Safety
+    /// `drop` must move the component out of the pointer or drop it
+    pub(crate) unsafe fn clear_with(&mut self, mut drop: impl FnMut(Entity, *mut u8)) {
+        for index in 0..self.entities.len() {
+            drop(self.entities[index], self.component_ptr(index));
+        }
+        for entity in self.entities.drain(..) {
+            self.sparse[entity.id() as usize] = u32::MAX;
+        }
+        self.added_ticks.clear();
+        self.mutated_ticks.clear();
+    }
+
+    /// Clamps the "added" and "mutated" ticks of all components. See [check_tick].
+    pub fn check_change_ticks(&mut self, change_tick: u32) {
+        for tick in self
+            .added_ticks
+            .iter_mut()
+            .chain(self.mutated_ticks.iter_mut())
+        {
+            check_tick(tick, change_tick);
+        }
+    }
+
+    fn grow(&mut self) {
+        let new_capacity = (self.capacity * 2).max(64);
+        let size = self.ty.layout().size();
+        if size != 0 {
+            unsafe {
+                let new_data = NonNull::new(alloc(
+                    Layout::from_size_align(size * new_capacity, self.ty.layout().align()).unwrap(),
+                ))
+                .unwrap();
+                ptr::copy_nonoverlapping(
+                    self.data.as_ptr(),
+                    new_data.as_ptr(),
+                    size * self.entities.len(),
+                );
+                self.dealloc_data();
+                self.data = new_data;
+            }
+        }
+        self.capacity = new_capacity;
+    }
+
+    unsafe fn dealloc_data(&mut self) {
+        let size = self.ty.layout().size();
+        if size != 0 && self.capacity != 0 {
+            dealloc(
+                self.data.as_ptr(),
+                Layout::from_size_align_unchecked(size * self.capacity, self.ty.layout().align()),
+            );
+        }
+    }
+}
+
+impl Drop for ComponentSparseSet {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe {
+            self.dealloc_data();
+        }
+    }
+}
@@ -14,18 +14,23 @@
 
 // modified by Bevy contributors
 
-use crate::alloc::vec::Vec;
+use crate::alloc::{boxed::Box, vec::Vec};
 use bevy_utils::{HashMap, HashSet};
-use core::{any::TypeId, convert::TryFrom, fmt, mem, ptr};
+use core::{convert::TryFrom, fmt, mem, ptr, ptr::NonNull};
 
 #[cfg(feature = "std")]
 use std::error::Error;
 
 use crate::{
-    archetype::Archetype,
-    entities::{Entities, Location},
-    Bundle, DynamicBundle, Entity, EntityRef, MissingComponent, NoSuchEntity, Query, QueryBorrow,
-    QueryOne, Ref, RefMut,
+    archetype::{Archetype, ComponentId, TypeInfo},
+    check_tick,
+    entities::{Entities, EntityReserver, Location},
+    hooks::Hooks,
+    oldest_tick,
+    sparse_set::{ComponentSparseSet, ComponentStorage},
+    Bundle, ChangeTickCounter, ChangeTicks, DynamicBundle, DynamicQuery, DynamicQueryBorrow,
+    Entity, EntityRef, MissingComponent, NoSuchEntity, Query, QueryBorrow, QueryOne, Ref, RefMut,
+    CHECK_TICK_THRESHOLD,
 };
 
 /// An unordered collection of entities, each having any number of distinctly typed components
@@ -37,11 +42,17 @@ use crate::{
 /// runs, allowing for extremely fast, cache-friendly iteration.
 pub struct World {
     entities: Entities,
-    index: HashMap<Vec<TypeId>, u32>,
-    removed_components: HashMap<TypeId, Vec<Entity>>,
+    index: HashMap<Vec<ComponentId>, u32>,
+    removed_components: HashMap<ComponentId, Vec<Entity>>,
+    // boxed so archetypes can keep pointers to the sets
+    sparse_sets: HashMap<ComponentId, Box<ComponentSparseSet>>,
     #[allow(missing_docs)]
     pub archetypes: Vec<Archetype>,
     archetype_generation: u64,
+    change_tick: ChangeTickCounter,
+    last_change_tick: u32,
+    last_check_tick: u32,
+    hooks: Hooks,
 }
 
 impl World {
@@ -58,6 +69,107 @@ impl World {
             archetypes,
             archetype_generation: 0,
             removed_components: HashMap::default(),
+            sparse_sets: HashMap::default(),
+            change_tick: ChangeTickCounter::default(),
+            last_change_tick: oldest_tick(1),
+            last_check_tick: 1,
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Choose how components of type `T` are stored
+    ///
+    /// Components use [ComponentStorage::Table] unless configured otherwise. Sparse set storage
+    /// makes adding and removing `T` cheap because entities stay in their archetype.
+    ///
+    /// Panics if any entity currently has a `T` component.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_hecs::*;
+    /// struct Selected;
+    /// let mut world = World::new();
+    /// world.set_component_storage::<Selected>(ComponentStorage::SparseSet);
+    /// let e = world.spawn((123,));
+    /// world.insert_one(e, Selected).unwrap();
+    /// assert!(world.get::<Selected>(e).is_ok());
+    /// assert_eq!(world.query::<(&i32, &Selected)>().iter().count(), 1);
+    /// ```
+    pub fn set_component_storage<T: Component>(&mut self, storage: ComponentStorage) {
+        let ty = ComponentId::of::<T>();
+        if storage == self.component_storage::<T>() {
+            return;
+        }
+        let in_use = match self.sparse_sets.get(&ty) {
+            Some(set) => !set.is_empty(),
+            None => self
+                .archetypes
+                .iter()
+                .any(|archetype| archetype.has::<T>() && !archetype.is_empty()),
+        };
+        if in_use {
+            panic!(
+                "cannot change the storage of {} while entities have it",
+                core::any::type_name::<T>()
+            );
+        }
+
+        match storage {
+            ComponentStorage::SparseSet => {
+                let mut set = Box::new(ComponentSparseSet::new(TypeInfo::of::<T>()));
+                let ptr = NonNull::from(set.as_mut());
+                for archetype in self.archetypes.iter_mut() {
+                    archetype.add_sparse(ty, ptr);
+                }
+                self.sparse_sets.insert(ty, set);
+            }
+            ComponentStorage::Table => {
+                for archetype in self.archetypes.iter_mut() {
+                    archetype.remove_sparse(ty);
+                }
+                self.sparse_sets.remove(&ty);
+            }
+        }
+        self.archetype_generation += 1;
+    }
+
+    /// Returns how components of type `T` are stored
+    pub fn component_storage<T: Component>(&self) -> ComponentStorage {
+        if self.sparse_sets.contains_key(&ComponentId::of::<T>()) {
+            ComponentStorage::SparseSet
+        } else {
+            ComponentStorage::Table
+        }
+    }
+
+    /// Returns the sparse set storing components of type `T`, if `T` uses
+    /// [ComponentStorage::SparseSet]
+    pub fn sparse_set<T: Component>(&self) -> Option<&ComponentSparseSet> {
+        self.sparse_sets
+            .get(&ComponentId::of::<T>())
+            .map(|set| set.as_ref())
+    }
+
+    /// Creates an archetype for the table components `info` that can also access all sparse sets
+    fn new_archetype(
+        sparse_sets: &mut HashMap<ComponentId, Box<ComponentSparseSet>>,
+        info: Vec<TypeInfo>,
+    ) -> Archetype {
+        let mut archetype = Archetype::new(info);
+        for (ty, set) in sparse_sets.iter_mut() {
+            archetype.add_sparse(*ty, NonNull::from(set.as_mut()));
+        }
+        archetype
+    }
+
+    /// Returns the table components of `info`, i.e. the ones not stored in sparse sets
+    fn table_types(&self, info: Vec<TypeInfo>) -> Vec<TypeInfo> {
+        if self.sparse_sets.is_empty() {
+            info
+        } else {
+            info.into_iter()
+                .filter(|ty| !self.sparse_sets.contains_key(&ty.id()))
+                .collect()
         }
     }
 
@@ -80,8 +192,9 @@ impl World {
     /// let b = world.spawn((456, true));
     /// ```
     pub fn spawn(&mut self, components: impl DynamicBundle) -> Entity {
-        let entity = Entity::new();
-        self.spawn_as_entity(entity, components);
+        self.flush();
+        let entity = self.entities.alloc();
+        self.spawn_inner(entity, components);
         entity
     }
 
@@ -93,32 +206,62 @@ impl World {
     /// `(x,)`.
     ///
     /// Any type that satisfies `Send + Sync + 'static` can be used as a component.
+    ///
+    /// If an entity with the same id is already alive it is despawned first, regardless of its
+    /// generation.
     pub fn spawn_as_entity(&mut self, entity: Entity, components: impl DynamicBundle) {
-        let archetype_id = components.with_ids(|ids| {
-            self.index.get(ids).copied().unwrap_or_else(|| {
-                let x = self.archetypes.len() as u32;
-                self.archetypes.push(Archetype::new(components.type_info()));
-                self.index.insert(ids.to_vec(), x);
-                self.archetype_generation += 1;
-                x
-            })
-        });
+        self.flush();
+        if let Some(loc) = self.entities.alloc_at(entity) {
+            let previous = self.archetypes[loc.archetype as usize].entity_id(loc.index);
+            self.despawn_at(previous, loc);
+        }
+        self.spawn_inner(entity, components);
+    }
+
+    fn spawn_inner(&mut self, entity: Entity, components: impl DynamicBundle) {
+        let archetype_id = if self.sparse_sets.is_empty() {
+            components.with_ids(|ids| self.index.get(ids).copied())
+        } else {
+            None
+        }
+        .unwrap_or_else(|| self.get_or_add_archetype(components.type_info()));
 
         let archetype = &mut self.archetypes[archetype_id as usize];
+        let sparse_sets = &mut self.sparse_sets;
+        let hooks = &mut self.hooks;
+        let change_tick = self.change_tick.read();
         unsafe {
-            let index = archetype.allocate(entity.id());
+            let index = archetype.allocate(entity);
             components.put(|ptr, ty, size| {
-                archetype.put_dynamic(ptr, ty, size, index, true);
+                let stored = if let Some(set) = sparse_sets.get_mut(&ty) {
+                    set.insert(entity, ptr, change_tick, |_| {})
+                } else {
+                    archetype.put_dynamic(ptr, ty, size, index, Some(change_tick))
+                };
+                hooks.inserted(entity, ty, stored);
                 true
             });
-            self.entities.insert(
-                entity,
-                Location {
-                    archetype: archetype_id,
-                    index,
-                },
-            );
+            self.entities.meta[entity.id as usize].location = Location {
+                archetype: archetype_id,
+                index,
+            };
         }
+        self.hooks.run_pending();
+    }
+
+    /// Returns the archetype storing the table components of `info`, adding it if needed
+    fn get_or_add_archetype(&mut self, info: Vec<TypeInfo>) -> u32 {
+        let info = self.table_types(info);
+        let ids = info.iter().map(|ty| ty.id()).collect::<Vec<_>>();
+        if let Some(x) = self.index.get(&ids) {
+            return *x;
+        }
+        let x = self.archetypes.len() as u32;
+        self.archetypes
+            .push(Self::new_archetype(&mut self.sparse_sets, info));
+        self.index.insert(ids, x);
+        self.archetype_generation += 1;
+        x
     }
 
     /// Efficiently spawn a large number of entities with the same components
@@ -139,6 +282,8 @@ impl World {
         I: IntoIterator,
         I::Item: Bundle,
     {
+        self.flush();
+
         let iter = iter.into_iter();
         let (lower, upper) = iter.size_hint();
         let archetype_id = self.reserve_inner::<I::Item>(
@@ -150,43 +295,59 @@ impl World {
             entities: &mut self.entities,
             archetype_id,
             archetype: &mut self.archetypes[archetype_id as usize],
+            sparse_sets: &mut self.sparse_sets,
+            hooks: &mut self.hooks,
+            change_tick: self.change_tick.read(),
         }
     }
 
     /// Destroy an entity and all its components
     pub fn despawn(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        self.flush();
+
         let loc = self.entities.free(entity)?;
+        self.despawn_at(entity, loc);
+        self.hooks.run_pending();
+        Ok(())
+    }
+
+    /// Drops the components stored at `loc`, which must belong to `entity`
+    fn despawn_at(&mut self, entity: Entity, loc: Location) {
         let archetype = &mut self.archetypes[loc.archetype as usize];
-        if let Some(moved) = unsafe { archetype.remove(loc.index) } {
-            self.entities.get_mut(Entity::from_id(moved)).unwrap().index = loc.index;
+        let hooks = &mut self.hooks;
+        if let Some(moved) =
+            unsafe { archetype.remove(loc.index, |ptr, ty| hooks.dropped(entity, ty, ptr)) }
+        {
+            self.entities.get_mut(moved).unwrap().index = loc.index;
         }
         for ty in archetype.types() {
-            let removed_entities = self
-                .removed_components
-                .entry(ty.id())
-                .or_insert_with(Vec::new);
+            let removed_entities = self.removed_components.entry(ty.id()).or_default();
             removed_entities.push(entity);
         }
-        Ok(())
+        for (ty, set) in self.sparse_sets.iter_mut() {
+            let info = set.type_info();
+            if unsafe { set.remove(entity, |ptr| hooks.dropped(entity, &info, ptr)) } {
+                let removed_entities = self.removed_components.entry(*ty).or_default();
+                removed_entities.push(entity);
+            }
+        }
     }
 
     /// Ensure `additional` entities with exact components `T` can be spawned without reallocating
     pub fn reserve<T: Bundle>(&mut self, additional: u32) {
+        self.flush();
         self.reserve_inner::<T>(additional);
     }
 
     fn reserve_inner<T: Bundle>(&mut self, additional: u32) -> u32 {
         self.entities.reserve(additional);
 
-        let archetype_id = T::with_static_ids(|ids| {
-            self.index.get(ids).copied().unwrap_or_else(|| {
-                let x = self.archetypes.len() as u32;
-                self.archetypes.push(Archetype::new(T::static_type_info()));
-                self.index.insert(ids.to_vec(), x);
-                self.archetype_generation += 1;
-                x
-            })
-        });
+        let archetype_id = if self.sparse_sets.is_empty() {
+            T::with_static_ids(|ids| self.index.get(ids).copied())
+        } else {
+            None
+        }
+        .unwrap_or_else(|| self.get_or_add_archetype(T::static_type_info()));
 
         self.archetypes[archetype_id as usize].reserve(additional);
         archetype_id
@@ -196,24 +357,68 @@ impl World {
     ///
     /// Preserves allocated storage for reuse.
     pub fn clear(&mut self) {
+        self.flush();
+        let hooks = &mut self.hooks;
         for archetype in &mut self.archetypes {
             for ty in archetype.types() {
-                let removed_entities = self
-                    .removed_components
-                    .entry(ty.id())
-                    .or_insert_with(Vec::new);
-                removed_entities.extend(archetype.iter_entities().map(|id| Entity::from_id(*id)));
+                let removed_entities = self.removed_components.entry(ty.id()).or_default();
+                removed_entities.extend(archetype.iter_entities().copied());
             }
-            archetype.clear();
+            unsafe { archetype.clear_with(|entity, ptr, ty| hooks.dropped(entity, ty, ptr)) };
+        }
+        for (ty, set) in self.sparse_sets.iter_mut() {
+            let removed_entities = self.removed_components.entry(*ty).or_default();
+            removed_entities.extend(set.iter_entities().copied());
+            let info = set.type_info();
+            unsafe { set.clear_with(|entity, ptr| hooks.dropped(entity, &info, ptr)) };
         }
         self.entities.clear();
+        self.hooks.run_pending();
     }
 
     /// Whether `entity` still exists
+    ///
+    /// Entities reserved with `reserve_entity` are not considered to exist until the world is
+    /// flushed.
     pub fn contains(&self, entity: Entity) -> bool {
         self.entities.contains(entity)
     }
 
+    /// Reserve an entity id without mutable access to the world
+    ///
+    /// The entity is spawned without any components the next time the world is flushed, which
+    /// happens automatically before any operation that adds or removes entities or components.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.reserve_entity();
+    /// assert!(!world.contains(e));
+    /// world.insert_one(e, 123).unwrap();
+    /// assert_eq!(*world.get::<i32>(e).unwrap(), 123);
+    /// ```
+    pub fn reserve_entity(&self) -> Entity {
+        self.entities.reserve_entity()
+    }
+
+    /// Returns an [EntityReserver] that reserves entity ids in this world from any thread
+    ///
+    /// It exists so command buffers can hand out entity ids while systems run; prefer
+    /// `reserve_entity` elsewhere.
+    pub fn get_entity_reserver(&self) -> EntityReserver {
+        self.entities.reserver()
+    }
+
+    /// Spawn all entities reserved with `reserve_entity` as entities without components
+    pub fn flush(&mut self) {
+        let archetype = &mut self.archetypes[0];
+        self.entities.flush(|entity, location| {
+            location.archetype = 0;
+            location.index = unsafe { archetype.allocate(entity) };
+        });
+    }
+
     /// Efficiently iterate over all entities that have certain components
     ///
     /// Calling `iter` on the returned value yields `(Entity, Q)` tuples, where `Q` is some query
@@ -254,7 +459,20 @@ impl World {
     /// assert!(entities.contains(&(b, 456, false)));
     /// ```
     pub fn query<Q: Query>(&self) -> QueryBorrow<'_, Q> {
-        QueryBorrow::new(&self.archetypes)
+        QueryBorrow::new(&self.entities, &self.archetypes, self.change_ticks())
+    }
+
+    /// Like `query`, but detects changes relative to `ticks` instead of the world's own ticks.
+    /// Systems use this to see every change made since they last ran.
+    pub fn query_with_ticks<Q: Query>(&self, ticks: ChangeTicks) -> QueryBorrow<'_, Q> {
+        QueryBorrow::new(&self.entities, &self.archetypes, ticks)
+    }
+
+    /// Efficiently iterate over all entities that have the components of a `DynamicQuery`
+    ///
+    /// Like `query`, but for components that are only known at runtime. See `DynamicQuery`.
+    pub fn query_dynamic<'a>(&'a self, query: &'a DynamicQuery) -> DynamicQueryBorrow<'a> {
+        DynamicQueryBorrow::new(&self.archetypes, query, self.change_ticks())
     }
 
     /// Prepare a query against a single entity
@@ -278,7 +496,13 @@ impl World {
     /// ```
     pub fn query_one<Q: Query>(&self, entity: Entity) -> Result<QueryOne<'_, Q>, NoSuchEntity> {
         let loc = self.entities.get(entity)?;
-        Ok(unsafe { QueryOne::new(&self.archetypes[loc.archetype as usize], loc.index) })
+        Ok(unsafe {
+            QueryOne::new(
+                &self.archetypes[loc.archetype as usize],
+                loc.index,
+                self.change_ticks(),
+            )
+        })
     }
 
     /// Borrow the `T` component of `entity`
@@ -287,9 +511,6 @@ impl World {
     /// components.
     pub fn get<T: Component>(&self, entity: Entity) -> Result<Ref<'_, T>, ComponentError> {
         let loc = self.entities.get(entity)?;
-        if loc.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
         Ok(unsafe { Ref::new(&self.archetypes[loc.archetype as usize], loc.index)? })
     }
 
@@ -298,19 +519,26 @@ impl World {
     /// Panics if the component is already borrowed from another entity with the same components.
     pub fn get_mut<T: Component>(&self, entity: Entity) -> Result<RefMut<'_, T>, ComponentError> {
         let loc = self.entities.get(entity)?;
-        if loc.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
-        Ok(unsafe { RefMut::new(&self.archetypes[loc.archetype as usize], loc.index)? })
+        Ok(unsafe {
+            RefMut::new(
+                &self.archetypes[loc.archetype as usize],
+                loc.index,
+                self.read_change_tick(),
+            )?
+        })
     }
 
     /// Access an entity regardless of its component types
     ///
     /// Does not immediately borrow any component.
     pub fn entity(&self, entity: Entity) -> Result<EntityRef<'_>, NoSuchEntity> {
-        Ok(match self.entities.get(entity)? {
-            Location { archetype: 0, .. } => EntityRef::empty(),
-            loc => unsafe { EntityRef::new(&self.archetypes[loc.archetype as usize], loc.index) },
+        let loc = self.entities.get(entity)?;
+        Ok(unsafe {
+            EntityRef::new(
+                &self.archetypes[loc.archetype as usize],
+                loc.index,
+                self.read_change_tick(),
+            )
         })
     }
 
@@ -331,16 +559,98 @@ impl World {
     /// assert!(ids.contains(&b));
     /// ```
     pub fn iter(&self) -> Iter<'_> {
-        Iter::new(&self.archetypes, &self.entities)
+        Iter::new(&self.archetypes, &self.entities, self.read_change_tick())
     }
 
     #[allow(missing_docs)]
     pub fn removed<C: Component>(&self) -> &[Entity] {
         self.removed_components
-            .get(&TypeId::of::<C>())
+            .get(&ComponentId::of::<C>())
             .map_or(&[], |entities| entities.as_slice())
     }
 
+    /// Call `hook` whenever a `C` component is added to an entity, including when it replaces an
+    /// existing `C`. The hook runs once the operation adding the component is done.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_hecs::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// let mut world = World::new();
+    /// let inserted = Arc::new(Mutex::new(Vec::new()));
+    /// let log = inserted.clone();
+    /// world.on_insert::<i32>(move |entity, x| log.lock().unwrap().push((entity, *x)));
+    /// let e = world.spawn((123, "abc"));
+    /// world.insert_one(e, 456).unwrap();
+    /// assert_eq!(*inserted.lock().unwrap(), vec![(e, 123), (e, 456)]);
+    /// ```
+    pub fn on_insert<C: Component>(
+        &mut self,
+        hook: impl FnMut(Entity, &C) + Send + Sync + 'static,
+    ) {
+        self.hooks.add_on_insert(hook);
+    }
+
+    /// Call `hook` with the removed value whenever a `C` component is removed from an entity,
+    /// whether by `remove`, `despawn`, `clear` or by being replaced through `insert`
+    ///
+    /// Hooks never run while the world is being modified: values the world drops are passed to
+    /// the hook once the operation is done, and values returned by `remove` right before it starts.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_hecs::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// let mut world = World::new();
+    /// let removed = Arc::new(Mutex::new(Vec::new()));
+    /// let log = removed.clone();
+    /// world.on_remove::<i32>(move |entity, x| log.lock().unwrap().push((entity, *x)));
+    /// let e = world.spawn((123, "abc"));
+    /// world.despawn(e).unwrap();
+    /// assert_eq!(*removed.lock().unwrap(), vec![(e, 123)]);
+    /// ```
+    pub fn on_remove<C: Component>(
+        &mut self,
+        hook: impl FnMut(Entity, &C) + Send + Sync + 'static,
+    ) {
+        self.hooks.add_on_remove(hook);
+    }
+
+    /// Keep `C` components that the world would otherwise drop, so they can be inspected with
+    /// `removed_values` and released with `drain_removed_values`
+    ///
+    /// Components returned by `remove` are owned by the caller and are not kept. Kept values are
+    /// dropped by `clear_trackers`, which bevy calls at the end of every frame, so drain them before
+    /// then to take ownership.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_hecs::*;
+    /// let mut world = World::new();
+    /// world.track_removed_values::<String>();
+    /// let e = world.spawn((String::from("abc"),));
+    /// world.insert_one(e, String::from("def")).unwrap();
+    /// world.despawn(e).unwrap();
+    /// assert_eq!(
+    ///     world.drain_removed_values::<String>(),
+    ///     vec![(e, String::from("abc")), (e, String::from("def"))]
+    /// );
+    /// assert!(world.removed_values::<String>().is_empty());
+    /// ```
+    pub fn track_removed_values<C: Component>(&mut self) {
+        self.hooks.track_removed_values::<C>();
+    }
+
+    /// The `C` components kept since they were last drained. See `track_removed_values`.
+    pub fn removed_values<C: Component>(&self) -> &[(Entity, C)] {
+        self.hooks.removed_values::<C>()
+    }
+
+    /// Take the `C` components kept since they were last drained. See `track_removed_values`.
+    pub fn drain_removed_values<C: Component>(&mut self) -> Vec<(Entity, C)> {
+        self.hooks.drain_removed_values::<C>()
+    }
+
     /// Add `components` to `entity`
     ///
     /// Computational cost is proportional to the number of components `entity` has. If an entity
@@ -364,14 +674,20 @@ impl World {
     ) -> Result<(), NoSuchEntity> {
         use std::collections::hash_map::Entry;
 
+        self.flush();
+
         let loc = self.entities.get_mut(entity)?;
         unsafe {
             // Assemble Vec<TypeInfo> for the final entity
             let arch = &mut self.archetypes[loc.archetype as usize];
             let mut info = arch.types().to_vec();
             for ty in components.type_info() {
+                if self.sparse_sets.contains_key(&ty.id()) {
+                    // replaced by the sparse set itself
+                    continue;
+                }
                 if let Some(ptr) = arch.get_dynamic(ty.id(), ty.layout().size(), loc.index) {
-                    ty.drop(ptr.as_ptr());
+                    self.hooks.dropped(entity, &ty, ptr.as_ptr());
                 } else {
                     info.push(ty);
                 }
@@ -384,20 +700,33 @@ impl World {
                 Entry::Occupied(x) => *x.get(),
                 Entry::Vacant(x) => {
                     let index = self.archetypes.len() as u32;
-                    self.archetypes.push(Archetype::new(info));
+                    self.archetypes
+                        .push(Self::new_archetype(&mut self.sparse_sets, info));
                     x.insert(index);
                     self.archetype_generation += 1;
                     index
                 }
             };
 
+            let sparse_sets = &mut self.sparse_sets;
+            let hooks = &mut self.hooks;
+            let change_tick = self.change_tick.read();
             if target == loc.archetype {
                 // Update components in the current archetype
                 let arch = &mut self.archetypes[loc.archetype as usize];
                 components.put(|ptr, ty, size| {
-                    arch.put_dynamic(ptr, ty, size, loc.index, false);
+                    let stored = if let Some(set) = sparse_sets.get_mut(&ty) {
+                        let info = set.type_info();
+                        set.insert(entity, ptr, change_tick, |old| {
+                            hooks.dropped(entity, &info, old)
+                        })
+                    } else {
+                        arch.put_dynamic(ptr, ty, size, loc.index, None)
+                    };
+                    hooks.inserted(entity, ty, stored);
                     true
                 });
+                self.hooks.run_pending();
                 return Ok(());
             }
 
@@ -407,25 +736,37 @@ impl World {
                 loc.archetype as usize,
                 target as usize,
             );
-            let target_index = target_arch.allocate(entity.id());
+            let target_index = target_arch.allocate(entity);
             loc.archetype = target;
             let old_index = mem::replace(&mut loc.index, target_index);
             if let Some(moved) =
-                source_arch.move_to(old_index, |ptr, ty, size, is_added, is_mutated| {
-                    target_arch.put_dynamic(ptr, ty, size, target_index, false);
+                source_arch.move_to(old_index, |ptr, ty, size, added_tick, mutated_tick| {
+                    target_arch.put_dynamic(ptr, ty, size, target_index, None);
                     let type_state = target_arch.get_type_state_mut(ty).unwrap();
-                    type_state.added_entities[target_index as usize] = is_added;
-                    type_state.mutated_entities[target_index as usize] = is_mutated;
+                    type_state.added_ticks[target_index as usize] = added_tick;
+                    type_state.mutated_ticks[target_index as usize] = mutated_tick;
                 })
             {
-                self.entities.get_mut(Entity::from_id(moved)).unwrap().index = old_index;
+                self.entities.get_mut(moved).unwrap().index = old_index;
             }
 
             components.put(|ptr, ty, size| {
-                target_arch.put_dynamic(ptr, ty, size, target_index, true);
+                let stored = if let Some(set) = sparse_sets.get_mut(&ty) {
+                    let info = set.type_info();
+                    set.insert(entity, ptr, change_tick, |old| {
+                        hooks.dropped(entity, &info, old)
+                    })
+                } else if source_arch.has_dynamic(ty) {
+                    // replaced components keep their change ticks
+                    target_arch.put_dynamic(ptr, ty, size, target_index, None)
+                } else {
+                    target_arch.put_dynamic(ptr, ty, size, target_index, Some(change_tick))
+                };
+                hooks.inserted(entity, ty, stored);
                 true
             });
         }
+        self.hooks.run_pending();
         Ok(())
     }
 
@@ -460,61 +801,219 @@ impl World {
     /// assert_eq!(*world.get::<bool>(e).unwrap(), true);
     /// ```
     pub fn remove<T: Bundle>(&mut self, entity: Entity) -> Result<T, ComponentError> {
-        use std::collections::hash_map::Entry;
+        self.flush();
 
-        let loc = self.entities.get_mut(entity)?;
+        let loc = self.entities.get(entity)?;
         unsafe {
             let removed = T::with_static_ids(|ids| ids.iter().copied().collect::<HashSet<_>>());
-            let info = self.archetypes[loc.archetype as usize]
-                .types()
-                .iter()
-                .cloned()
-                .filter(|x| !removed.contains(&x.id()))
-                .collect::<Vec<_>>();
-            let elements = info.iter().map(|x| x.id()).collect::<Vec<_>>();
-            let target = match self.index.entry(elements) {
-                Entry::Occupied(x) => *x.get(),
-                Entry::Vacant(x) => {
-                    self.archetypes.push(Archetype::new(info));
-                    let index = (self.archetypes.len() - 1) as u32;
-                    x.insert(index);
-                    self.archetype_generation += 1;
-                    index
-                }
-            };
-            let old_index = loc.index;
             let source_arch = &self.archetypes[loc.archetype as usize];
-            let bundle = T::get(|ty, size| source_arch.get_dynamic(ty, size, old_index))?;
-            let (source_arch, target_arch) = index2(
-                &mut self.archetypes,
-                loc.archetype as usize,
-                target as usize,
-            );
-            let target_index = target_arch.allocate(entity.id());
-            loc.archetype = target;
-            loc.index = target_index;
+            let sparse_sets = &mut self.sparse_sets;
+            let bundle = T::get(|ty, size| match sparse_sets.get(&ty) {
+                Some(set) => set.get(entity),
+                None => source_arch.get_dynamic(ty, size, loc.index),
+            })?;
+            // run the hooks while the components are still in place
+            let hooks = &mut self.hooks;
+            for (ty, set) in sparse_sets.iter() {
+                if let (true, Some(ptr)) = (removed.contains(ty), set.get(entity)) {
+                    hooks.removed(entity, *ty, ptr.as_ptr());
+                }
+            }
+            for ty in source_arch.types() {
+                if removed.contains(&ty.id()) {
+                    let ptr = source_arch.get_dynamic(ty.id(), ty.layout().size(), loc.index);
+                    hooks.removed(entity, ty.id(), ptr.unwrap().as_ptr());
+                }
+            }
+
             let removed_components = &mut self.removed_components;
-            if let Some(moved) =
-                source_arch.move_to(old_index, |src, ty, size, is_added, is_mutated| {
-                    // Only move the components present in the target archetype, i.e. the non-removed ones.
-                    if let Some(dst) = target_arch.get_dynamic(ty, size, target_index) {
-                        ptr::copy_nonoverlapping(src, dst.as_ptr(), size);
-                        let state = target_arch.get_type_state_mut(ty).unwrap();
-                        state.added_entities[target_index as usize] = is_added;
-                        state.mutated_entities[target_index as usize] = is_mutated;
-                    } else {
-                        let removed_entities =
-                            removed_components.entry(ty).or_insert_with(Vec::new);
-                        removed_entities.push(entity);
-                    }
-                })
-            {
-                self.entities.get_mut(Entity::from_id(moved)).unwrap().index = old_index;
+            for ty in removed.iter() {
+                if let Some(set) = sparse_sets.get_mut(ty) {
+                    // the component was moved into `bundle`
+                    set.remove(entity, |_| {});
+                    let removed_entities = removed_components.entry(*ty).or_default();
+                    removed_entities.push(entity);
+                }
             }
+            let target = self.archetype_without(loc.archetype, &removed);
+            self.move_to_archetype(entity, loc, target, |_, _, _| {});
             Ok(bundle)
         }
     }
 
+    /// Remove and drop the component identified by `id` from `entity`
+    ///
+    /// Like `remove_one`, for components that are only known at runtime. The component is passed to
+    /// `on_remove` hooks and kept if its removed values are tracked, just like when despawning.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123, "abc"));
+    /// world.remove_dynamic(e, ComponentId::of::<i32>()).unwrap();
+    /// assert!(world.get::<i32>(e).is_err());
+    /// assert!(world.remove_dynamic(e, ComponentId::of::<i32>()).is_err());
+    /// ```
+    pub fn remove_dynamic(
+        &mut self,
+        entity: Entity,
+        id: ComponentId,
+    ) -> Result<(), ComponentError> {
+        self.flush();
+
+        let loc = self.entities.get(entity)?;
+        let hooks = &mut self.hooks;
+        if let Some(set) = self.sparse_sets.get_mut(&id) {
+            let info = set.type_info();
+            if unsafe { set.remove(entity, |ptr| hooks.dropped(entity, &info, ptr)) } {
+                let removed_entities = self.removed_components.entry(id).or_default();
+                removed_entities.push(entity);
+                self.hooks.run_pending();
+                return Ok(());
+            }
+        } else if self.archetypes[loc.archetype as usize].has_dynamic(id) {
+            let mut removed = HashSet::default();
+            removed.insert(id);
+            let target = self.archetype_without(loc.archetype, &removed);
+            unsafe {
+                self.move_to_archetype(entity, loc, target, |hooks, ty, src| {
+                    hooks.dropped(entity, ty, src)
+                });
+            }
+            self.hooks.run_pending();
+            return Ok(());
+        }
+        Err(ComponentError::MissingComponent(MissingComponent::dynamic(
+            id,
+        )))
+    }
+
+    /// Removes every trace of the component type `id` from the world
+    ///
+    /// The components of that type are removed from their entities, and the archetypes, sparse set
+    /// and hooks of the type are discarded, so that no type info or drop function of the type is
+    /// kept. Use this before the code defining a component type is unloaded. Adding components of
+    /// the type again afterwards creates new archetypes for them.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123, "abc"));
+    /// world.purge_component(ComponentId::of::<i32>());
+    /// assert!(world.get::<i32>(e).is_err());
+    /// assert!(world.archetypes().all(|archetype| !archetype.has::<i32>()));
+    /// ```
+    pub fn purge_component(&mut self, id: ComponentId) {
+        self.flush();
+
+        let mut entities = self
+            .archetypes
+            .iter()
+            .filter(|archetype| archetype.has_dynamic(id))
+            .flat_map(|archetype| archetype.iter_entities().copied())
+            .collect::<Vec<_>>();
+        if let Some(set) = self.sparse_sets.get(&id) {
+            entities.extend(set.entities().iter().copied());
+        }
+        for entity in entities {
+            let _ = self.remove_dynamic(entity, id);
+        }
+
+        if self.sparse_sets.remove(&id).is_some() {
+            for archetype in self.archetypes.iter_mut() {
+                archetype.remove_sparse(id);
+            }
+        }
+        // the emptied archetypes are replaced rather than removed, because entity locations refer
+        // to archetypes by index
+        let mut purged = HashSet::default();
+        for index in 0..self.archetypes.len() {
+            if self.archetypes[index].has_dynamic(id) {
+                self.archetypes[index] = Self::new_archetype(&mut self.sparse_sets, Vec::new());
+                purged.insert(index as u32);
+            }
+        }
+        self.index
+            .retain(|_, archetype| !purged.contains(archetype));
+        self.hooks.remove(id);
+        self.archetype_generation += 1;
+    }
+
+    /// Returns the archetype storing the table components of `archetype`, except for `removed`
+    fn archetype_without(&mut self, archetype: u32, removed: &HashSet<ComponentId>) -> u32 {
+        use std::collections::hash_map::Entry;
+
+        let info = self.archetypes[archetype as usize]
+            .types()
+            .iter()
+            .cloned()
+            .filter(|x| !removed.contains(&x.id()))
+            .collect::<Vec<_>>();
+        let elements = info.iter().map(|x| x.id()).collect::<Vec<_>>();
+        match self.index.entry(elements) {
+            Entry::Occupied(x) => *x.get(),
+            Entry::Vacant(x) => {
+                self.archetypes
+                    .push(Self::new_archetype(&mut self.sparse_sets, info));
+                let index = (self.archetypes.len() - 1) as u32;
+                x.insert(index);
+                self.archetype_generation += 1;
+                index
+            }
+        }
+    }
+
+    /// Moves `entity` from `loc` into the `target` archetype, which must store a subset of its
+    /// table components. The components missing from `target` are passed to `removed`.
+    ///
+    /// # Safety
+    /// `removed` must move the component out of the pointer or drop it
+    unsafe fn move_to_archetype(
+        &mut self,
+        entity: Entity,
+        loc: Location,
+        target: u32,
+        mut removed: impl FnMut(&mut Hooks, &TypeInfo, *mut u8),
+    ) {
+        if target == loc.archetype {
+            return;
+        }
+
+        let (source_arch, target_arch) = index2(
+            &mut self.archetypes,
+            loc.archetype as usize,
+            target as usize,
+        );
+        let target_index = target_arch.allocate(entity);
+        *self.entities.get_mut(entity).unwrap() = Location {
+            archetype: target,
+            index: target_index,
+        };
+        let types = source_arch.types().to_vec();
+        let removed_components = &mut self.removed_components;
+        let hooks = &mut self.hooks;
+        if let Some(moved) =
+            source_arch.move_to(loc.index, |src, ty, size, added_tick, mutated_tick| {
+                // Only move the components present in the target archetype, i.e. the non-removed ones.
+                if let Some(dst) = target_arch.get_dynamic(ty, size, target_index) {
+                    ptr::copy_nonoverlapping(src, dst.as_ptr(), size);
+                    let state = target_arch.get_type_state_mut(ty).unwrap();
+                    state.added_ticks[target_index as usize] = added_tick;
+                    state.mutated_ticks[target_index as usize] = mutated_tick;
+                } else {
+                    let info = types.iter().find(|info| info.id() == ty).unwrap();
+                    removed(hooks, info, src);
+                    let removed_entities = removed_components.entry(ty).or_default();
+                    removed_entities.push(entity);
+                }
+            })
+        {
+            self.entities.get_mut(moved).unwrap().index = loc.index;
+        }
+    }
+
     /// Remove the `T` component from `entity`
     ///
     /// See `remove`.
@@ -532,14 +1031,11 @@ impl World {
     /// same component of `entity` may be live simultaneous to the returned reference.
     pub unsafe fn get_unchecked<T: Component>(&self, entity: Entity) -> Result<&T, ComponentError> {
         let loc = self.entities.get(entity)?;
-        if loc.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
         Ok(&*self.archetypes[loc.archetype as usize]
-            .get::<T>()
+            .get_component::<T>(loc.index)
             .ok_or_else(MissingComponent::new::<T>)?
-            .as_ptr()
-            .add(loc.index as usize))
+            .0
+            .as_ptr())
     }
 
     /// Uniquely borrow the `T` component of `entity` without safety checks
@@ -555,14 +1051,11 @@ impl World {
         entity: Entity,
     ) -> Result<&mut T, ComponentError> {
         let loc = self.entities.get(entity)?;
-        if loc.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
         Ok(&mut *self.archetypes[loc.archetype as usize]
-            .get::<T>()
+            .get_component::<T>(loc.index)
             .ok_or_else(MissingComponent::new::<T>)?
-            .as_ptr()
-            .add(loc.index as usize))
+            .0
+            .as_ptr())
     }
 
     /// Inspect the archetypes that entities are organized into
@@ -600,13 +1093,57 @@ impl World {
         self.entities.get(entity).ok()
     }
 
-    /// Clears each entity's tracker state. For example, each entity's component "mutated" state will be reset to `false`.
+    /// The ticks used to detect changes in queries made directly on the world. Changes count as new
+    /// until the next call to `clear_trackers`.
+    pub fn change_ticks(&self) -> ChangeTicks {
+        ChangeTicks::new(self.last_change_tick, self.read_change_tick())
+    }
+
+    /// Returns the current change tick of this world
+    #[inline]
+    pub fn read_change_tick(&self) -> u32 {
+        self.change_tick.read()
+    }
+
+    /// Advances the change tick of this world, returning its previous value. Systems call this
+    /// every time they run.
+    #[inline]
+    pub fn increment_change_tick(&self) -> u32 {
+        self.change_tick.increment()
+    }
+
+    /// Clears the list of removed components and the removed values kept by `track_removed_values`,
+    /// and advances the change tick, so changes made so far
+    /// are no longer reported by queries made directly on the world. Systems keep track of the
+    /// changes they have seen themselves and are not affected.
+    ///
+    /// Also clamps the change ticks of all components from time to time, see
+    /// [check_change_ticks](Self::check_change_ticks).
     pub fn clear_trackers(&mut self) {
+        self.last_change_tick = self.increment_change_tick();
+        self.check_change_ticks();
+        self.removed_components.clear();
+        self.hooks.clear_removed_values();
+    }
+
+    /// Clamps the change ticks of all components if [CHECK_TICK_THRESHOLD] ticks have passed since
+    /// the last check, so old changes don't appear new again once the change tick wraps around.
+    pub fn check_change_ticks(&mut self) {
+        let change_tick = self.read_change_tick();
+        if change_tick.wrapping_sub(self.last_check_tick) < CHECK_TICK_THRESHOLD {
+            return;
+        }
+
         for archetype in self.archetypes.iter_mut() {
-            archetype.clear_trackers();
+            archetype.check_change_ticks(change_tick);
         }
 
-        self.removed_components.clear();
+        for set in self.sparse_sets.values_mut() {
+            set.check_change_ticks(change_tick);
+        }
+
+        check_tick(&mut self.last_change_tick, change_tick);
+        self.last_check_tick = change_tick;
     }
 }
 
@@ -683,15 +1220,17 @@ pub struct Iter<'a> {
     entities: &'a Entities,
     current: Option<&'a Archetype>,
     index: u32,
+    change_tick: u32,
 }
 
 impl<'a> Iter<'a> {
-    fn new(archetypes: &'a [Archetype], entities: &'a Entities) -> Self {
+    fn new(archetypes: &'a [Archetype], entities: &'a Entities, change_tick: u32) -> Self {
         Self {
             archetypes: archetypes.iter(),
             entities,
             current: None,
             index: 0,
+            change_tick,
         }
     }
 }
@@ -716,9 +1255,9 @@ impl<'a> Iterator for Iter<'a> {
                     }
                     let index = self.index;
                     self.index += 1;
-                    let id = current.entity_id(index);
-                    return Some((Entity::from_id(id), unsafe {
-                        EntityRef::new(current, index)
+                    let entity = current.entity_id(index);
+                    return Some((entity, unsafe {
+                        EntityRef::new(current, index, self.change_tick)
                     }));
                 }
             }
@@ -726,7 +1265,7 @@ impl<'a> Iterator for Iter<'a> {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (0, Some(self.entities.entity_locations.len()))
+        (0, Some(self.entities.len() as usize))
     }
 }
 
@@ -763,6 +1302,9 @@ where
     entities: &'a mut Entities,
     archetype_id: u32,
     archetype: &'a mut Archetype,
+    sparse_sets: &'a mut HashMap<ComponentId, Box<ComponentSparseSet>>,
+    hooks: &'a mut Hooks,
+    change_tick: u32,
 }
 
 impl<I> Drop for SpawnBatchIter<'_, I>
@@ -784,21 +1326,28 @@ where
 
     fn next(&mut self) -> Option<Entity> {
         let components = self.inner.next()?;
-        let entity = Entity::new();
+        let entity = self.entities.alloc();
         unsafe {
-            let index = self.archetype.allocate(entity.id());
+            let index = self.archetype.allocate(entity);
+            let archetype = &mut self.archetype;
+            let sparse_sets = &mut self.sparse_sets;
+            let hooks = &mut self.hooks;
+            let change_tick = self.change_tick;
             components.put(|ptr, ty, size| {
-                self.archetype.put_dynamic(ptr, ty, size, index, true);
+                let stored = if let Some(set) = sparse_sets.get_mut(&ty) {
+                    set.insert(entity, ptr, change_tick, |_| {})
+                } else {
+                    archetype.put_dynamic(ptr, ty, size, index, Some(change_tick))
+                };
+                hooks.inserted(entity, ty, stored);
                 true
             });
-            self.entities.insert(
-                entity,
-                Location {
-                    archetype: self.archetype_id,
-                    index,
-                },
-            );
+            self.entities.meta[entity.id as usize].location = Location {
+                archetype: self.archetype_id,
+                index,
+            };
         }
+        self.hooks.run_pending();
         Some(entity)
     }
 
@@ -241,6 +241,75 @@ fn bad_bundle_derive() {
     world.spawn(Foo { x: 42, y: 42 });
 }
 
+#[cfg(feature = "macros")]
+#[derive(Bundle, Debug, PartialEq)]
+struct Inner {
+    x: i32,
+    y: f64,
+}
+
+#[test]
+#[cfg(feature = "macros")]
+fn nested_bundle_derive() {
+    #[derive(Bundle, Debug, PartialEq)]
+    struct Outer {
+        #[bundle]
+        inner: Inner,
+        z: &'static str,
+    }
+
+    #[derive(Bundle, Debug, PartialEq)]
+    struct Tuple(u8, #[bundle] Outer);
+
+    let mut world = World::new();
+    let e = world.spawn(Tuple(
+        7,
+        Outer {
+            inner: Inner { x: 42, y: 1.0 },
+            z: "abc",
+        },
+    ));
+    assert_eq!(*world.get::<i32>(e).unwrap(), 42);
+    assert_eq!(*world.get::<f64>(e).unwrap(), 1.0);
+    assert_eq!(*world.get::<&str>(e).unwrap(), "abc");
+    assert_eq!(*world.get::<u8>(e).unwrap(), 7);
+
+    let f = world.spawn((2i32, "def"));
+    assert_eq!(
+        world.remove::<Outer>(f).unwrap_err().to_string(),
+        "missing f64 component"
+    );
+    assert_eq!(*world.get::<i32>(f).unwrap(), 2);
+
+    assert_eq!(
+        world.remove::<Outer>(e).unwrap(),
+        Outer {
+            inner: Inner { x: 42, y: 1.0 },
+            z: "abc",
+        }
+    );
+    assert!(world.get::<i32>(e).is_err());
+    assert_eq!(*world.get::<u8>(e).unwrap(), 7);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+#[should_panic(expected = "BadOuter has multiple i32 components")]
+fn bad_nested_bundle_derive() {
+    #[derive(Bundle)]
+    struct BadOuter {
+        #[bundle]
+        inner: Inner,
+        x: i32,
+    }
+
+    let mut world = World::new();
+    world.spawn(BadOuter {
+        inner: Inner { x: 1, y: 1.0 },
+        x: 2,
+    });
+}
+
 #[test]
 #[cfg_attr(miri, ignore)]
 fn spawn_many() {
@@ -404,3 +473,471 @@ fn remove_tracking() {
         "world clears result in 'removed component' states"
     );
 }
+
+#[test]
+fn despawned_ids_are_reused_with_new_generation() {
+    let mut world = World::new();
+    let a = world.spawn(("abc", 123));
+    world.despawn(a).unwrap();
+    let b = world.spawn(("def", 456));
+    assert_eq!(a.id(), b.id());
+    assert_ne!(a.generation(), b.generation());
+    assert!(!world.contains(a));
+    assert!(world.get::<i32>(a).is_err());
+    assert_eq!(*world.get::<i32>(b).unwrap(), 456);
+}
+
+#[test]
+fn spawn_as_entity_replaces_live_entity() {
+    let mut world = World::new();
+    let a = world.spawn(("abc", 123));
+    let b = world.spawn(("def", 456));
+    world.spawn_as_entity(a, (true,));
+    assert!(world.get::<i32>(a).is_err());
+    assert!(*world.get::<bool>(a).unwrap());
+    assert_eq!(*world.get::<i32>(b).unwrap(), 456);
+    assert_eq!(world.removed::<i32>(), &[a]);
+
+    let c = Entity::new(10);
+    world.spawn_as_entity(c, (789,));
+    assert_eq!(*world.get::<i32>(c).unwrap(), 789);
+    let d = world.spawn(());
+    assert!(d.id() < 10);
+}
+
+#[test]
+fn reserve_entity() {
+    let mut world = World::new();
+    let reserver = world.get_entity_reserver();
+    let a = reserver.reserve_entity();
+    let b = world.reserve_entity();
+    assert_ne!(a, b);
+    assert!(!world.contains(a));
+    world.flush();
+    assert!(world.contains(a));
+    assert!(world.contains(b));
+    world.insert_one(b, 123).unwrap();
+    assert_eq!(*world.get::<i32>(b).unwrap(), 123);
+    let c = world.spawn(("abc",));
+    assert_ne!(c, a);
+    assert_ne!(c, b);
+}
+
+#[test]
+fn reserve_entities_while_flushing() {
+    let mut world = World::new();
+    let reservers = (0..4)
+        .map(|_| {
+            let reserver = world.get_entity_reserver();
+            std::thread::spawn(move || {
+                (0..1000)
+                    .map(|_| reserver.reserve_entity())
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+    while reservers.iter().any(|reserver| !reserver.is_finished()) {
+        world.flush();
+    }
+    world.flush();
+
+    let mut reserved = reservers
+        .into_iter()
+        .flat_map(|reserver| reserver.join().unwrap())
+        .collect::<Vec<_>>();
+    assert!(reserved.iter().all(|entity| world.contains(*entity)));
+    reserved.sort_by_key(|entity| entity.id());
+    reserved.dedup();
+    assert_eq!(reserved.len(), 4000);
+}
+
+#[test]
+fn entity_reserver_outlives_world() {
+    let reserver = World::new().get_entity_reserver();
+    let a = reserver.reserve_entity();
+    let b = reserver.reserve_entity();
+    assert_ne!(a, b);
+}
+
+#[test]
+fn worlds_have_their_own_change_ticks() {
+    let mut a = World::new();
+    let b = World::new();
+    a.clear_trackers();
+    a.increment_change_tick();
+    assert_eq!(a.read_change_tick(), 3);
+    assert_eq!(b.read_change_tick(), 1);
+}
+
+#[test]
+fn sparse_set_components() {
+    #[derive(Debug, PartialEq)]
+    struct Marker(u32);
+
+    let mut world = World::new();
+    world.set_component_storage::<Marker>(ComponentStorage::SparseSet);
+    assert_eq!(
+        world.component_storage::<Marker>(),
+        ComponentStorage::SparseSet
+    );
+    let a = world.spawn((123, Marker(1)));
+    let b = world.spawn((456,));
+    let c = world.spawn((Marker(3),));
+    let generation = world.archetypes_generation();
+
+    world.insert_one(b, Marker(2)).unwrap();
+    assert_eq!(world.archetypes_generation(), generation);
+    assert_eq!(*world.get::<Marker>(b).unwrap(), Marker(2));
+    assert_eq!(world.sparse_set::<Marker>().unwrap().len(), 3);
+
+    let mut markers = world
+        .query::<(Entity, &Marker)>()
+        .iter()
+        .map(|(e, m)| (e, m.0))
+        .collect::<Vec<_>>();
+    markers.sort_by_key(|(_, m)| *m);
+    assert_eq!(markers, &[(a, 1), (b, 2), (c, 3)]);
+    assert_eq!(world.query::<(&i32, &Marker)>().iter().len(), 2);
+
+    assert_eq!(world.remove_one::<Marker>(a), Ok(Marker(1)));
+    assert!(world.get::<Marker>(a).is_err());
+    assert_eq!(*world.get::<i32>(a).unwrap(), 123);
+    assert_eq!(world.removed::<Marker>(), &[a]);
+    assert_eq!(world.archetypes_generation(), generation);
+
+    let ints = world
+        .query::<(&i32, Option<&Marker>)>()
+        .iter()
+        .map(|(i, m)| (*i, m.map(|m| m.0)))
+        .collect::<Vec<_>>();
+    assert_eq!(ints, &[(123, None), (456, Some(2))]);
+    let without = world
+        .query::<Without<Marker, &i32>>()
+        .iter()
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(without, &[123]);
+    let with = world
+        .query::<With<Marker, &i32>>()
+        .iter()
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(with, &[456]);
+    assert_eq!(world.query::<With<Marker, &i32>>().iter().len(), 1);
+    assert!(world.query_one::<&Marker>(a).unwrap().get().is_none());
+
+    world.despawn(b).unwrap();
+    assert_eq!(world.sparse_set::<Marker>().unwrap().len(), 1);
+    world.clear();
+    assert_eq!(world.sparse_set::<Marker>().unwrap().len(), 0);
+}
+
+#[test]
+fn sparse_set_change_tracking() {
+    struct Marker(u32);
+
+    let mut world = World::new();
+    world.set_component_storage::<Marker>(ComponentStorage::SparseSet);
+    let a = world.spawn((1, Marker(1)));
+    let b = world.spawn((2, Marker(2)));
+    assert_eq!(world.query::<Added<Marker>>().iter().count(), 2);
+
+    world.clear_trackers();
+    assert_eq!(world.query::<Added<Marker>>().iter().count(), 0);
+    world.get_mut::<Marker>(b).unwrap().0 += 1;
+    let mutated = world
+        .query::<(Entity, Mutated<Marker>)>()
+        .iter()
+        .map(|(e, m)| (e, m.0))
+        .collect::<Vec<_>>();
+    assert_eq!(mutated, &[(b, 3)]);
+
+    world.clear_trackers();
+    for mut marker in world.query::<Mut<Marker>>().iter() {
+        marker.0 += 10;
+    }
+    assert_eq!(world.query::<Changed<Marker>>().iter().count(), 2);
+    assert_eq!(world.get::<Marker>(a).unwrap().0, 11);
+}
+
+#[test]
+#[should_panic]
+fn sparse_set_storage_change_with_live_components() {
+    let mut world = World::new();
+    world.spawn((123,));
+    world.set_component_storage::<i32>(ComponentStorage::SparseSet);
+}
+
+#[test]
+fn component_hooks() {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, PartialEq)]
+    struct Marker(u32);
+
+    let mut world = World::new();
+    world.set_component_storage::<Marker>(ComponentStorage::SparseSet);
+    let log = Arc::new(Mutex::new(Vec::new()));
+    for storage in 0..2 {
+        let insert_log = log.clone();
+        let remove_log = log.clone();
+        if storage == 0 {
+            world.on_insert::<i32>(move |e, x| insert_log.lock().unwrap().push(("+", e, *x)));
+            world.on_remove::<i32>(move |e, x| remove_log.lock().unwrap().push(("-", e, *x)));
+        } else {
+            world.on_insert::<Marker>(move |e, x| {
+                insert_log.lock().unwrap().push(("+", e, x.0 as i32))
+            });
+            world.on_remove::<Marker>(move |e, x| {
+                remove_log.lock().unwrap().push(("-", e, x.0 as i32))
+            });
+        }
+    }
+
+    let a = world.spawn((1, Marker(10)));
+    let b = world.spawn_batch(vec![(2, true)]).next().unwrap();
+    world.insert(a, (3, Marker(30))).unwrap();
+    world.insert(b, (4, "abc")).unwrap();
+    assert_eq!(world.remove::<(i32, Marker)>(a), Ok((3, Marker(30))));
+    world.despawn(b).unwrap();
+    let c = world.spawn((5, Marker(50)));
+    world.clear();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            ("+", a, 1),
+            ("+", a, 10),
+            ("+", b, 2),
+            ("-", a, 1),
+            ("+", a, 3),
+            ("-", a, 10),
+            ("+", a, 30),
+            ("-", b, 2),
+            ("+", b, 4),
+            ("-", a, 30),
+            ("-", a, 3),
+            ("-", b, 4),
+            ("+", c, 5),
+            ("+", c, 50),
+            ("-", c, 5),
+            ("-", c, 50),
+        ]
+    );
+}
+
+#[test]
+fn panicking_remove_hook() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let mut world = World::new();
+    world.on_remove::<i32>(|_, x| assert_ne!(*x, 1));
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, false));
+    let c = world.spawn((3, true));
+    assert!(catch_unwind(AssertUnwindSafe(|| world.despawn(a))).is_err());
+
+    // the entity was removed before the hook ran
+    assert!(!world.contains(a));
+    let mut values = world
+        .query::<(Entity, &i32, &bool)>()
+        .iter()
+        .map(|(e, i, b)| (e, *i, *b))
+        .collect::<Vec<_>>();
+    values.sort_by_key(|(_, i, _)| *i);
+    assert_eq!(values, &[(b, 2, false), (c, 3, true)]);
+    world.despawn(b).unwrap();
+    world.clear();
+    assert!(!world.contains(c));
+}
+
+#[test]
+fn removed_values() {
+    #[derive(Debug, PartialEq)]
+    struct Handle(u32);
+
+    let mut world = World::new();
+    world.track_removed_values::<Handle>();
+    let a = world.spawn((Handle(1), 1));
+    let b = world.spawn((Handle(2), 2));
+    let c = world.spawn((Handle(3), 3));
+    world.insert_one(a, Handle(4)).unwrap();
+    world.despawn(b).unwrap();
+    assert_eq!(world.remove_one::<Handle>(c), Ok(Handle(3)));
+    assert_eq!(
+        world.removed_values::<Handle>(),
+        &[(a, Handle(1)), (b, Handle(2))]
+    );
+    world.clear_trackers();
+    assert!(world.removed_values::<Handle>().is_empty());
+
+    world.clear();
+    assert_eq!(world.drain_removed_values::<Handle>(), vec![(a, Handle(4))]);
+    assert!(world.removed_values::<Handle>().is_empty());
+    assert!(world.removed_values::<i32>().is_empty());
+}
+
+#[test]
+fn external_components() {
+    use std::{
+        alloc::Layout,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+    unsafe fn drop_position(_: *mut u8) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+    let position = TypeInfo::external(1, Layout::new::<[f32; 2]>(), drop_position);
+    let velocity = TypeInfo::external(2, Layout::new::<[f32; 2]>(), |_| {});
+
+    let mut world = World::new();
+    let mut builder = EntityBuilder::new();
+    let mut spawn = |world: &mut World, components: &[(TypeInfo, [f32; 2])], number: i32| {
+        for (info, value) in components {
+            let mut value = *value;
+            unsafe { builder.add_dynamic(*info, value.as_mut_ptr().cast()) };
+        }
+        builder.add(number);
+        world.spawn(builder.build())
+    };
+    let a = spawn(
+        &mut world,
+        &[(position, [0.0, 0.0]), (velocity, [1.0, 2.0])],
+        1,
+    );
+    let b = spawn(&mut world, &[(position, [5.0, 5.0])], 2);
+    let c = spawn(
+        &mut world,
+        &[(position, [3.0, 3.0]), (velocity, [1.0, 1.0])],
+        3,
+    );
+
+    world.clear_trackers();
+    let query = DynamicQuery::new()
+        .write(position.id())
+        .read(velocity.id())
+        .without(ComponentId::of::<bool>());
+    world.insert_one(c, true).unwrap();
+    for mut item in world.query_dynamic(&query).iter() {
+        assert_eq!(item.entity(), a);
+        assert!(!item.mutated(0));
+        let velocity = unsafe { *item.get(1).cast::<[f32; 2]>().as_ref() };
+        let position = unsafe { item.get_mut(0).cast::<[f32; 2]>().as_mut() };
+        position[0] += velocity[0];
+        position[1] += velocity[1];
+        assert!(item.mutated(0));
+    }
+
+    let query = DynamicQuery::new()
+        .read(position.id())
+        .read(ComponentId::of::<i32>());
+    let mut positions = world
+        .query_dynamic(&query)
+        .iter()
+        .map(|item| unsafe {
+            (
+                *item.get(1).cast::<i32>().as_ref(),
+                *item.get(0).cast::<[f32; 2]>().as_ref(),
+            )
+        })
+        .collect::<Vec<_>>();
+    positions.sort_by_key(|x| x.0);
+    assert_eq!(
+        positions,
+        &[(1, [1.0, 2.0]), (2, [5.0, 5.0]), (3, [3.0, 3.0])]
+    );
+
+    world.remove_dynamic(a, position.id()).unwrap();
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 1);
+    assert_eq!(world.removed::<i32>(), &[]);
+    assert!(world.remove_dynamic(a, position.id()).is_err());
+    assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+    world.despawn(b).unwrap();
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 2);
+    world.clear();
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn dynamic_query_sparse_set() {
+    struct Marker;
+
+    let mut world = World::new();
+    world.set_component_storage::<Marker>(ComponentStorage::SparseSet);
+    let a = world.spawn((1, Marker));
+    let b = world.spawn((2,));
+    world.spawn((3, Marker));
+
+    let query = DynamicQuery::new()
+        .read(ComponentId::of::<i32>())
+        .without(ComponentId::of::<Marker>());
+    let entities = world
+        .query_dynamic(&query)
+        .iter()
+        .map(|item| item.entity())
+        .collect::<Vec<_>>();
+    assert_eq!(entities, &[b]);
+
+    let query = DynamicQuery::new().read(ComponentId::of::<Marker>());
+    assert_eq!(world.query_dynamic(&query).iter().count(), 2);
+    world
+        .remove_dynamic(a, ComponentId::of::<Marker>())
+        .unwrap();
+    assert_eq!(world.query_dynamic(&query).iter().count(), 1);
+}
+
+#[test]
+#[should_panic]
+fn dynamic_query_illegal_borrow() {
+    let mut world = World::new();
+    world.spawn((123,));
+    let query = DynamicQuery::new().write(ComponentId::of::<i32>());
+    let mut borrow = world.query_dynamic(&query);
+    let _items = borrow.iter();
+    world.query::<&i32>().iter();
+}
+
+#[test]
+fn purge_component() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    struct Dropped(Arc<AtomicUsize>);
+
+    impl Drop for Dropped {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let mut world = World::new();
+    world.set_component_storage::<bool>(ComponentStorage::SparseSet);
+    let a = world.spawn((1, Dropped(drops.clone())));
+    let b = world.spawn((2, "b", Dropped(drops.clone())));
+    let c = world.spawn((3, true));
+
+    world.purge_component(ComponentId::of::<Dropped>());
+    assert_eq!(drops.load(Ordering::Relaxed), 2);
+    assert!(world.get::<Dropped>(a).is_err());
+    assert_eq!(*world.get::<&str>(b).unwrap(), "b");
+    assert!(world
+        .archetypes()
+        .all(|archetype| !archetype.has::<Dropped>()));
+
+    world.purge_component(ComponentId::of::<bool>());
+    assert!(world.get::<bool>(c).is_err());
+    assert_eq!(world.component_storage::<bool>(), ComponentStorage::Table);
+    let archetypes = world.archetypes().len();
+
+    // new archetypes are created for the purged type
+    world.insert_one(a, Dropped(drops.clone())).unwrap();
+    assert!(world.get::<Dropped>(a).is_ok());
+    assert_eq!(world.archetypes().len(), archetypes + 1);
+    assert_eq!(world.query::<(&i32, &Dropped)>().iter().count(), 1);
+    world.despawn(a).unwrap();
+    assert_eq!(drops.load(Ordering::Relaxed), 3);
+}
@@ -3,9 +3,8 @@ use crate::{
     system::{SystemId, TypeAccess},
     Resource, ResourceIndex,
 };
-use bevy_hecs::smaller_tuples_too;
+use bevy_hecs::{smaller_tuples_too, ChangeTicks};
 use core::{
-    any::TypeId,
     ops::{Deref, DerefMut},
     ptr::NonNull,
 };
@@ -14,18 +13,32 @@ use std::marker::PhantomData;
 /// Shared borrow of a Resource
 pub struct Res<'a, T: Resource> {
     value: &'a T,
+    added: bool,
+    mutated: bool,
 }
 
 impl<'a, T: Resource> Res<'a, T> {
-    /// Creates a reference cell to a Resource from a pointer
+    /// Creates a reference cell to a Resource from a pointer and its change trackers
     ///
     /// # Safety
     /// The pointer must have correct lifetime / storage
-    pub unsafe fn new(value: NonNull<T>) -> Self {
+    pub unsafe fn new(value: NonNull<T>, added: bool, mutated: bool) -> Self {
         Self {
             value: &*value.as_ptr(),
+            added,
+            mutated,
         }
     }
+
+    /// Returns true if the resource was inserted since the system last ran
+    pub fn is_added(&self) -> bool {
+        self.added
+    }
+
+    /// Returns true if the resource was inserted or mutably borrowed since the system last ran
+    pub fn is_changed(&self) -> bool {
+        self.added || self.mutated
+    }
 }
 
 /// A clone that is unsafe to perform. You probably shouldn't use this.
@@ -36,7 +49,17 @@ pub trait UnsafeClone {
 
 impl<'a, T: Resource> UnsafeClone for Res<'a, T> {
     unsafe fn unsafe_clone(&self) -> Self {
-        Self { value: self.value }
+        Self {
+            value: self.value,
+            added: self.added,
+            mutated: self.mutated,
+        }
+    }
+}
+
+impl<T: UnsafeClone> UnsafeClone for Option<T> {
+    unsafe fn unsafe_clone(&self) -> Self {
+        self.as_ref().map(|value| value.unsafe_clone())
     }
 }
 
@@ -51,20 +74,60 @@ impl<'a, T: Resource> Deref for Res<'a, T> {
     }
 }
 
+/// A shared borrow of a Resource that only exists if the resource changed since the system last
+/// ran. Systems with a `ChangedRes<T>` parameter are skipped otherwise.
+pub struct ChangedRes<'a, T: Resource> {
+    value: &'a T,
+}
+
+impl<'a, T: Resource> ChangedRes<'a, T> {
+    /// Creates a reference cell to a Resource from a pointer
+    ///
+    /// # Safety
+    /// The pointer must have correct lifetime / storage
+    pub unsafe fn new(value: NonNull<T>) -> Self {
+        Self {
+            value: &*value.as_ptr(),
+        }
+    }
+}
+
+impl<'a, T: Resource> UnsafeClone for ChangedRes<'a, T> {
+    unsafe fn unsafe_clone(&self) -> Self {
+        Self { value: self.value }
+    }
+}
+
+unsafe impl<T: Resource> Send for ChangedRes<'_, T> {}
+unsafe impl<T: Resource> Sync for ChangedRes<'_, T> {}
+
+impl<'a, T: Resource> Deref for ChangedRes<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
 /// Unique borrow of a Resource
 pub struct ResMut<'a, T: Resource> {
     _marker: PhantomData<&'a T>,
     value: *mut T,
+    mutated_tick: *mut u32,
+    change_tick: u32,
 }
 
 impl<'a, T: Resource> ResMut<'a, T> {
-    /// Creates a mutable reference cell to a Resource from a pointer
+    /// Creates a mutable reference cell to a Resource from a pointer and its "mutated" tick, which
+    /// is set to `change_tick` whenever the resource is mutably dereferenced
     ///
     /// # Safety
-    /// The pointer must have correct lifetime / storage / ownership
-    pub unsafe fn new(value: NonNull<T>) -> Self {
+    /// The pointers must have correct lifetime / storage / ownership
+    pub unsafe fn new(value: NonNull<T>, mutated_tick: NonNull<u32>, change_tick: u32) -> Self {
         Self {
             value: value.as_ptr(),
+            mutated_tick: mutated_tick.as_ptr(),
+            change_tick,
             _marker: Default::default(),
         }
     }
@@ -83,7 +146,10 @@ impl<'a, T: Resource> Deref for ResMut<'a, T> {
 
 impl<'a, T: Resource> DerefMut for ResMut<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.value }
+        unsafe {
+            *self.mutated_tick = self.change_tick;
+            &mut *self.value
+        }
     }
 }
 
@@ -91,6 +157,8 @@ impl<'a, T: Resource> UnsafeClone for ResMut<'a, T> {
     unsafe fn unsafe_clone(&self) -> Self {
         Self {
             value: self.value,
+            mutated_tick: self.mutated_tick,
+            change_tick: self.change_tick,
             _marker: Default::default(),
         }
     }
@@ -142,8 +210,19 @@ pub trait FetchResource<'a>: Sized {
     fn borrow(resources: &Resources);
     fn release(resources: &Resources);
 
+    /// if this returns true, systems fetching this won't run. For example, `ChangedRes<T>` skips
+    /// systems while `T` is unchanged.
+    fn should_skip(_resources: &Resources, _ticks: ChangeTicks) -> bool {
+        false
+    }
+
+    /// Fetches the resources. Change detection compares against `ticks`.
     #[allow(clippy::missing_safety_doc)]
-    unsafe fn get(resources: &'a Resources, system_id: Option<SystemId>) -> Self::Item;
+    unsafe fn get(
+        resources: &'a Resources,
+        system_id: Option<SystemId>,
+        ticks: ChangeTicks,
+    ) -> Self::Item;
 }
 
 impl<'a, T: Resource> ResourceQuery for Res<'a, T> {
@@ -156,8 +235,18 @@ pub struct FetchResourceRead<T>(NonNull<T>);
 impl<'a, T: Resource> FetchResource<'a> for FetchResourceRead<T> {
     type Item = Res<'a, T>;
 
-    unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
-        Res::new(resources.get_unsafe_ref::<T>(ResourceIndex::Global))
+    unsafe fn get(
+        resources: &'a Resources,
+        _system_id: Option<SystemId>,
+        ticks: ChangeTicks,
+    ) -> Self::Item {
+        let (value, added, mutated) =
+            resources.get_unsafe_ref_with_added_and_mutated::<T>(ResourceIndex::Global);
+        Res::new(
+            value,
+            ticks.is_newer(*added.as_ptr()),
+            ticks.is_newer(*mutated.as_ptr()),
+        )
     }
 
     fn borrow(resources: &Resources) {
@@ -170,7 +259,85 @@ impl<'a, T: Resource> FetchResource<'a> for FetchResourceRead<T> {
 
     fn access() -> TypeAccess {
         let mut access = TypeAccess::default();
-        access.immutable.insert(TypeId::of::<T>());
+        access.add_read::<T>();
+        access
+    }
+}
+
+impl<'a, T: Resource> ResourceQuery for Option<Res<'a, T>> {
+    type Fetch = FetchResourceReadOption<T>;
+}
+
+/// Fetches a shared resource reference if the resource exists
+pub struct FetchResourceReadOption<T>(PhantomData<T>);
+
+impl<'a, T: Resource> FetchResource<'a> for FetchResourceReadOption<T> {
+    type Item = Option<Res<'a, T>>;
+
+    unsafe fn get(
+        resources: &'a Resources,
+        system_id: Option<SystemId>,
+        ticks: ChangeTicks,
+    ) -> Self::Item {
+        if resources.contains::<T>() {
+            Some(FetchResourceRead::<T>::get(resources, system_id, ticks))
+        } else {
+            None
+        }
+    }
+
+    fn borrow(resources: &Resources) {
+        resources.borrow::<T>();
+    }
+
+    fn release(resources: &Resources) {
+        resources.release::<T>();
+    }
+
+    fn access() -> TypeAccess {
+        let mut access = TypeAccess::default();
+        access.add_read::<T>();
+        access
+    }
+}
+
+impl<'a, T: Resource> ResourceQuery for ChangedRes<'a, T> {
+    type Fetch = FetchResourceChanged<T>;
+}
+
+/// Fetches a shared resource reference, skipping the system if the resource is unchanged
+pub struct FetchResourceChanged<T>(PhantomData<T>);
+
+impl<'a, T: Resource> FetchResource<'a> for FetchResourceChanged<T> {
+    type Item = ChangedRes<'a, T>;
+
+    unsafe fn get(
+        resources: &'a Resources,
+        _system_id: Option<SystemId>,
+        _ticks: ChangeTicks,
+    ) -> Self::Item {
+        ChangedRes::new(resources.get_unsafe_ref::<T>(ResourceIndex::Global))
+    }
+
+    fn borrow(resources: &Resources) {
+        resources.borrow::<T>();
+    }
+
+    fn release(resources: &Resources) {
+        resources.release::<T>();
+    }
+
+    fn should_skip(resources: &Resources, ticks: ChangeTicks) -> bool {
+        unsafe {
+            let (_value, added, mutated) =
+                resources.get_unsafe_ref_with_added_and_mutated::<T>(ResourceIndex::Global);
+            !ticks.is_newer(*added.as_ptr()) && !ticks.is_newer(*mutated.as_ptr())
+        }
+    }
+
+    fn access() -> TypeAccess {
+        let mut access = TypeAccess::default();
+        access.add_read::<T>();
         access
     }
 }
@@ -185,8 +352,14 @@ pub struct FetchResourceWrite<T>(NonNull<T>);
 impl<'a, T: Resource> FetchResource<'a> for FetchResourceWrite<T> {
     type Item = ResMut<'a, T>;
 
-    unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
-        ResMut::new(resources.get_unsafe_ref::<T>(ResourceIndex::Global))
+    unsafe fn get(
+        resources: &'a Resources,
+        _system_id: Option<SystemId>,
+        ticks: ChangeTicks,
+    ) -> Self::Item {
+        let (value, _added, mutated) =
+            resources.get_unsafe_ref_with_added_and_mutated::<T>(ResourceIndex::Global);
+        ResMut::new(value, mutated, ticks.change_tick)
     }
 
     fn borrow(resources: &Resources) {
@@ -199,7 +372,7 @@ impl<'a, T: Resource> FetchResource<'a> for FetchResourceWrite<T> {
 
     fn access() -> TypeAccess {
         let mut access = TypeAccess::default();
-        access.mutable.insert(TypeId::of::<T>());
+        access.add_write::<T>();
         access
     }
 }
@@ -220,7 +393,11 @@ pub struct FetchResourceLocalMut<T>(NonNull<T>);
 impl<'a, T: Resource + FromResources> FetchResource<'a> for FetchResourceLocalMut<T> {
     type Item = Local<'a, T>;
 
-    unsafe fn get(resources: &'a Resources, system_id: Option<SystemId>) -> Self::Item {
+    unsafe fn get(
+        resources: &'a Resources,
+        system_id: Option<SystemId>,
+        _ticks: ChangeTicks,
+    ) -> Self::Item {
         let id = system_id.expect("Local<T> resources can only be used by systems");
         Local {
             value: resources
@@ -240,7 +417,7 @@ impl<'a, T: Resource + FromResources> FetchResource<'a> for FetchResourceLocalMu
 
     fn access() -> TypeAccess {
         let mut access = TypeAccess::default();
-        access.mutable.insert(TypeId::of::<T>());
+        access.add_write::<T>();
         access
     }
 }
@@ -260,9 +437,14 @@ macro_rules! tuple_impl {
                 $($name::release(resources);)*
             }
 
+            #[allow(unused_variables, clippy::unused_unit)]
+            unsafe fn get(resources: &'a Resources, system_id: Option<SystemId>, ticks: ChangeTicks) -> Self::Item {
+                ($($name::get(resources, system_id, ticks),)*)
+            }
+
             #[allow(unused_variables)]
-            unsafe fn get(resources: &'a Resources, system_id: Option<SystemId>) -> Self::Item {
-                ($($name::get(resources, system_id),)*)
+            fn should_skip(resources: &Resources, ticks: ChangeTicks) -> bool {
+                $($name::should_skip(resources, ticks)||)* false
             }
 
             #[allow(unused_mut)]
@@ -1,6 +1,9 @@
 use super::{FetchResource, ResourceQuery};
 use crate::system::SystemId;
-use bevy_hecs::{Archetype, Ref, RefMut, TypeInfo};
+use bevy_hecs::{
+    check_tick, oldest_tick, Archetype, ChangeTickCounter, ChangeTicks, Entity, Ref, RefMut,
+    TypeInfo, CHECK_TICK_THRESHOLD,
+};
 use bevy_utils::HashMap;
 use core::any::TypeId;
 use std::ptr::NonNull;
@@ -21,9 +24,22 @@ pub enum ResourceIndex {
 }
 
 /// A collection of resource instances identified by their type.
-#[derive(Default)]
 pub struct Resources {
     pub(crate) resource_data: HashMap<TypeId, ResourceData>,
+    change_tick: ChangeTickCounter,
+    last_change_tick: u32,
+    last_check_tick: u32,
+}
+
+impl Default for Resources {
+    fn default() -> Self {
+        Self {
+            resource_data: HashMap::default(),
+            change_tick: ChangeTickCounter::default(),
+            last_change_tick: oldest_tick(1),
+            last_check_tick: 1,
+        }
+    }
 }
 
 impl Resources {
@@ -94,22 +110,31 @@ impl Resources {
         use std::cmp::Ordering;
         match index.cmp(&archetype.len()) {
             Ordering::Equal => {
-                unsafe { archetype.allocate(index as u128) };
+                unsafe { archetype.allocate(Entity::new(index)) };
             }
             Ordering::Greater => panic!("attempted to access index beyond 'current_capacity + 1'"),
             Ordering::Less => (),
         }
 
+        let change_tick = self.change_tick.read();
         unsafe {
             let resource_ptr = (&mut resource as *mut T).cast::<u8>();
             archetype.put_dynamic(
                 resource_ptr,
-                type_id,
+                type_id.into(),
                 core::mem::size_of::<T>(),
                 index,
-                added,
+                if added { Some(change_tick) } else { None },
             );
             std::mem::forget(resource);
+            if !added {
+                // replacing a resource counts as mutating it
+                *archetype
+                    .get_mutated::<T>()
+                    .unwrap()
+                    .as_ptr()
+                    .add(index as usize) = change_tick;
+            }
         }
     }
 
@@ -136,19 +161,20 @@ impl Resources {
                     ResourceIndex::Global => data.default_index?,
                     ResourceIndex::System(id) => *data.system_id_to_archetype_index.get(&id.0)?,
                 };
-                RefMut::new(&data.archetype, index).ok()
+                RefMut::new(&data.archetype, index, self.read_change_tick()).ok()
             })
     }
 
     pub fn query<Q: ResourceQuery>(&self) -> <Q::Fetch as FetchResource>::Item {
-        unsafe { Q::Fetch::get(&self, None) }
+        unsafe { Q::Fetch::get(self, None, self.change_ticks()) }
     }
 
     pub fn query_system<Q: ResourceQuery>(
         &self,
         id: SystemId,
+        ticks: ChangeTicks,
     ) -> <Q::Fetch as FetchResource>::Item {
-        unsafe { Q::Fetch::get(&self, Some(id)) }
+        unsafe { Q::Fetch::get(self, Some(id), ticks) }
     }
 
     #[inline]
@@ -170,6 +196,88 @@ impl Resources {
             .unwrap_or_else(|| panic!("Resource does not exist {}", std::any::type_name::<T>()))
     }
 
+    #[inline]
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn get_unsafe_ref_with_added_and_mutated<T: Resource>(
+        &self,
+        resource_index: ResourceIndex,
+    ) -> (NonNull<T>, NonNull<u32>, NonNull<u32>) {
+        self.resource_data
+            .get(&TypeId::of::<T>())
+            .and_then(|data| {
+                let index = match resource_index {
+                    ResourceIndex::Global => data.default_index?,
+                    ResourceIndex::System(id) => {
+                        data.system_id_to_archetype_index.get(&id.0).cloned()?
+                    }
+                } as usize;
+                let (value, added, mutated) = data.archetype.get_with_added_and_mutated::<T>()?;
+                Some((
+                    NonNull::new_unchecked(value.as_ptr().add(index)),
+                    NonNull::new_unchecked(added.as_ptr().add(index)),
+                    NonNull::new_unchecked(mutated.as_ptr().add(index)),
+                ))
+            })
+            .unwrap_or_else(|| panic!("Resource does not exist {}", std::any::type_name::<T>()))
+    }
+
+    /// The ticks used to detect changes in `query`. Changes count as new until the next call to
+    /// `clear_trackers`.
+    pub fn change_ticks(&self) -> ChangeTicks {
+        ChangeTicks::new(self.last_change_tick, self.read_change_tick())
+    }
+
+    /// Returns the current change tick of these resources
+    #[inline]
+    pub fn read_change_tick(&self) -> u32 {
+        self.change_tick.read()
+    }
+
+    /// Advances the change tick of these resources, returning its previous value. Systems call
+    /// this every time they run.
+    #[inline]
+    pub fn increment_change_tick(&self) -> u32 {
+        self.change_tick.increment()
+    }
+
+    /// Advances the change tick, so changes made so far are no longer reported by `query`. Systems
+    /// keep track of the changes they have seen themselves and are not affected.
+    pub fn clear_trackers(&mut self) {
+        self.last_change_tick = self.increment_change_tick();
+
+        let change_tick = self.read_change_tick();
+        if change_tick.wrapping_sub(self.last_check_tick) >= CHECK_TICK_THRESHOLD {
+            for data in self.resource_data.values_mut() {
+                data.archetype.check_change_ticks(change_tick);
+            }
+            check_tick(&mut self.last_change_tick, change_tick);
+            self.last_check_tick = change_tick;
+        }
+    }
+
+    /// Returns the types of all resources, including local resources
+    pub fn resource_types(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.resource_data.keys().copied()
+    }
+
+    /// Removes the resource of the given type, along with all local resources of that type. Returns false if there is
+    /// no such resource.
+    pub fn remove_type(&mut self, type_id: TypeId) -> bool {
+        self.resource_data.remove(&type_id).is_some()
+    }
+
+    /// Removes the types of resources that only exist as local resources of the given systems. Local resources of types
+    /// that are also used elsewhere are kept.
+    pub fn remove_local_resources(&mut self, system_ids: &[SystemId]) {
+        self.resource_data.retain(|_, data| {
+            data.default_index.is_some()
+                || data
+                    .system_id_to_archetype_index
+                    .keys()
+                    .any(|id| !system_ids.contains(&SystemId(*id)))
+        });
+    }
+
     pub fn borrow<T: Resource>(&self) {
         if let Some(data) = self.resource_data.get(&TypeId::of::<T>()) {
             data.archetype.borrow::<T>();
@@ -267,4 +375,24 @@ mod tests {
         let _x = resources.get_mut::<i32>();
         let _y = resources.get_mut::<i32>();
     }
+
+    #[test]
+    fn remove_resources() {
+        let mut resources = Resources::default();
+        resources.insert(123);
+        resources.insert_local(SystemId(0), 1.0f64);
+        resources.insert_local(SystemId(0), 1u8);
+        resources.insert_local(SystemId(1), 2u8);
+        resources.insert_local(SystemId(0), 1i32);
+
+        resources.remove_local_resources(&[SystemId(0)]);
+        assert!(resources.get_local::<f64>(SystemId(0)).is_none());
+        assert_eq!(*resources.get_local::<u8>(SystemId(1)).unwrap(), 2);
+        assert_eq!(*resources.get::<i32>().unwrap(), 123);
+
+        assert!(resources.remove_type(std::any::TypeId::of::<i32>()));
+        assert!(!resources.remove_type(std::any::TypeId::of::<i32>()));
+        assert!(resources.get::<i32>().is_none());
+        assert_eq!(resources.resource_types().count(), 1);
+    }
 }
@@ -0,0 +1,228 @@
+use super::{Schedule, StageInfo, SystemConflict};
+use crate::system::ThreadLocalExecution;
+use fixedbitset::FixedBitSet;
+use std::{borrow::Cow, fmt::Write};
+
+/// Determines how [Schedule::initialize] reports systems in the same stage whose access conflicts but that have no
+/// explicit ordering between them. Such systems run in the order they happened to be added in, which is easy to change
+/// by accident.
+///
+/// Conflicts are found from the resources and the component types each system accesses, which are known before the
+/// systems first run. Query filters like `With` and `Without` are not taken into account.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum AmbiguityDetection {
+    /// Don't check for ambiguities
+    #[default]
+    Off,
+    /// Log a warning for each ambiguity
+    Warn,
+    /// Panic if there are any ambiguities
+    Deny,
+}
+
+impl StageInfo {
+    /// Returns the pairs of systems in this stage whose resource or component access conflicts, but that are not
+    /// ordered relative to each other, either directly or through other systems. Thread local systems that run
+    /// immediately always run exclusively, so they are never ambiguous.
+    pub fn ambiguities(&self) -> Vec<SystemConflict> {
+        // ordered_after[i] contains every system that has to run before system i. systems are sorted, so any system
+        // ordered before system i comes earlier in the stage
+        let mut ordered_after =
+            vec![FixedBitSet::with_capacity(self.systems.len()); self.systems.len()];
+        for (i, system) in self.systems.iter().enumerate() {
+            for (j, earlier) in self.systems.iter().enumerate().take(i) {
+                if earlier.ordering.runs_before(&system.ordering) {
+                    let earlier_ordered_after = ordered_after[j].clone();
+                    ordered_after[i].union_with(&earlier_ordered_after);
+                    ordered_after[i].insert(j);
+                }
+            }
+        }
+
+        self.conflicts()
+            .into_iter()
+            .filter(|conflict| {
+                let immediate = |index: usize| {
+                    self.systems[index].thread_local_execution == ThreadLocalExecution::Immediate
+                };
+                (!conflict.resources.is_empty() || !conflict.components.is_empty())
+                    && !immediate(conflict.first)
+                    && !immediate(conflict.second)
+                    && !ordered_after[conflict.second].contains(conflict.first)
+            })
+            .collect()
+    }
+}
+
+impl Schedule {
+    /// Sets how [Schedule::initialize] reports ambiguities. See [AmbiguityDetection].
+    pub fn set_ambiguity_detection(
+        &mut self,
+        ambiguity_detection: AmbiguityDetection,
+    ) -> &mut Self {
+        self.ambiguity_detection = ambiguity_detection;
+        self
+    }
+
+    /// Returns the ambiguities in each stage of this schedule, see [StageInfo::ambiguities].
+    pub fn ambiguities(&self) -> Vec<(Cow<'static, str>, SystemConflict)> {
+        self.stage_infos()
+            .into_iter()
+            .flat_map(|stage| {
+                let name = stage.name.clone();
+                stage
+                    .ambiguities()
+                    .into_iter()
+                    .map(move |ambiguity| (name.clone(), ambiguity))
+            })
+            .collect()
+    }
+
+    pub(crate) fn check_ambiguities(&self) {
+        if self.ambiguity_detection == AmbiguityDetection::Off {
+            return;
+        }
+
+        let mut messages = Vec::new();
+        for stage in self.stage_infos() {
+            for ambiguity in stage.ambiguities() {
+                let mut message = format!(
+                    "systems {} and {} in stage {} have an ambiguous order because they both access",
+                    stage.systems[ambiguity.first].name,
+                    stage.systems[ambiguity.second].name,
+                    stage.name
+                );
+                if !ambiguity.resources.is_empty() {
+                    write!(message, " resources {}", ambiguity.resources.join(", ")).unwrap();
+                    if !ambiguity.components.is_empty() {
+                        message.push_str(" and");
+                    }
+                }
+                if !ambiguity.components.is_empty() {
+                    write!(message, " components {}", ambiguity.components.join(", ")).unwrap();
+                }
+                message.push_str(
+                    " and at least one of them writes. Use `before` or `after` to order them.",
+                );
+                messages.push(message);
+            }
+        }
+
+        match self.ambiguity_detection {
+            AmbiguityDetection::Warn => {
+                for message in messages {
+                    log::warn!("{}", message);
+                }
+            }
+            AmbiguityDetection::Deny if !messages.is_empty() => {
+                panic!("Found system order ambiguities:\n{}", messages.join("\n"))
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AmbiguityDetection;
+    use crate::{
+        resource::{Res, ResMut, Resources},
+        schedule::Schedule,
+        system::{IntoForEachSystem, IntoQuerySystem, IntoThreadLocalSystem, Query},
+    };
+    use bevy_hecs::{Mut, With, World};
+
+    fn read(_value: Res<u32>) {}
+    fn write_a(_value: ResMut<u32>) {}
+    fn write_b(_value: ResMut<u32>) {}
+    fn other(_value: ResMut<u64>) {}
+    fn thread_local(_world: &mut World, _resources: &mut Resources) {}
+    fn write_component_a(_value: Mut<f32>) {}
+    fn write_component_b(_query: Query<With<bool, &mut f32>>) {}
+    fn read_other_component(_value: &f64) {}
+
+    #[test]
+    fn ambiguities() {
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", write_a.system().label("a"));
+        schedule.add_system_to_stage("update", write_b.system());
+        schedule.add_system_to_stage("update", read.system().after("a"));
+        schedule.add_system_to_stage("update", other.system());
+        schedule.add_system_to_stage("update", thread_local.thread_local_system());
+
+        let ambiguities = schedule.ambiguities();
+        assert_eq!(ambiguities.len(), 2);
+        assert_eq!(ambiguities[0].0, "update");
+        assert_eq!((ambiguities[0].1.first, ambiguities[0].1.second), (0, 1));
+        assert_eq!(
+            ambiguities[0].1.resources,
+            vec![std::any::type_name::<u32>()]
+        );
+        assert_eq!((ambiguities[1].1.first, ambiguities[1].1.second), (1, 2));
+    }
+
+    #[test]
+    fn component_ambiguities() {
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", write_component_a.system());
+        schedule.add_system_to_stage("update", write_component_b.system());
+        schedule.add_system_to_stage("update", read_other_component.system());
+
+        // the systems never ran, so no archetype access is known yet
+        let ambiguities = schedule.ambiguities();
+        assert_eq!(ambiguities.len(), 1);
+        assert_eq!((ambiguities[0].1.first, ambiguities[0].1.second), (0, 1));
+        assert!(ambiguities[0].1.resources.is_empty());
+        assert_eq!(
+            ambiguities[0].1.components,
+            vec![std::any::type_name::<f32>()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "components f32 and at least one of them writes")]
+    fn deny_component_ambiguities() {
+        let mut resources = Resources::default();
+        let mut schedule = Schedule::default();
+        schedule.set_ambiguity_detection(AmbiguityDetection::Deny);
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", write_component_a.system());
+        schedule.add_system_to_stage("update", write_component_b.system());
+        schedule.initialize(&mut resources);
+    }
+
+    #[test]
+    fn transitive_ordering_is_not_ambiguous() {
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", write_a.system().label("a"));
+        schedule.add_system_to_stage("update", other.system().label("b").after("a"));
+        schedule.add_system_to_stage("update", write_b.system().after("b"));
+        assert!(schedule.ambiguities().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Found system order ambiguities")]
+    fn deny_ambiguities() {
+        let mut resources = Resources::default();
+        let mut schedule = Schedule::default();
+        schedule.set_ambiguity_detection(AmbiguityDetection::Deny);
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", write_a.system());
+        schedule.add_system_to_stage("update", write_b.system());
+        schedule.initialize(&mut resources);
+    }
+
+    #[test]
+    fn deny_ordered_systems() {
+        let mut resources = Resources::default();
+        let mut schedule = Schedule::default();
+        schedule.set_ambiguity_detection(AmbiguityDetection::Deny);
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", write_a.system().label("a"));
+        schedule.add_system_to_stage("update", write_b.system().after("a"));
+        schedule.initialize(&mut resources);
+    }
+}
@@ -0,0 +1,8 @@
+use super::Schedule;
+use crate::resource::Resources;
+use bevy_hecs::World;
+
+/// Runs the systems of a [Schedule] on a [World] and [Resources]
+pub trait Executor: Send + Sync {
+    fn run(&mut self, schedule: &mut Schedule, world: &mut World, resources: &mut Resources);
+}
@@ -0,0 +1,416 @@
+use super::{ParallelExecutor, Schedule};
+use crate::system::{
+    ArchetypeAccess, System, SystemId, SystemOrdering, ThreadLocalExecution, TypeAccess,
+};
+use std::{borrow::Cow, fmt::Write};
+
+/// A snapshot of a [System] in a [Schedule] and the access it computed
+///
+/// Archetype access is only known once the system has been prepared to run on a [World](crate::World), so it is empty
+/// for systems that haven't run yet.
+#[derive(Debug, Clone)]
+pub struct SystemInfo {
+    pub id: SystemId,
+    pub name: Cow<'static, str>,
+    pub thread_local_execution: ThreadLocalExecution,
+    pub ordering: SystemOrdering,
+    pub resource_access: TypeAccess,
+    pub component_access: TypeAccess,
+    pub archetype_access: ArchetypeAccess,
+}
+
+impl SystemInfo {
+    pub fn new(system: &dyn System) -> Self {
+        Self {
+            id: system.id(),
+            name: system.name(),
+            thread_local_execution: system.thread_local_execution(),
+            ordering: system.ordering().clone(),
+            resource_access: system.resource_access().clone(),
+            component_access: system.component_access().clone(),
+            archetype_access: system.archetype_access().clone(),
+        }
+    }
+
+    /// Returns the resources and archetypes that both systems access while at least one of them writes, in which case
+    /// the systems can't run in parallel
+    pub fn conflicts(&self, other: &SystemInfo) -> (Vec<Cow<'static, str>>, Vec<usize>) {
+        let resources = type_conflicts(&self.resource_access, &other.resource_access);
+        let archetypes = self.archetype_access.conflicts(&other.archetype_access);
+        (resources, archetypes)
+    }
+
+    /// Returns the component types that the queries of both systems access while at least one of them writes.
+    /// Unlike archetype conflicts, these are known before the systems first run.
+    pub fn component_conflicts(&self, other: &SystemInfo) -> Vec<Cow<'static, str>> {
+        type_conflicts(&self.component_access, &other.component_access)
+    }
+}
+
+fn type_conflicts(access: &TypeAccess, other: &TypeAccess) -> Vec<Cow<'static, str>> {
+    access
+        .conflicts(other)
+        .into_iter()
+        .map(
+            |ty| match access.type_name(&ty).or_else(|| other.type_name(&ty)) {
+                Some(name) => Cow::Borrowed(name),
+                None => Cow::Owned(format!("{:?}", ty)),
+            },
+        )
+        .collect()
+}
+
+/// Two systems in the same stage that can't run in parallel because of the resources, components or archetypes they
+/// access
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SystemConflict {
+    /// The index of the earlier system in its stage
+    pub first: usize,
+    /// The index of the later system in its stage
+    pub second: usize,
+    /// The names of the conflicting resources
+    pub resources: Vec<Cow<'static, str>>,
+    /// The names of the conflicting component types, see [SystemInfo::component_conflicts]
+    pub components: Vec<Cow<'static, str>>,
+    /// The indices of the conflicting archetypes
+    pub archetypes: Vec<usize>,
+}
+
+/// A snapshot of a stage in a [Schedule]
+#[derive(Debug, Clone)]
+pub struct StageInfo {
+    pub name: Cow<'static, str>,
+    /// The stage's systems, in the order they were sorted into
+    pub systems: Vec<SystemInfo>,
+    pub has_run_criteria: bool,
+}
+
+impl StageInfo {
+    /// Returns every pair of systems in this stage whose resource, component or archetype access conflicts
+    pub fn conflicts(&self) -> Vec<SystemConflict> {
+        let mut conflicts = Vec::new();
+        for (first, first_system) in self.systems.iter().enumerate() {
+            for (second, second_system) in self.systems.iter().enumerate().skip(first + 1) {
+                let (resources, archetypes) = first_system.conflicts(second_system);
+                let components = first_system.component_conflicts(second_system);
+                if !resources.is_empty() || !components.is_empty() || !archetypes.is_empty() {
+                    conflicts.push(SystemConflict {
+                        first,
+                        second,
+                        resources,
+                        components,
+                        archetypes,
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Returns the index of the system with the given name in this stage
+    pub fn system_index(&self, name: &str) -> Option<usize> {
+        self.systems.iter().position(|system| system.name == name)
+    }
+}
+
+impl Schedule {
+    /// Returns a snapshot of each stage in this schedule, in the order the stages run
+    pub fn stage_infos(&self) -> Vec<StageInfo> {
+        self.stage_order
+            .iter()
+            .map(|stage_name| self.stage_info(stage_name).unwrap())
+            .collect()
+    }
+
+    /// Returns a snapshot of the stage with the given name
+    pub fn stage_info(&self, stage_name: &str) -> Option<StageInfo> {
+        let systems = self.stages.get(stage_name)?;
+        let name = self
+            .stage_order
+            .iter()
+            .find(|name| *name == stage_name)
+            .unwrap()
+            .clone();
+        Some(StageInfo {
+            name,
+            systems: systems
+                .iter()
+                .map(|system| SystemInfo::new(&**system.lock()))
+                .collect(),
+            has_run_criteria: self.run_criteria.contains_key(stage_name),
+        })
+    }
+
+    /// Returns a [Graphviz](https://graphviz.org) DOT graph of the stages in the order they run. Each stage is drawn as
+    /// a cluster of its systems, which are connected in the order this schedule runs them.
+    pub fn to_dot(&self) -> String {
+        write_dot(
+            "schedule",
+            &self.stage_infos(),
+            |dot, stage_index, stage| {
+                for system_index in 1..stage.systems.len() {
+                    writeln!(
+                        dot,
+                        "    {} -> {};",
+                        system_node(stage_index, system_index - 1),
+                        system_node(stage_index, system_index)
+                    )
+                    .unwrap();
+                }
+            },
+        )
+    }
+}
+
+impl ParallelExecutor {
+    /// Returns a [Graphviz](https://graphviz.org) DOT graph of the dependencies this executor computed for the systems
+    /// of `schedule`. Each stage is drawn as a cluster of its systems, where an edge from one system to another means
+    /// the second system waits for the first one, labeled with the reason.
+    ///
+    /// Dependencies are computed when a stage runs, so stages that have not run with the current version of the
+    /// schedule have no edges.
+    pub fn to_dot(&self, schedule: &Schedule) -> String {
+        let stages = schedule.stage_infos();
+        let schedule_changed = schedule.generation() != self.last_schedule_generation;
+        write_dot("parallel_executor", &stages, |dot, stage_index, stage| {
+            let executor_stage = match self.stages().get(stage_index) {
+                Some(executor_stage) if !schedule_changed => executor_stage,
+                _ => return,
+            };
+            for (system_index, dependencies) in executor_stage
+                .system_dependencies()
+                .iter()
+                .enumerate()
+                .take(stage.systems.len())
+            {
+                for dependency in dependencies.ones() {
+                    writeln!(
+                        dot,
+                        "    {} -> {} [label=\"{}\"];",
+                        system_node(stage_index, dependency),
+                        system_node(stage_index, system_index),
+                        escape(&dependency_label(
+                            &stage.systems[dependency],
+                            &stage.systems[system_index]
+                        ))
+                    )
+                    .unwrap();
+                }
+            }
+        })
+    }
+}
+
+/// Describes why `later` can't run until `earlier` has finished
+fn dependency_label(earlier: &SystemInfo, later: &SystemInfo) -> String {
+    if earlier.thread_local_execution == ThreadLocalExecution::Immediate
+        || later.thread_local_execution == ThreadLocalExecution::Immediate
+    {
+        return "thread local".to_string();
+    }
+
+    let mut reasons = Vec::new();
+    if earlier.ordering.runs_before(&later.ordering) {
+        reasons.push(Cow::Borrowed("ordering"));
+    }
+    let (resources, archetypes) = earlier.conflicts(later);
+    reasons.extend(resources);
+    if !archetypes.is_empty() {
+        reasons.push(Cow::Borrowed("archetypes"));
+    }
+    reasons.join(", ")
+}
+
+fn write_dot(
+    graph_name: &str,
+    stages: &[StageInfo],
+    mut write_edges: impl FnMut(&mut String, usize, &StageInfo),
+) -> String {
+    let mut dot = String::new();
+    writeln!(dot, "digraph {} {{", graph_name).unwrap();
+    writeln!(dot, "    compound=true;").unwrap();
+    writeln!(dot, "    node [shape=box];").unwrap();
+    for (stage_index, stage) in stages.iter().enumerate() {
+        writeln!(dot, "    subgraph cluster_{} {{", stage_index).unwrap();
+        let label = if stage.has_run_criteria {
+            format!("{} (run criteria)", stage.name)
+        } else {
+            stage.name.to_string()
+        };
+        writeln!(dot, "        label=\"{}\";", escape(&label)).unwrap();
+        // invisible anchor so that stages without systems can still be connected
+        writeln!(
+            dot,
+            "        {} [shape=point, style=invis];",
+            stage_node(stage_index)
+        )
+        .unwrap();
+        for (system_index, system) in stage.systems.iter().enumerate() {
+            let style = match system.thread_local_execution {
+                ThreadLocalExecution::Immediate => ", peripheries=2",
+                ThreadLocalExecution::NextFlush => "",
+            };
+            writeln!(
+                dot,
+                "        {} [label=\"{}\"{}];",
+                system_node(stage_index, system_index),
+                escape(&system.name),
+                style
+            )
+            .unwrap();
+        }
+        writeln!(dot, "    }}").unwrap();
+        write_edges(&mut dot, stage_index, stage);
+    }
+    for stage_index in 1..stages.len() {
+        writeln!(
+            dot,
+            "    {} -> {} [ltail=cluster_{}, lhead=cluster_{}];",
+            stage_node(stage_index - 1),
+            stage_node(stage_index),
+            stage_index - 1,
+            stage_index
+        )
+        .unwrap();
+    }
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+fn stage_node(stage_index: usize) -> String {
+    format!("stage_{}", stage_index)
+}
+
+fn system_node(stage_index: usize, system_index: usize) -> String {
+    format!("stage_{}_system_{}", stage_index, system_index)
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        resource::{Res, ResMut, Resources},
+        schedule::{ParallelExecutor, Schedule},
+        system::{IntoQuerySystem, IntoThreadLocalSystem, Query},
+    };
+    use bevy_hecs::World;
+    use bevy_tasks::{ComputeTaskPool, TaskPool};
+
+    fn read_u32(_counter: Res<u32>, _query: Query<&f32>) {}
+    fn write_u32(_counter: ResMut<u32>) {}
+    fn write_f32(_query: Query<&mut f32>) {}
+    fn read_u64(_value: Res<u64>) {}
+    fn thread_local(_world: &mut World, _resources: &mut Resources) {}
+
+    fn schedule() -> Schedule {
+        let mut schedule = Schedule::default();
+        schedule.add_stage("first");
+        schedule.add_stage("second");
+        schedule.add_system_to_stage("first", read_u32.system());
+        schedule.add_system_to_stage("first", write_u32.system());
+        schedule.add_system_to_stage("first", write_f32.system());
+        schedule.add_system_to_stage("first", read_u64.system());
+        schedule.add_system_to_stage("second", thread_local.thread_local_system());
+        schedule.add_system_to_stage("second", read_u64.system());
+        schedule
+    }
+
+    fn run(schedule: &mut Schedule) -> ParallelExecutor {
+        let mut world = World::new();
+        world.spawn((1.0f32,));
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(0u32);
+        resources.insert(0u64);
+        schedule.initialize(&mut resources);
+        let mut executor = ParallelExecutor::default();
+        executor.run(schedule, &mut world, &mut resources);
+        executor
+    }
+
+    #[test]
+    fn stage_infos() {
+        let mut schedule = schedule();
+        run(&mut schedule);
+        let stages = schedule.stage_infos();
+        assert_eq!(
+            stages
+                .iter()
+                .map(|stage| stage.name.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+        let first = &stages[0];
+        assert_eq!(first.systems.len(), 4);
+        assert_eq!(
+            first.system_index(&read_u64.system().name()),
+            Some(3),
+            "systems keep their order"
+        );
+        assert_eq!(first.systems[0].resource_access.immutable.len(), 1);
+        assert_eq!(first.systems[1].resource_access.mutable.len(), 1);
+
+        let conflicts = first.conflicts();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!((conflicts[0].first, conflicts[0].second), (0, 1));
+        assert_eq!(conflicts[0].resources, vec![std::any::type_name::<u32>()]);
+        assert!(conflicts[0].archetypes.is_empty());
+        assert_eq!((conflicts[1].first, conflicts[1].second), (0, 2));
+        assert!(conflicts[1].resources.is_empty());
+        assert_eq!(conflicts[1].archetypes.len(), 1);
+    }
+
+    #[test]
+    fn executor_dependencies() {
+        let mut schedule = schedule();
+        let executor = run(&mut schedule);
+        let first = &executor.stages()[0];
+        let dependencies = first
+            .system_dependencies()
+            .iter()
+            .map(|dependencies| dependencies.ones().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(dependencies, vec![vec![], vec![0], vec![0], vec![]]);
+        assert_eq!(first.system_dependents()[0], vec![1, 2]);
+
+        let second = &executor.stages()[1];
+        assert_eq!(
+            second.system_dependencies()[1].ones().collect::<Vec<_>>(),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn dot() {
+        let mut schedule = schedule();
+        let schedule_dot = schedule.to_dot();
+        assert!(schedule_dot.starts_with("digraph schedule {"));
+        assert!(schedule_dot.contains("label=\"first\";"));
+        assert!(schedule_dot.contains("stage_0_system_0 -> stage_0_system_1;"));
+        assert!(schedule_dot.contains("stage_0 -> stage_1 [ltail=cluster_0, lhead=cluster_1];"));
+        assert!(schedule_dot.contains("peripheries=2"));
+
+        let executor = run(&mut schedule);
+        let executor_dot = executor.to_dot(&schedule);
+        assert!(executor_dot.contains(&format!(
+            "stage_0_system_0 -> stage_0_system_1 [label=\"{}\"];",
+            std::any::type_name::<u32>()
+        )));
+        assert!(
+            executor_dot.contains("stage_0_system_0 -> stage_0_system_2 [label=\"archetypes\"];")
+        );
+        assert!(
+            executor_dot.contains("stage_1_system_0 -> stage_1_system_1 [label=\"thread local\"];")
+        );
+        assert!(!executor_dot.contains("stage_0_system_3 ->"));
+
+        // the dependencies are outdated once the schedule changes
+        schedule.add_system_to_stage("first", read_u64.system());
+        assert!(!executor
+            .to_dot(&schedule)
+            .contains("stage_0_system_0 -> stage_0_system_1"));
+    }
+}
@@ -1,6 +1,16 @@
+mod ambiguity;
+mod executor;
+mod introspection;
 mod parallel_executor;
+mod run_criteria;
 #[allow(clippy::module_inception)]
 mod schedule;
+mod serial_executor;
 
+pub use ambiguity::*;
+pub use executor::*;
+pub use introspection::*;
 pub use parallel_executor::*;
+pub use run_criteria::*;
 pub use schedule::*;
+pub use serial_executor::*;
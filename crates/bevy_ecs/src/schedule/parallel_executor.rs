@@ -1,4 +1,4 @@
-use super::Schedule;
+use super::{Executor, Schedule, ShouldRun};
 use crate::{
     resource::Resources,
     system::{ArchetypeAccess, System, ThreadLocalExecution, TypeAccess},
@@ -16,11 +16,13 @@ use std::{ops::Range, sync::Arc};
 /// * in a given stage, systems the read archetype X cannot run before systems registered before them that write archetype X
 /// * in a given stage, systems that mutate resource Y cannot run before systems registered before them that read/write resource Y
 /// * in a given stage, systems the read resource Y cannot run before systems registered before them that write resource Y
+/// * in a given stage, systems that are explicitly ordered after a label (or that a labeled system is ordered before) run after
+///   the systems with that label, regardless of their resource and archetype access
 
 #[derive(Debug)]
 pub struct ParallelExecutor {
     stages: Vec<ExecutorStage>,
-    last_schedule_generation: usize,
+    pub(crate) last_schedule_generation: usize,
     clear_trackers: bool,
 }
 
@@ -42,7 +44,13 @@ impl ParallelExecutor {
         }
     }
 
+    /// Returns the state of each stage, in the order of the stages of the last schedule this executor ran
+    pub fn stages(&self) -> &[ExecutorStage] {
+        &self.stages
+    }
+
     pub fn run(&mut self, schedule: &mut Schedule, world: &mut World, resources: &mut Resources) {
+        schedule.initialize(resources);
         let schedule_generation = schedule.generation();
         let schedule_changed = schedule.generation() != self.last_schedule_generation;
         if schedule_changed {
@@ -53,18 +61,41 @@ impl ParallelExecutor {
         for (stage_name, executor_stage) in schedule.stage_order.iter().zip(self.stages.iter_mut())
         {
             if let Some(stage_systems) = schedule.stages.get_mut(stage_name) {
-                executor_stage.run(world, resources, stage_systems, schedule_changed);
+                let mut run_criteria = schedule.run_criteria.get_mut(stage_name);
+                loop {
+                    let should_run = run_criteria
+                        .as_mut()
+                        .map_or(ShouldRun::Yes, |run_criteria| {
+                            run_criteria.should_run(world, resources)
+                        });
+                    if should_run == ShouldRun::No {
+                        break;
+                    }
+
+                    executor_stage.run(world, resources, stage_systems, schedule_changed);
+
+                    if should_run == ShouldRun::Yes {
+                        break;
+                    }
+                }
             }
         }
 
         if self.clear_trackers {
             world.clear_trackers();
+            resources.clear_trackers();
         }
 
         self.last_schedule_generation = schedule_generation;
     }
 }
 
+impl Executor for ParallelExecutor {
+    fn run(&mut self, schedule: &mut Schedule, world: &mut World, resources: &mut Resources) {
+        ParallelExecutor::run(self, schedule, world, resources);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ExecutorStage {
     /// each system's set of dependencies
@@ -81,6 +112,9 @@ pub struct ExecutorStage {
     sender: Sender<usize>,
     receiver: Receiver<usize>,
     last_archetypes_generation: ArchetypesGeneration,
+    /// true if the stage has not been run since it was created. stages can be skipped by their run criteria,
+    /// so the first run is not necessarily the one where the schedule changed
+    first_run: bool,
 }
 
 impl Default for ExecutorStage {
@@ -96,6 +130,7 @@ impl Default for ExecutorStage {
             sender,
             receiver,
             last_archetypes_generation: ArchetypesGeneration(u64::MAX), // MAX forces prepare to run the first time
+            first_run: true,
         }
     }
 }
@@ -111,6 +146,17 @@ enum RunReadyType {
 }
 
 impl ExecutorStage {
+    /// Returns the set of systems each system in this stage waits for, by index in the stage. These are computed while
+    /// the stage runs.
+    pub fn system_dependencies(&self) -> &[FixedBitSet] {
+        &self.system_dependencies
+    }
+
+    /// Returns the systems that wait for each system in this stage, by index in the stage
+    pub fn system_dependents(&self) -> &[Vec<usize>] {
+        &self.system_dependents
+    }
+
     pub fn prepare_to_next_thread_local(
         &mut self,
         world: &World,
@@ -158,9 +204,11 @@ impl ExecutorStage {
                 match system.thread_local_execution() {
                     ThreadLocalExecution::NextFlush => {
                         let resource_access = system.resource_access();
-                        // if any system before this one conflicts, check all systems that came before for compatibility
+                        let ordering = system.ordering();
+                        // if any system before this one conflicts or this system is explicitly ordered, check all systems that came before
                         if !current_archetype_access.is_compatible(archetype_access)
                             || !current_resource_access.is_compatible(resource_access)
+                            || !ordering.is_empty()
                         {
                             #[allow(clippy::needless_range_loop)]
                             for earlier_system_index in
@@ -174,13 +222,14 @@ impl ExecutorStage {
                                     ThreadLocalExecution::NextFlush
                                 );
 
-                                // if earlier system is incompatible, make the current system dependent
+                                // if earlier system is incompatible or explicitly ordered before, make the current system dependent
                                 if !earlier_system
                                     .archetype_access()
                                     .is_compatible(archetype_access)
                                     || !earlier_system
                                         .resource_access()
                                         .is_compatible(resource_access)
+                                    || earlier_system.ordering().runs_before(ordering)
                                 {
                                     self.system_dependents[earlier_system_index].push(system_index);
                                     self.system_dependencies[system_index]
@@ -282,6 +331,8 @@ impl ExecutorStage {
         systems: &[Arc<Mutex<Box<dyn System>>>],
         schedule_changed: bool,
     ) {
+        let schedule_changed = schedule_changed || self.first_run;
+        self.first_run = false;
         let start_archetypes_generation = world.archetypes_generation();
         let compute_pool = resources
             .get_cloned::<bevy_tasks::ComputeTaskPool>()
@@ -402,12 +453,12 @@ mod tests {
     use super::ParallelExecutor;
     use crate::{
         resource::{Res, ResMut, Resources},
-        schedule::Schedule,
+        schedule::{Schedule, ShouldRun},
         system::{IntoQuerySystem, IntoThreadLocalSystem, Query},
         Commands,
     };
     use bevy_hecs::{Entity, World};
-    use bevy_tasks::{ComputeTaskPool, TaskPool};
+    use bevy_tasks::{ComputeTaskPool, TaskPool, TaskPoolBuilder};
     use fixedbitset::FixedBitSet;
     use parking_lot::Mutex;
     use std::sync::Arc;
@@ -448,6 +499,43 @@ mod tests {
         executor.run(&mut schedule, &mut world, &mut resources);
     }
 
+    #[test]
+    fn par_iter_on_one_thread() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(
+            TaskPoolBuilder::new().num_threads(1).build(),
+        ));
+        world.spawn_batch((0..100u32).map(|i| (i,)));
+        world.spawn_batch((0..100u64).map(|i| (i,)));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+
+        // the systems run on the pool's only thread, which also has to run the batches
+        fn double(pool: Res<ComputeTaskPool>, mut query: Query<&mut u32>) {
+            query.par_iter(8).for_each(&pool, |mut i| *i *= 2);
+        }
+
+        fn add_one(pool: Res<ComputeTaskPool>, mut query: Query<&mut u64>) {
+            query.par_iter(8).for_each(&pool, |mut i| *i += 1);
+        }
+
+        schedule.add_system_to_stage("update", double.system());
+        schedule.add_system_to_stage("update", add_one.system());
+
+        let mut executor = ParallelExecutor::default();
+        executor.run(&mut schedule, &mut world, &mut resources);
+        assert_eq!(
+            world.query::<&u32>().iter().copied().sum::<u32>(),
+            (0..100).map(|i| i * 2).sum()
+        );
+        assert_eq!(
+            world.query::<&u64>().iter().copied().sum::<u64>(),
+            (1..=100).sum()
+        );
+    }
+
     #[test]
     fn intra_stage_archetype_change_prepare() {
         let mut world = World::new();
@@ -478,6 +566,83 @@ mod tests {
         executor.run(&mut schedule, &mut world, &mut resources);
     }
 
+    #[test]
+    fn run_criteria_skipped_first_run() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(Counter::default());
+        resources.insert(false);
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+
+        fn count(counter: Res<Counter>, _query: Query<&u32>) {
+            *counter.count.lock() += 1;
+        }
+
+        fn count_other(counter: Res<Counter>, _query: Query<&mut u32>) {
+            *counter.count.lock() += 1;
+        }
+
+        schedule.add_system_to_stage("update", count.system());
+        schedule.add_system_to_stage("update", count_other.system());
+        schedule.set_run_criteria("update", |_world: &World, resources: &Resources| {
+            if *resources.get::<bool>().unwrap() {
+                ShouldRun::Yes
+            } else {
+                ShouldRun::No
+            }
+        });
+
+        let mut executor = ParallelExecutor::default();
+        executor.run(&mut schedule, &mut world, &mut resources);
+        assert_eq!(*resources.get::<Counter>().unwrap().count.lock(), 0);
+
+        // the stage was skipped while the schedule changed, so it still needs to be prepared on its first run
+        *resources.get_mut::<bool>().unwrap() = true;
+        executor.run(&mut schedule, &mut world, &mut resources);
+        assert_eq!(*resources.get::<Counter>().unwrap().count.lock(), 2);
+    }
+
+    #[test]
+    fn explicit_ordering_dependencies() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(Counter::default());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+
+        fn first(counter: Res<Counter>) {
+            let mut count = counter.count.lock();
+            assert_eq!(*count, 0, "should always be the 1st system to run");
+            *count += 1;
+        }
+
+        fn second(counter: Res<Counter>) {
+            let mut count = counter.count.lock();
+            assert_eq!(*count, 1, "should always be the 2nd system to run");
+            *count += 1;
+        }
+
+        fn unordered(_counter: Res<Counter>) {}
+
+        schedule.add_system_to_stage("update", second.system().after("first"));
+        schedule.add_system_to_stage("update", unordered.system());
+        schedule.add_system_to_stage("update", first.system().label("first"));
+
+        let mut executor = ParallelExecutor::default();
+        executor.run(&mut schedule, &mut world, &mut resources);
+
+        assert_eq!(
+            executor.stages[0].system_dependents,
+            vec![vec![], vec![2], vec![]]
+        );
+        assert_eq!(*resources.get::<Counter>().unwrap().count.lock(), 2);
+    }
+
     #[test]
     fn schedule() {
         let mut world = World::new();
@@ -0,0 +1,28 @@
+use crate::resource::Resources;
+use bevy_hecs::World;
+
+/// Determines whether a [Schedule](crate::Schedule) stage runs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ShouldRun {
+    /// Skip the stage
+    No,
+    /// Run the stage once
+    Yes,
+    /// Run the stage, then evaluate the run criteria again
+    YesAndLoop,
+}
+
+/// Decides whether a [Schedule](crate::Schedule) stage should run. Run criteria are evaluated before each
+/// stage run, which allows stages to run zero or more times per schedule run.
+pub trait RunCriteria: Send + Sync {
+    fn should_run(&mut self, world: &World, resources: &Resources) -> ShouldRun;
+}
+
+impl<F> RunCriteria for F
+where
+    F: FnMut(&World, &Resources) -> ShouldRun + Send + Sync,
+{
+    fn should_run(&mut self, world: &World, resources: &Resources) -> ShouldRun {
+        self(world, resources)
+    }
+}
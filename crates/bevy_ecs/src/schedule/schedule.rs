@@ -1,3 +1,4 @@
+use super::{AmbiguityDetection, RunCriteria, SerialExecutor};
 use crate::{
     resource::Resources,
     system::{System, SystemId, ThreadLocalExecution},
@@ -15,6 +16,8 @@ pub struct Schedule {
     pub(crate) stages: HashMap<Cow<'static, str>, Vec<Arc<Mutex<Box<dyn System>>>>>,
     pub(crate) stage_order: Vec<Cow<'static, str>>,
     pub(crate) system_ids: HashSet<SystemId>,
+    pub(crate) run_criteria: HashMap<Cow<'static, str>, Box<dyn RunCriteria>>,
+    pub(crate) ambiguity_detection: AmbiguityDetection,
     generation: usize,
     last_initialize_generation: usize,
 }
@@ -76,6 +79,10 @@ impl Schedule {
         self.stage_order.insert(target_index, stage);
     }
 
+    pub fn has_stage(&self, stage_name: &str) -> bool {
+        self.stages.contains_key(stage_name)
+    }
+
     pub fn add_system_to_stage(
         &mut self,
         stage_name: impl Into<Cow<'static, str>>,
@@ -124,49 +131,66 @@ impl Schedule {
         self
     }
 
-    pub fn run(&mut self, world: &mut World, resources: &mut Resources) {
-        for stage_name in self.stage_order.iter() {
-            if let Some(stage_systems) = self.stages.get_mut(stage_name) {
-                for system in stage_systems.iter_mut() {
-                    let mut system = system.lock();
-                    #[cfg(feature = "profiler")]
-                    crate::profiler_start(resources, system.name().clone());
-                    system.update_archetype_access(world);
-                    match system.thread_local_execution() {
-                        ThreadLocalExecution::NextFlush => system.run(world, resources),
-                        ThreadLocalExecution::Immediate => {
-                            system.run(world, resources);
-                            // NOTE: when this is made parallel a full sync is required here
-                            system.run_thread_local(world, resources);
-                        }
-                    }
-                    #[cfg(feature = "profiler")]
-                    crate::profiler_stop(resources, system.name().clone());
-                }
-
-                // "flush"
-                // NOTE: when this is made parallel a full sync is required here
-                for system in stage_systems.iter_mut() {
-                    let mut system = system.lock();
-                    match system.thread_local_execution() {
-                        ThreadLocalExecution::NextFlush => {
-                            system.run_thread_local(world, resources)
-                        }
-                        ThreadLocalExecution::Immediate => { /* already ran immediate */ }
-                    }
-                }
+    /// Removes the system with the given id from the stage it is in. Returns the system, or `None` if this schedule
+    /// does not contain it.
+    pub fn remove_system(&mut self, id: SystemId) -> Option<Box<dyn System>> {
+        if !self.system_ids.remove(&id) {
+            return None;
+        }
+
+        self.generation += 1;
+        for systems in self.stages.values_mut() {
+            if let Some(index) = systems.iter().position(|system| system.lock().id() == id) {
+                let system = systems.remove(index);
+                return Arc::try_unwrap(system)
+                    .ok()
+                    .map(|system| system.into_inner());
             }
         }
+        None
+    }
+
+    /// Returns the ids of all systems in this schedule
+    pub fn system_ids(&self) -> impl Iterator<Item = SystemId> + '_ {
+        self.system_ids.iter().copied()
+    }
+
+    /// Sets the [RunCriteria] of the given stage, replacing any existing criteria. The criteria are evaluated each time the
+    /// stage is about to run and can skip the stage or run it multiple times.
+    pub fn set_run_criteria(
+        &mut self,
+        stage_name: impl Into<Cow<'static, str>>,
+        run_criteria: impl RunCriteria + 'static,
+    ) -> &mut Self {
+        let stage_name = stage_name.into();
+        if !self.stages.contains_key(&stage_name) {
+            panic!("Stage does not exist: {}", stage_name);
+        }
 
-        world.clear_trackers();
+        self.run_criteria.insert(stage_name, Box::new(run_criteria));
+        self
     }
 
+    /// Runs this schedule on the current thread, see [SerialExecutor]
+    pub fn run(&mut self, world: &mut World, resources: &mut Resources) {
+        SerialExecutor::default().run(self, world, resources);
+    }
+
+    /// Initializes systems that were added since the last call and sorts each stage by the explicit system ordering,
+    /// then checks the schedule for ambiguities if enabled with [Schedule::set_ambiguity_detection].
+    ///
+    /// # Panics
+    /// Panics if the explicit ordering of a stage contains a cycle.
     // TODO: move this code to ParallelExecutor
     pub fn initialize(&mut self, resources: &mut Resources) {
         if self.last_initialize_generation == self.generation {
             return;
         }
 
+        for stage_name in self.stage_order.iter() {
+            sort_systems(stage_name, self.stages.get_mut(stage_name).unwrap());
+        }
+
         for stage in self.stages.values_mut() {
             for system in stage.iter_mut() {
                 let mut system = system.lock();
@@ -174,6 +198,7 @@ impl Schedule {
             }
         }
 
+        self.check_ambiguities();
         self.last_initialize_generation = self.generation;
     }
 
@@ -181,3 +206,273 @@ impl Schedule {
         self.generation
     }
 }
+
+pub(crate) fn run_stage(
+    stage_systems: &mut [Arc<Mutex<Box<dyn System>>>],
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    for system in stage_systems.iter_mut() {
+        let mut system = system.lock();
+        #[cfg(feature = "profiler")]
+        crate::profiler_start(resources, system.name().clone());
+        system.update_archetype_access(world);
+        match system.thread_local_execution() {
+            ThreadLocalExecution::NextFlush => system.run(world, resources),
+            ThreadLocalExecution::Immediate => {
+                system.run(world, resources);
+                // NOTE: when this is made parallel a full sync is required here
+                system.run_thread_local(world, resources);
+            }
+        }
+        #[cfg(feature = "profiler")]
+        crate::profiler_stop(resources, system.name().clone());
+    }
+
+    // "flush"
+    // NOTE: when this is made parallel a full sync is required here
+    for system in stage_systems.iter_mut() {
+        let mut system = system.lock();
+        match system.thread_local_execution() {
+            ThreadLocalExecution::NextFlush => system.run_thread_local(world, resources),
+            ThreadLocalExecution::Immediate => { /* already ran immediate */ }
+        }
+    }
+}
+
+/// Sorts a stage's systems so that every system runs after the systems it is explicitly ordered after. Systems without
+/// an explicit ordering between them keep their relative insertion order. Panics with the systems that form a cycle if
+/// the explicit orderings contain one.
+fn sort_systems(stage_name: &str, systems: &mut Vec<Arc<Mutex<Box<dyn System>>>>) {
+    let orderings = systems
+        .iter()
+        .map(|system| system.lock().ordering().clone())
+        .collect::<Vec<_>>();
+    if orderings.iter().all(|ordering| ordering.is_empty()) {
+        return;
+    }
+
+    let mut dependency_counts = vec![0; systems.len()];
+    let mut dependencies = vec![Vec::new(); systems.len()];
+    let mut dependents = vec![Vec::new(); systems.len()];
+    for (i, ordering) in orderings.iter().enumerate() {
+        for (j, other) in orderings.iter().enumerate() {
+            if i != j && ordering.runs_before(other) {
+                dependents[i].push(j);
+                dependencies[j].push(i);
+                dependency_counts[j] += 1;
+            }
+        }
+    }
+
+    // always pick the earliest inserted system that is ready to run so that unordered systems keep their insertion order
+    let mut sorted = Vec::with_capacity(systems.len());
+    let mut visited = vec![false; systems.len()];
+    while let Some(next) =
+        (0..systems.len()).find(|&index| !visited[index] && dependency_counts[index] == 0)
+    {
+        visited[next] = true;
+        sorted.push(next);
+        for &dependent in dependents[next].iter() {
+            dependency_counts[dependent] -= 1;
+        }
+    }
+
+    if sorted.len() != systems.len() {
+        // every system that was not sorted waits on another one that was not sorted. following those dependencies
+        // backwards from any of them eventually revisits a system, which closes the cycle
+        let mut path = vec![(0..systems.len()).find(|&index| !visited[index]).unwrap()];
+        let cycle_start = loop {
+            let current = *path.last().unwrap();
+            let previous = dependencies[current]
+                .iter()
+                .copied()
+                .find(|&index| !visited[index])
+                .unwrap();
+            if let Some(position) = path.iter().position(|&index| index == previous) {
+                break position;
+            }
+            path.push(previous);
+        };
+        let mut cycle = path.split_off(cycle_start);
+        cycle.reverse();
+        // start the cycle at its earliest added system
+        let first = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+        cycle.rotate_left(first);
+        cycle.push(cycle[0]);
+        let cycle = cycle
+            .iter()
+            .map(|&index| systems[index].lock().name())
+            .collect::<Vec<_>>();
+        panic!(
+            "Found a cycle in the system ordering of stage {}: {}",
+            stage_name,
+            cycle.join(" -> ")
+        );
+    }
+
+    let mut unsorted = std::mem::take(systems)
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    systems.extend(
+        sorted
+            .into_iter()
+            .map(|index| unsorted[index].take().unwrap()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Schedule;
+    use crate::{
+        resource::{ResMut, Resources},
+        schedule::ShouldRun,
+        system::{IntoQuerySystem, System},
+    };
+    use bevy_hecs::World;
+
+    fn stage_system_names(schedule: &Schedule, stage_name: &'static str) -> Vec<String> {
+        schedule.stages[stage_name]
+            .iter()
+            .map(|system| system.lock().name().to_string())
+            .collect()
+    }
+
+    fn a(mut order: ResMut<Vec<&'static str>>) {
+        order.push("a");
+    }
+
+    fn b(mut order: ResMut<Vec<&'static str>>) {
+        order.push("b");
+    }
+
+    fn c(mut order: ResMut<Vec<&'static str>>) {
+        order.push("c");
+    }
+
+    #[test]
+    fn explicit_system_ordering() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(Vec::<&'static str>::new());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", a.system().after("c"));
+        schedule.add_system_to_stage("update", b.system().label("b"));
+        schedule.add_system_to_stage("update", c.system().label("c").after("b"));
+
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(
+            *resources.get::<Vec<&'static str>>().unwrap(),
+            vec!["b", "c", "a"]
+        );
+    }
+
+    #[test]
+    fn unordered_systems_keep_insertion_order() {
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        let system_a: Box<dyn System> = a.system();
+        let system_b = b.system().before("a");
+        let system_c = c.system();
+        let names = [system_a.name(), system_b.name(), system_c.name()];
+        schedule.add_system_to_stage("update", system_a.label("a"));
+        schedule.add_system_to_stage("update", system_c);
+        schedule.add_system_to_stage("update", system_b);
+        schedule.initialize(&mut Resources::default());
+
+        assert_eq!(
+            stage_system_names(&schedule, "update"),
+            vec![
+                names[2].to_string(),
+                names[1].to_string(),
+                names[0].to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn run_criteria() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(Vec::<&'static str>::new());
+        resources.insert(2usize);
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("skipped");
+        schedule.add_stage("looped");
+        schedule.add_system_to_stage("skipped", a.system());
+        schedule.add_system_to_stage("looped", b.system());
+        schedule.set_run_criteria("skipped", |_world: &World, _resources: &Resources| {
+            ShouldRun::No
+        });
+        schedule.set_run_criteria("looped", |_world: &World, resources: &Resources| {
+            let mut remaining = resources.get_mut::<usize>().unwrap();
+            if *remaining == 0 {
+                ShouldRun::No
+            } else {
+                *remaining -= 1;
+                ShouldRun::YesAndLoop
+            }
+        });
+
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(
+            *resources.get::<Vec<&'static str>>().unwrap(),
+            vec!["b", "b"]
+        );
+    }
+
+    #[test]
+    fn system_ordering_cycle() {
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", a.system().label("a").after("c"));
+        schedule.add_system_to_stage("update", b.system().label("b").after("a"));
+        schedule.add_system_to_stage("update", c.system().label("c").after("b"));
+        // adding systems does not panic, so the cycle could still be broken before the schedule is initialized
+        schedule.add_system_to_stage("update", c.system().after("a"));
+
+        let message = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            schedule.initialize(&mut Resources::default())
+        }))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap();
+        let names = stage_system_names(&schedule, "update");
+        assert_eq!(
+            *message,
+            format!(
+                "Found a cycle in the system ordering of stage update: {} -> {} -> {} -> {}",
+                names[0], names[1], names[2], names[0]
+            )
+        );
+    }
+
+    #[test]
+    fn remove_system() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(Vec::<&'static str>::new());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        let system_b = b.system();
+        let id = system_b.id();
+        schedule.add_system_to_stage("update", a.system());
+        schedule.add_system_to_stage("update", system_b);
+        schedule.add_system_to_stage("update", c.system());
+        schedule.run(&mut world, &mut resources);
+
+        assert_eq!(schedule.remove_system(id).unwrap().id(), id);
+        assert!(schedule.remove_system(id).is_none());
+        assert_eq!(schedule.system_ids().count(), 2);
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(
+            *resources.get::<Vec<&'static str>>().unwrap(),
+            vec!["a", "b", "c", "a", "c"]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use super::{run_stage, Executor, Schedule, ShouldRun};
+use crate::resource::Resources;
+use bevy_hecs::World;
+
+/// Executes a schedule on the current thread, one system at a time, in a fixed order:
+/// * stages run in the order of the schedule. A stage runs as often as its run criteria decide.
+/// * in a given stage, systems run in the order they are sorted into, which is the order they were added in, except
+///   for systems that are explicitly ordered relative to each other
+/// * the thread local part of a system with [ThreadLocalExecution::Immediate](crate::ThreadLocalExecution::Immediate)
+///   runs right after the system
+/// * at the end of each stage, the thread local parts of the remaining systems run in the same order
+///
+/// Given the same initial [World] and [Resources], this executor always runs systems in the same order, which makes it
+/// suitable for lockstep simulations and for reproducing recorded runs.
+#[derive(Debug)]
+pub struct SerialExecutor {
+    clear_trackers: bool,
+}
+
+impl Default for SerialExecutor {
+    fn default() -> Self {
+        Self {
+            clear_trackers: true,
+        }
+    }
+}
+
+impl SerialExecutor {
+    pub fn without_tracker_clears() -> Self {
+        Self {
+            clear_trackers: false,
+        }
+    }
+
+    pub fn run(&mut self, schedule: &mut Schedule, world: &mut World, resources: &mut Resources) {
+        schedule.initialize(resources);
+        for stage_name in schedule.stage_order.iter() {
+            if let Some(stage_systems) = schedule.stages.get_mut(stage_name) {
+                let mut run_criteria = schedule.run_criteria.get_mut(stage_name);
+                loop {
+                    let should_run = run_criteria
+                        .as_mut()
+                        .map_or(ShouldRun::Yes, |run_criteria| {
+                            run_criteria.should_run(world, resources)
+                        });
+                    if should_run == ShouldRun::No {
+                        break;
+                    }
+
+                    run_stage(stage_systems, world, resources);
+
+                    if should_run == ShouldRun::Yes {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if self.clear_trackers {
+            world.clear_trackers();
+            resources.clear_trackers();
+        }
+    }
+}
+
+impl Executor for SerialExecutor {
+    fn run(&mut self, schedule: &mut Schedule, world: &mut World, resources: &mut Resources) {
+        SerialExecutor::run(self, schedule, world, resources);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SerialExecutor;
+    use crate::{
+        resource::{ResMut, Resources},
+        schedule::Schedule,
+        system::{Commands, IntoQuerySystem, IntoThreadLocalSystem},
+    };
+    use bevy_hecs::World;
+
+    fn a(mut order: ResMut<Vec<&'static str>>) {
+        order.push("a");
+    }
+
+    fn b(mut commands: Commands, mut order: ResMut<Vec<&'static str>>) {
+        order.push("b");
+        commands.insert_resource(());
+    }
+
+    fn c(_world: &mut World, resources: &mut Resources) {
+        resources.get_mut::<Vec<&'static str>>().unwrap().push("c");
+    }
+
+    fn d(mut order: ResMut<Vec<&'static str>>) {
+        order.push("d");
+    }
+
+    #[test]
+    fn fixed_order() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(Vec::<&'static str>::new());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("first");
+        schedule.add_stage("second");
+        schedule.add_system_to_stage("second", d.system());
+        schedule.add_system_to_stage("first", b.system());
+        schedule.add_system_to_stage("first", c.thread_local_system());
+        schedule.add_system_to_stage("first", a.system().before("b_label"));
+        schedule.add_system_to_stage("first", d.system().label("b_label"));
+
+        let mut executor = SerialExecutor::default();
+        schedule.initialize(&mut resources);
+        for _ in 0..2 {
+            executor.run(&mut schedule, &mut world, &mut resources);
+        }
+        assert_eq!(
+            *resources.get::<Vec<&'static str>>().unwrap(),
+            vec!["b", "c", "a", "d", "d", "b", "c", "a", "d", "d"]
+        );
+        assert!(resources.get::<()>().is_some(), "commands were applied");
+    }
+}
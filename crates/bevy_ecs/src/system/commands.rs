@@ -1,6 +1,6 @@
 use super::SystemId;
 use crate::resource::{Resource, Resources};
-use bevy_hecs::{Bundle, Component, DynamicBundle, Entity, World};
+use bevy_hecs::{Bundle, Component, DynamicBundle, Entity, EntityReserver, World};
 use parking_lot::Mutex;
 use std::{marker::PhantomData, sync::Arc};
 
@@ -8,8 +8,12 @@ use std::{marker::PhantomData, sync::Arc};
 pub enum Command {
     WriteWorld(Box<dyn WorldWriter>),
     WriteResources(Box<dyn ResourcesWriter>),
+    Write(WriteFn),
 }
 
+/// A closure queued with [Commands::add]
+pub type WriteFn = Box<dyn FnOnce(&mut World, &mut Resources) + Send + Sync>;
+
 /// A [World] mutation
 pub trait WorldWriter: Send + Sync {
     fn write(self: Box<Self>, world: &mut World);
@@ -110,6 +114,24 @@ where
     }
 }
 
+pub(crate) struct Remove<T>
+where
+    T: Bundle + Send + Sync + 'static,
+{
+    entity: Entity,
+    phantom: PhantomData<T>,
+}
+
+impl<T> WorldWriter for Remove<T>
+where
+    T: Bundle + Send + Sync + 'static,
+{
+    fn write(self: Box<Self>, world: &mut World) {
+        // entities that don't have all of the bundle's components are left unchanged
+        let _ = world.remove::<T>(self.entity);
+    }
+}
+
 pub(crate) struct RemoveOne<T>
 where
     T: Component,
@@ -158,11 +180,17 @@ impl<T: Resource> ResourcesWriter for InsertLocalResource<T> {
 pub struct CommandsInternal {
     pub commands: Vec<Command>,
     pub current_entity: Option<Entity>,
+    pub entity_reserver: Option<EntityReserver>,
 }
 
 impl CommandsInternal {
     pub fn spawn(&mut self, components: impl DynamicBundle + Send + Sync + 'static) -> &mut Self {
-        self.spawn_as_entity(Entity::new(), components)
+        let entity = self
+            .entity_reserver
+            .as_ref()
+            .expect("Cannot spawn an entity because these commands do not belong to a World. Create them with Commands::new.")
+            .reserve_entity();
+        self.spawn_as_entity(entity, components)
     }
 
     pub fn spawn_as_entity(
@@ -214,6 +242,14 @@ impl CommandsInternal {
             .push(Command::WriteResources(Box::new(resources_writer)));
         self
     }
+
+    pub fn add(
+        &mut self,
+        f: impl FnOnce(&mut World, &mut Resources) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.commands.push(Command::Write(Box::new(f)));
+        self
+    }
 }
 
 /// A queue of [Command]s to run on the current [World] and [Resources]
@@ -223,8 +259,17 @@ pub struct Commands {
 }
 
 impl Commands {
+    /// Creates an empty command queue for the given [World]. Entities spawned by the queue get ids reserved in that
+    /// world. Systems create their own queue, so this is mostly useful for applying commands outside of a schedule.
+    pub fn new(world: &World) -> Self {
+        let commands = Commands::default();
+        commands.set_entity_reserver(world.get_entity_reserver());
+        commands
+    }
+
     pub fn spawn(&mut self, components: impl DynamicBundle + Send + Sync + 'static) -> &mut Self {
-        self.spawn_as_entity(Entity::new(), components)
+        self.commands.lock().spawn(components);
+        self
     }
 
     pub fn spawn_as_entity(
@@ -311,6 +356,23 @@ impl Commands {
         self
     }
 
+    /// Queues a closure that runs with mutable access to the [World] and [Resources]
+    pub fn add(
+        &mut self,
+        f: impl FnOnce(&mut World, &mut Resources) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.commands.lock().add(f);
+        self
+    }
+
+    /// Returns an [EntityCommands] that queues commands for the given entity
+    pub fn entity(&mut self, entity: Entity) -> EntityCommands<'_> {
+        EntityCommands {
+            entity,
+            commands: self,
+        }
+    }
+
     pub fn apply(&self, world: &mut World, resources: &mut Resources) {
         let mut commands = self.commands.lock();
         for command in commands.commands.drain(..) {
@@ -319,10 +381,17 @@ impl Commands {
                     writer.write(world);
                 }
                 Command::WriteResources(writer) => writer.write(resources),
+                Command::Write(f) => f(world, resources),
             }
         }
     }
 
+    /// Sets the [EntityReserver] used to pick ids for spawned entities. [Commands::new] and systems set this to the
+    /// reserver of the [World] they belong to.
+    pub fn set_entity_reserver(&self, entity_reserver: EntityReserver) {
+        self.commands.lock().entity_reserver = Some(entity_reserver);
+    }
+
     pub fn current_entity(&self) -> Option<Entity> {
         let commands = self.commands.lock();
         commands.current_entity
@@ -350,6 +419,64 @@ impl Commands {
     }
 }
 
+/// Queues commands for a single [Entity]. Unlike [Commands::with], this does not depend on the 'current entity'.
+pub struct EntityCommands<'a> {
+    entity: Entity,
+    commands: &'a mut Commands,
+}
+
+impl<'a> EntityCommands<'a> {
+    /// The entity these commands apply to
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn insert(&mut self, components: impl DynamicBundle + Send + Sync + 'static) -> &mut Self {
+        self.commands.insert(self.entity, components);
+        self
+    }
+
+    pub fn insert_one(&mut self, component: impl Component) -> &mut Self {
+        self.commands.insert_one(self.entity, component);
+        self
+    }
+
+    /// Removes the components of the bundle `T`. Nothing is removed if the entity does not have all of them.
+    pub fn remove<T>(&mut self) -> &mut Self
+    where
+        T: Bundle + Send + Sync + 'static,
+    {
+        self.commands.write_world(Remove::<T> {
+            entity: self.entity,
+            phantom: PhantomData,
+        });
+        self
+    }
+
+    pub fn remove_one<T>(&mut self) -> &mut Self
+    where
+        T: Component,
+    {
+        self.commands.remove_one::<T>(self.entity);
+        self
+    }
+
+    /// Queues a closure that runs with the entity and mutable access to the [World] and [Resources]
+    pub fn add(
+        &mut self,
+        f: impl FnOnce(Entity, &mut World, &mut Resources) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let entity = self.entity;
+        self.commands
+            .add(move |world, resources| f(entity, world, resources));
+        self
+    }
+
+    pub fn despawn(&mut self) {
+        self.commands.despawn(self.entity);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Commands;
@@ -360,7 +487,7 @@ mod tests {
     fn command_buffer() {
         let mut world = World::default();
         let mut resources = Resources::default();
-        let mut command_buffer = Commands::default();
+        let mut command_buffer = Commands::new(&world);
         command_buffer.spawn((1u32, 2u64));
         command_buffer.insert_resource(3.14f32);
         command_buffer.apply(&mut world, &mut resources);
@@ -372,4 +499,34 @@ mod tests {
         assert_eq!(results, vec![(1u32, 2u64)]);
         assert_eq!(*resources.get::<f32>().unwrap(), 3.14f32);
     }
+
+    #[test]
+    fn closures_and_entity_commands() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let a = world.spawn((1u32, 2u64));
+        let b = world.spawn((3u32,));
+        let mut commands = Commands::default();
+        commands.add(|world, resources| {
+            world.spawn((4u32,));
+            resources.insert(5u8);
+        });
+        commands
+            .entity(a)
+            .insert((6u16, 7i32))
+            .remove::<(u32, u64)>()
+            .add(|entity, world, _resources| {
+                *world.get_mut::<u16>(entity).unwrap() += 1;
+            });
+        commands.entity(b).insert_one(8u64).despawn();
+        commands.apply(&mut world, &mut resources);
+
+        assert_eq!(*resources.get::<u8>().unwrap(), 5);
+        assert!(world.get::<u32>(a).is_err());
+        assert_eq!(*world.get::<u16>(a).unwrap(), 7);
+        assert_eq!(*world.get::<i32>(a).unwrap(), 7);
+        assert!(!world.contains(b));
+        let values = world.query::<&u32>().iter().copied().collect::<Vec<_>>();
+        assert_eq!(values, vec![4]);
+    }
 }
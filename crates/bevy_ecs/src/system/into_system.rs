@@ -2,35 +2,57 @@ pub use super::Query;
 use super::TypeAccess;
 use crate::{
     resource::{FetchResource, ResourceQuery, Resources, UnsafeClone},
-    system::{ArchetypeAccess, Commands, System, SystemId, ThreadLocalExecution},
+    system::{ArchetypeAccess, Commands, System, SystemId, SystemOrdering, ThreadLocalExecution},
 };
-use bevy_hecs::{Fetch, Query as HecsQuery, World};
+use bevy_hecs::{check_tick, oldest_tick, ChangeTicks, Fetch, Query as HecsQuery, World};
 use std::borrow::Cow;
 
 pub(crate) struct SystemFn<State, F, ThreadLocalF, Init, SetArchetypeAccess>
 where
-    F: FnMut(&World, &Resources, &ArchetypeAccess, &mut State) + Send + Sync,
+    F: FnMut(&World, &Resources, &ArchetypeAccess, ChangeTicks, ChangeTicks, &mut State) -> bool
+        + Send
+        + Sync,
     ThreadLocalF: FnMut(&mut World, &mut Resources, &mut State) + Send + Sync,
     Init: FnMut(&mut Resources) + Send + Sync,
     SetArchetypeAccess: FnMut(&World, &mut ArchetypeAccess, &mut State) + Send + Sync,
     State: Send + Sync,
 {
     pub state: State,
+    /// Runs the system, returning false if it was skipped
     pub func: F,
     pub thread_local_func: ThreadLocalF,
     pub init_func: Init,
     pub thread_local_execution: ThreadLocalExecution,
     pub resource_access: TypeAccess,
+    pub component_access: TypeAccess,
     pub name: Cow<'static, str>,
     pub id: SystemId,
     pub archetype_access: ArchetypeAccess,
     pub set_archetype_access: SetArchetypeAccess,
+    pub ordering: SystemOrdering,
+    /// The world change tick of the last run. Component changes made after it are new to this
+    /// system. `None` until the system first runs.
+    pub last_change_tick: Option<u32>,
+    /// The resources change tick of the last run. Resource changes made after it are new to this
+    /// system. `None` until the system first runs.
+    pub last_resource_change_tick: Option<u32>,
+}
+
+/// Advances `last_change_tick` to `change_tick`, returning the ticks of the current run. Systems that
+/// never ran see every change.
+fn advance_ticks(last_change_tick: &mut Option<u32>, change_tick: u32) -> ChangeTicks {
+    let mut last = last_change_tick.unwrap_or_else(|| oldest_tick(change_tick));
+    check_tick(&mut last, change_tick);
+    *last_change_tick = Some(change_tick);
+    ChangeTicks::new(last, change_tick)
 }
 
 impl<State, F, ThreadLocalF, Init, SetArchetypeAccess> System
     for SystemFn<State, F, ThreadLocalF, Init, SetArchetypeAccess>
 where
-    F: FnMut(&World, &Resources, &ArchetypeAccess, &mut State) + Send + Sync,
+    F: FnMut(&World, &Resources, &ArchetypeAccess, ChangeTicks, ChangeTicks, &mut State) -> bool
+        + Send
+        + Sync,
     ThreadLocalF: FnMut(&mut World, &mut Resources, &mut State) + Send + Sync,
     Init: FnMut(&mut Resources) + Send + Sync,
     SetArchetypeAccess: FnMut(&World, &mut ArchetypeAccess, &mut State) + Send + Sync,
@@ -52,13 +74,36 @@ where
         &self.resource_access
     }
 
+    fn component_access(&self) -> &TypeAccess {
+        &self.component_access
+    }
+
     fn thread_local_execution(&self) -> ThreadLocalExecution {
         self.thread_local_execution
     }
 
     #[inline]
     fn run(&mut self, world: &World, resources: &Resources) {
-        (self.func)(world, resources, &self.archetype_access, &mut self.state);
+        let mut last_change_tick = self.last_change_tick;
+        let mut last_resource_change_tick = self.last_resource_change_tick;
+        let ticks = advance_ticks(&mut last_change_tick, world.increment_change_tick());
+        let resource_ticks = advance_ticks(
+            &mut last_resource_change_tick,
+            resources.increment_change_tick(),
+        );
+        let ran = (self.func)(
+            world,
+            resources,
+            &self.archetype_access,
+            ticks,
+            resource_ticks,
+            &mut self.state,
+        );
+        // a skipped system sees the changes made since its last run when it runs again
+        if ran {
+            self.last_change_tick = last_change_tick;
+            self.last_resource_change_tick = last_resource_change_tick;
+        }
     }
 
     fn run_thread_local(&mut self, world: &mut World, resources: &mut Resources) {
@@ -72,6 +117,14 @@ where
     fn id(&self) -> SystemId {
         self.id
     }
+
+    fn ordering(&self) -> &SystemOrdering {
+        &self.ordering
+    }
+
+    fn ordering_mut(&mut self) -> Option<&mut SystemOrdering> {
+        Some(&mut self.ordering)
+    }
 }
 
 /// Converts `Self` into a For-Each system
@@ -103,15 +156,20 @@ macro_rules! impl_into_foreach_system {
                     thread_local_execution: ThreadLocalExecution::NextFlush,
                     name: core::any::type_name::<Self>().into(),
                     id,
-                    func: move |world, resources, _archetype_access, state| {
+                    func: move |world, resources, _archetype_access, ticks, resource_ticks, state| {
+                        state.set_entity_reserver(world.get_entity_reserver());
+                        if <<($($resource,)*) as ResourceQuery>::Fetch as FetchResource>::should_skip(&resources, resource_ticks) {
+                            return false;
+                        }
                         <<($($resource,)*) as ResourceQuery>::Fetch as FetchResource>::borrow(&resources);
                         {
-                            let ($($resource,)*) = resources.query_system::<($($resource,)*)>(id);
-                            for ($($component,)*) in world.query::<($($component,)*)>().iter() {
+                            let ($($resource,)*) = resources.query_system::<($($resource,)*)>(id, resource_ticks);
+                            for ($($component,)*) in world.query_with_ticks::<($($component,)*)>(ticks).iter() {
                                 fn_call!(self, ($($commands, state)*), ($($resource),*), ($($component),*))
                             }
                         }
                         <<($($resource,)*) as ResourceQuery>::Fetch as FetchResource>::release(&resources);
+                        true
                     },
                     thread_local_func: move |world, resources, state| {
                         state.apply(world, resources);
@@ -120,11 +178,19 @@ macro_rules! impl_into_foreach_system {
                         <($($resource,)*)>::initialize(resources, Some(id));
                     },
                     resource_access: <<($($resource,)*) as ResourceQuery>::Fetch as FetchResource>::access(),
+                    component_access: {
+                        let mut component_access = TypeAccess::default();
+                        component_access.add_query::<($($component,)*)>();
+                        component_access
+                    },
                     archetype_access: ArchetypeAccess::default(),
                     set_archetype_access: |world, archetype_access, _state| {
                         archetype_access.clear();
                         archetype_access.set_access_for_query::<($($component,)*)>(world);
                     },
+                    ordering: SystemOrdering::default(),
+                    last_change_tick: None,
+                    last_resource_change_tick: None,
                 })
             }
         }
@@ -172,13 +238,17 @@ macro_rules! impl_into_query_system {
                     thread_local_execution: ThreadLocalExecution::NextFlush,
                     id,
                     name: core::any::type_name::<Self>().into(),
-                    func: move |world, resources, archetype_access, state| {
+                    func: move |world, resources, archetype_access, ticks, resource_ticks, state| {
+                        state.commands.set_entity_reserver(world.get_entity_reserver());
+                        if <<($($resource,)*) as ResourceQuery>::Fetch as FetchResource>::should_skip(&resources, resource_ticks) {
+                            return false;
+                        }
                         <<($($resource,)*) as ResourceQuery>::Fetch as FetchResource>::borrow(&resources);
                         {
-                            let ($($resource,)*) = resources.query_system::<($($resource,)*)>(id);
+                            let ($($resource,)*) = resources.query_system::<($($resource,)*)>(id, resource_ticks);
                             let mut i = 0;
                             $(
-                                let $query = Query::<$query>::new(world, &state.archetype_accesses[i]);
+                                let $query = Query::<$query>::new(world, &state.archetype_accesses[i], ticks);
                                 i += 1;
                             )*
 
@@ -186,6 +256,7 @@ macro_rules! impl_into_query_system {
                             fn_call!(self, ($($commands, commands)*), ($($resource),*), ($($query),*))
                         }
                         <<($($resource,)*) as ResourceQuery>::Fetch as FetchResource>::release(&resources);
+                        true
                     },
                     thread_local_func: move |world, resources, state| {
                         state.commands.apply(world, resources);
@@ -194,6 +265,11 @@ macro_rules! impl_into_query_system {
                         <($($resource,)*)>::initialize(resources, Some(id));
                     },
                     resource_access: <<($($resource,)*) as ResourceQuery>::Fetch as FetchResource>::access(),
+                    component_access: {
+                        let mut component_access = TypeAccess::default();
+                        $(component_access.add_query::<$query>();)*
+                        component_access
+                    },
                     archetype_access: ArchetypeAccess::default(),
                     set_archetype_access: |world, archetype_access, state| {
                         archetype_access.clear();
@@ -207,6 +283,9 @@ macro_rules! impl_into_query_system {
                             i += 1;
                          )*
                     },
+                    ordering: SystemOrdering::default(),
+                    last_change_tick: None,
+                    last_resource_change_tick: None,
                 })
             }
         }
@@ -314,14 +393,18 @@ where
             thread_local_func: move |world, resources, _| {
                 self.run(world, resources);
             },
-            func: |_, _, _, _| {},
+            func: |_, _, _, _, _, _| true,
             init_func: |_| {},
             set_archetype_access: |_, _, _| {},
             thread_local_execution: ThreadLocalExecution::Immediate,
             name: core::any::type_name::<F>().into(),
             id: SystemId::new(),
             resource_access: TypeAccess::default(),
+            component_access: TypeAccess::default(),
             archetype_access: ArchetypeAccess::default(),
+            ordering: SystemOrdering::default(),
+            last_change_tick: None,
+            last_resource_change_tick: None,
         })
     }
 }
@@ -344,10 +427,10 @@ where
 mod tests {
     use super::{IntoQuerySystem, Query};
     use crate::{
-        resource::{ResMut, Resources},
+        resource::{ChangedRes, Res, ResMut, Resources},
         schedule::Schedule,
     };
-    use bevy_hecs::{Entity, With, World};
+    use bevy_hecs::{Changed, Entity, With, World};
 
     struct A;
     struct B;
@@ -415,4 +498,159 @@ mod tests {
 
         assert!(*resources.get::<bool>().unwrap(), "system ran");
     }
+
+    #[test]
+    fn changed_resource_system() {
+        fn incr_e_on_flip(_run_on_flip: ChangedRes<bool>, mut i: ResMut<i32>) {
+            *i += 1;
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(false);
+        resources.insert(0);
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", incr_e_on_flip.system());
+
+        // the resource was just added
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<i32>().unwrap(), 1);
+
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<i32>().unwrap(), 1);
+
+        *resources.get_mut::<bool>().unwrap() = true;
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<i32>().unwrap(), 2);
+
+        resources.insert(false);
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<i32>().unwrap(), 3);
+    }
+
+    #[test]
+    fn skipped_system_sees_changes() {
+        fn count_changes(
+            _flag: ChangedRes<bool>,
+            mut changes: ResMut<usize>,
+            mut query: Query<Changed<i32>>,
+        ) {
+            *changes += query.iter().iter().count();
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(false);
+        resources.insert(0usize);
+        let entity = world.spawn((0,));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", count_changes.system());
+
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 1);
+
+        // the flag is unchanged, so the system is skipped in this frame
+        *world.get_mut::<i32>(entity).unwrap() += 1;
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 1);
+
+        *resources.get_mut::<bool>().unwrap() = true;
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 2);
+    }
+
+    #[test]
+    fn optional_resource_system() {
+        fn add_step(step: Option<Res<u32>>, mut i: ResMut<i32>) {
+            *i += step.map_or(1, |step| *step as i32);
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(0);
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", add_step.system());
+
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<i32>().unwrap(), 1);
+
+        resources.insert(10u32);
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<i32>().unwrap(), 11);
+    }
+
+    #[test]
+    fn resource_change_trackers() {
+        fn writer(mut value: ResMut<i32>, flag: Res<bool>) {
+            if *flag {
+                *value += 1;
+            }
+        }
+
+        fn reader(value: Res<i32>, mut changes: ResMut<Vec<(bool, bool)>>) {
+            changes.push((value.is_added(), value.is_changed()));
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(0);
+        resources.insert(false);
+        resources.insert(Vec::<(bool, bool)>::new());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_stage("read");
+        schedule.add_system_to_stage("update", writer.system());
+        schedule.add_system_to_stage("read", reader.system());
+
+        schedule.run(&mut world, &mut resources);
+        schedule.run(&mut world, &mut resources);
+        *resources.get_mut::<bool>().unwrap() = true;
+        schedule.run(&mut world, &mut resources);
+
+        assert_eq!(
+            *resources.get::<Vec<(bool, bool)>>().unwrap(),
+            vec![(true, true), (false, false), (false, true)]
+        );
+    }
+
+    #[test]
+    fn changes_are_seen_regardless_of_stage_order() {
+        fn reader(mut changes: ResMut<usize>, mut query: Query<Changed<i32>>) {
+            *changes += query.iter().iter().count();
+        }
+
+        fn writer(mut query: Query<&mut i32>) {
+            for mut value in &mut query.iter() {
+                *value += 1;
+            }
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(0usize);
+        world.spawn((0i32,));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("read");
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("read", reader.system());
+        schedule.add_system_to_stage("update", writer.system());
+
+        // the reader runs first and sees the component being added
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 1);
+
+        // the writer mutated the component after the reader ran in the previous update
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 2);
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 3);
+    }
 }
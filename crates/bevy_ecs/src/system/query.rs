@@ -1,17 +1,26 @@
 use crate::ArchetypeAccess;
 use bevy_hecs::{
-    Archetype, Component, ComponentError, Entity, Fetch, Query as HecsQuery, QueryOne, Ref, RefMut,
-    World,
+    Archetype, ChangeTicks, Component, ComponentError, Entity, Fetch, Query as HecsQuery, QueryOne,
+    Ref, RefMut, World,
+};
+use bevy_tasks::TaskPool;
+use std::{
+    convert::TryInto,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
 };
-use std::marker::PhantomData;
 
 /// Provides scoped access to a World according to a given [HecsQuery]
 pub struct Query<'a, Q: HecsQuery> {
     pub(crate) world: &'a World,
     pub(crate) archetype_access: &'a ArchetypeAccess,
+    ticks: ChangeTicks,
     _marker: PhantomData<Q>,
 }
 
+/// The result of the query `Q` for a single entity
+pub type QueryItem<'a, Q> = <<Q as HecsQuery>::Fetch as Fetch<'a>>::Item;
+
 /// An error that occurs when using a [Query]
 #[derive(Debug)]
 pub enum QueryError {
@@ -19,21 +28,49 @@ pub enum QueryError {
     CannotWriteArchetype,
     ComponentError(ComponentError),
     NoSuchEntity,
+    /// The entity exists, but does not have the components of the query
+    NoMatch(Entity),
+    /// The same entity was requested more than once, which would alias its components
+    AliasedEntity(Entity),
+    /// [Query::single] found no entities
+    NoEntities,
+    /// [Query::single] found more than one entity
+    MultipleEntities,
 }
 
 impl<'a, Q: HecsQuery> Query<'a, Q> {
+    /// Creates a query that detects changes made after `ticks.last_change_tick`
     #[inline]
-    pub fn new(world: &'a World, archetype_access: &'a ArchetypeAccess) -> Self {
+    pub fn new(
+        world: &'a World,
+        archetype_access: &'a ArchetypeAccess,
+        ticks: ChangeTicks,
+    ) -> Self {
         Self {
             world,
             archetype_access,
+            ticks,
             _marker: PhantomData::default(),
         }
     }
 
     #[inline]
     pub fn iter(&mut self) -> QueryBorrow<'_, Q> {
-        QueryBorrow::new(&self.world.archetypes, self.archetype_access)
+        QueryBorrow::new(self.world, self.archetype_access, self.ticks)
+    }
+
+    /// Iterates over the query results in parallel, in batches of at most `batch_size` entities.
+    /// See [ParIter::for_each].
+    #[inline]
+    pub fn par_iter(&mut self, batch_size: usize) -> ParIter<'_, Q> {
+        assert!(
+            batch_size > 0,
+            "par_iter batch size must be greater than zero"
+        );
+        ParIter {
+            borrow: QueryBorrow::new(self.world, self.archetype_access, self.ticks),
+            batch_size: batch_size as u32,
+        }
     }
 
     /// Gets a reference to the entity's component of the given type. This will fail if the entity does not have
@@ -69,7 +106,13 @@ impl<'a, Q: HecsQuery> Query<'a, Q> {
                     .mutable
                     .contains(location.archetype as usize)
             {
-                Ok(self.world.query_one(entity).unwrap())
+                Ok(unsafe {
+                    QueryOne::new(
+                        &self.world.archetypes[location.archetype as usize],
+                        location.index,
+                        self.ticks,
+                    )
+                })
             } else {
                 Err(QueryError::CannotReadArchetype)
             }
@@ -91,9 +134,14 @@ impl<'a, Q: HecsQuery> Query<'a, Q> {
             .mutable
             .contains(location.archetype as usize)
         {
-            self.world
-                .get_mut(entity)
-                .map_err(QueryError::ComponentError)
+            unsafe {
+                RefMut::new(
+                    &self.world.archetypes[location.archetype as usize],
+                    location.index,
+                    self.ticks.change_tick,
+                )
+                .map_err(|err| QueryError::ComponentError(err.into()))
+            }
         } else {
             Err(QueryError::CannotWriteArchetype)
         }
@@ -110,19 +158,216 @@ impl<'a, Q: HecsQuery> Query<'a, Q> {
         *current = component;
         Ok(())
     }
+
+    /// Returns the query result of the only entity that matches this query. This fails if no entity or more than one
+    /// entity matches. Use [Query::single_mut] for queries that write components.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::{prelude::*, ArchetypeAccess};
+    /// let mut world = World::new();
+    /// world.spawn((1u32,));
+    /// let mut access = ArchetypeAccess::default();
+    /// access.set_access_for_query::<&u32>(&world);
+    /// let query = Query::<&u32>::new(&world, &access, world.change_ticks());
+    /// assert_eq!(**query.single().unwrap(), 1);
+    /// ```
+    pub fn single(&self) -> Result<QueryGuard<'_, Q, QueryItem<'_, Q>>, QueryError> {
+        if self.archetype_access.mutable.ones().next().is_some() {
+            return Err(QueryError::CannotWriteArchetype);
+        }
+        // SAFE: the query only reads components
+        unsafe { self.single_unchecked() }
+    }
+
+    /// Returns the query result of the only entity that matches this query. This fails if no entity or more than one
+    /// entity matches.
+    pub fn single_mut(&mut self) -> Result<QueryGuard<'_, Q, QueryItem<'_, Q>>, QueryError> {
+        // SAFE: the query is borrowed mutably for as long as the result lives
+        unsafe { self.single_unchecked() }
+    }
+
+    unsafe fn single_unchecked(&self) -> Result<QueryGuard<'_, Q, QueryItem<'_, Q>>, QueryError> {
+        let guard = QueryGuard::<Q, ()>::borrow(
+            self.archetype_access
+                .immutable
+                .ones()
+                .chain(self.archetype_access.mutable.ones())
+                .map(|index| &self.world.archetypes[index])
+                .collect(),
+        );
+
+        let mut result = None;
+        for archetype in self.world.archetypes.iter() {
+            let mut iter = match Q::Fetch::get(archetype, 0, self.ticks) {
+                Some(fetch) => ChunkIter::<Q> {
+                    fetch,
+                    len: archetype.len(),
+                },
+                None => continue,
+            };
+            while let Some(item) = iter.next() {
+                if result.is_some() {
+                    return Err(QueryError::MultipleEntities);
+                }
+                result = Some(item);
+            }
+        }
+
+        match result {
+            Some(item) => Ok(guard.with_value(item)),
+            None => Err(QueryError::NoEntities),
+        }
+    }
+
+    /// Returns the query results of several distinct entities at once, in the order of `entities`. This fails if an
+    /// entity does not match this query or if the same entity is requested twice.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::{prelude::*, ArchetypeAccess};
+    /// let mut world = World::new();
+    /// let a = world.spawn((1u32,));
+    /// let b = world.spawn((2u32,));
+    /// let mut access = ArchetypeAccess::default();
+    /// access.set_access_for_query::<&mut u32>(&world);
+    /// let mut query = Query::<&mut u32>::new(&world, &access, world.change_ticks());
+    /// let mut results = query.get_many_mut([a, b]).unwrap();
+    /// let [x, y] = &mut *results;
+    /// std::mem::swap(&mut **x, &mut **y);
+    /// # drop(results);
+    /// # assert_eq!(*world.get::<u32>(a).unwrap(), 2);
+    /// ```
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        entities: [Entity; N],
+    ) -> Result<QueryGuard<'_, Q, [QueryItem<'_, Q>; N]>, QueryError> {
+        let mut locations = Vec::with_capacity(N);
+        for (i, &entity) in entities.iter().enumerate() {
+            if entities[..i].contains(&entity) {
+                return Err(QueryError::AliasedEntity(entity));
+            }
+            let location = self
+                .world
+                .get_entity_location(entity)
+                .ok_or(QueryError::NoSuchEntity)?;
+            let archetype = location.archetype as usize;
+            if !self.archetype_access.immutable.contains(archetype)
+                && !self.archetype_access.mutable.contains(archetype)
+            {
+                return Err(QueryError::NoMatch(entity));
+            }
+            locations.push(location);
+        }
+
+        // entities in the same archetype share borrows, so each archetype is only borrowed once
+        let mut archetypes = locations
+            .iter()
+            .map(|location| location.archetype as usize)
+            .collect::<Vec<_>>();
+        archetypes.sort_unstable();
+        archetypes.dedup();
+        let guard = QueryGuard::<Q, ()>::borrow(
+            archetypes
+                .into_iter()
+                .map(|index| &self.world.archetypes[index])
+                .collect(),
+        );
+
+        let mut items = Vec::with_capacity(N);
+        for (location, &entity) in locations.iter().zip(entities.iter()) {
+            // SAFE: the entities are distinct, so their components don't alias, and the query is borrowed mutably for
+            // as long as the results live
+            unsafe {
+                let mut fetch = Q::Fetch::get(
+                    &self.world.archetypes[location.archetype as usize],
+                    location.index as usize,
+                    self.ticks,
+                )
+                .ok_or(QueryError::NoMatch(entity))?;
+                if fetch.is_missing() || fetch.should_skip() {
+                    return Err(QueryError::NoMatch(entity));
+                }
+                items.push(fetch.next());
+            }
+        }
+
+        let items = match items.try_into() {
+            Ok(items) => items,
+            Err(_) => unreachable!("one item is fetched per entity"),
+        };
+        Ok(guard.with_value(items))
+    }
+}
+
+/// Query results that keep the components of their archetypes borrowed until dropped. Dereferences to the results.
+pub struct QueryGuard<'a, Q: HecsQuery, T> {
+    value: T,
+    archetypes: Vec<&'a Archetype>,
+    _marker: PhantomData<Q>,
+}
+
+impl<'a, Q: HecsQuery> QueryGuard<'a, Q, ()> {
+    fn borrow(archetypes: Vec<&'a Archetype>) -> Self {
+        for archetype in archetypes.iter() {
+            Q::Fetch::borrow(archetype);
+        }
+        Self {
+            value: (),
+            archetypes,
+            _marker: PhantomData,
+        }
+    }
+
+    fn with_value<T>(mut self, value: T) -> QueryGuard<'a, Q, T> {
+        QueryGuard {
+            value,
+            archetypes: std::mem::take(&mut self.archetypes),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, Q: HecsQuery, T> Deref for QueryGuard<'a, Q, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, Q: HecsQuery, T> DerefMut for QueryGuard<'a, Q, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<'a, Q: HecsQuery, T> Drop for QueryGuard<'a, Q, T> {
+    fn drop(&mut self) {
+        for archetype in self.archetypes.iter() {
+            Q::Fetch::release(archetype);
+        }
+    }
 }
 
 /// A borrow of a `World` sufficient to execute the query `Q`
 ///
 /// Note that borrows are not released until this object is dropped.
 pub struct QueryBorrow<'w, Q: HecsQuery> {
+    world: &'w World,
     archetypes: &'w [Archetype],
     archetype_access: &'w ArchetypeAccess,
+    ticks: ChangeTicks,
     _marker: PhantomData<Q>,
 }
 
 impl<'w, Q: HecsQuery> QueryBorrow<'w, Q> {
-    pub(crate) fn new(archetypes: &'w [Archetype], archetype_access: &'w ArchetypeAccess) -> Self {
+    pub(crate) fn new(
+        world: &'w World,
+        archetype_access: &'w ArchetypeAccess,
+        ticks: ChangeTicks,
+    ) -> Self {
+        let archetypes = &world.archetypes;
         for index in archetype_access.immutable.ones() {
             Q::Fetch::borrow(&archetypes[index]);
         }
@@ -131,8 +376,10 @@ impl<'w, Q: HecsQuery> QueryBorrow<'w, Q> {
             Q::Fetch::borrow(&archetypes[index]);
         }
         Self {
+            world,
             archetypes,
             archetype_access,
+            ticks,
             _marker: PhantomData,
         }
     }
@@ -143,11 +390,28 @@ impl<'w, Q: HecsQuery> QueryBorrow<'w, Q> {
     #[inline]
     pub fn iter<'q>(&'q mut self) -> QueryIter<'q, 'w, Q> {
         QueryIter {
+            sparse_entities: self.sparse_entities(),
+            sparse_index: 0,
             borrow: self,
             archetype_index: 0,
             iter: None,
         }
     }
+
+    /// The entities of the sparse set required by `Q`, if any
+    fn sparse_entities(&self) -> Option<&'w [Entity]> {
+        let set = Q::Fetch::sparse_set(self.archetypes.first()?)?;
+        // SAFE: sparse sets live as long as the world, and can't change while it is borrowed
+        Some(unsafe { &*(set.as_ref().entities() as *const [Entity]) })
+    }
+
+    /// Fetches the components of `entity`, if it is in an archetype that matches the query
+    unsafe fn get_entity(&self, entity: Entity) -> Option<ChunkIter<Q>> {
+        let location = self.world.get_entity_location(entity)?;
+        let archetype = &self.archetypes[location.archetype as usize];
+        Q::Fetch::get(archetype, location.index as usize, self.ticks)
+            .map(|fetch| ChunkIter { fetch, len: 1 })
+    }
 }
 
 unsafe impl<'w, Q: HecsQuery> Send for QueryBorrow<'w, Q> {}
@@ -181,6 +445,10 @@ pub struct QueryIter<'q, 'w, Q: HecsQuery> {
     borrow: &'q mut QueryBorrow<'w, Q>,
     archetype_index: u32,
     iter: Option<ChunkIter<Q>>,
+    // when `Q` requires a component stored in a sparse set, the entities of that set are visited
+    // instead of the archetypes
+    sparse_entities: Option<&'w [Entity]>,
+    sparse_index: usize,
 }
 
 unsafe impl<'q, 'w, Q: HecsQuery> Send for QueryIter<'q, 'w, Q> {}
@@ -191,16 +459,29 @@ impl<'q, 'w, Q: HecsQuery> Iterator for QueryIter<'q, 'w, Q> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(entities) = self.sparse_entities {
+            while let Some(entity) = entities.get(self.sparse_index) {
+                self.sparse_index += 1;
+                if let Some(mut iter) = unsafe { self.borrow.get_entity(*entity) } {
+                    if let Some(components) = unsafe { iter.next() } {
+                        return Some(components);
+                    }
+                }
+            }
+            return None;
+        }
+
         loop {
             match self.iter {
                 None => {
                     let archetype = self.borrow.archetypes.get(self.archetype_index as usize)?;
                     self.archetype_index += 1;
                     unsafe {
-                        self.iter = Q::Fetch::get(archetype, 0).map(|fetch| ChunkIter {
-                            fetch,
-                            len: archetype.len(),
-                        });
+                        self.iter =
+                            Q::Fetch::get(archetype, 0, self.borrow.ticks).map(|fetch| ChunkIter {
+                                fetch,
+                                len: archetype.len(),
+                            });
                     }
                 }
                 Some(ref mut iter) => match unsafe { iter.next() } {
@@ -224,6 +505,17 @@ impl<'q, 'w, Q: HecsQuery> Iterator for QueryIter<'q, 'w, Q> {
 
 impl<'q, 'w, Q: HecsQuery> ExactSizeIterator for QueryIter<'q, 'w, Q> {
     fn len(&self) -> usize {
+        if let Some(entities) = self.sparse_entities {
+            return entities
+                .iter()
+                .filter(|entity| unsafe {
+                    self.borrow
+                        .get_entity(**entity)
+                        .is_some_and(|iter| iter.matches())
+                })
+                .count();
+        }
+
         self.borrow
             .archetypes
             .iter()
@@ -247,13 +539,200 @@ impl<Q: HecsQuery> ChunkIter<Q> {
             }
 
             self.len -= 1;
-            if self.fetch.should_skip() {
+            if !self.matches() {
                 // we still need to progress the iterator
-                let _ = self.fetch.next();
+                self.fetch.skip();
                 continue;
             }
 
             break Some(self.fetch.next());
         }
     }
+
+    /// Whether the current entity matches the query
+    #[inline]
+    unsafe fn matches(&self) -> bool {
+        !self.fetch.is_missing() && !self.fetch.should_skip()
+    }
+}
+
+/// Parallel iterator over the set of entities with the components in `Q`
+///
+/// Each archetype is split into batches of at most `batch_size` entities, which run as separate
+/// tasks on a [TaskPool].
+pub struct ParIter<'w, Q: HecsQuery> {
+    borrow: QueryBorrow<'w, Q>,
+    batch_size: u32,
+}
+
+impl<'w, Q: HecsQuery> ParIter<'w, Q> {
+    /// Calls `f` on every query result, spreading the batches across `task_pool`. Returns once all
+    /// batches have been processed.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::{prelude::*, ArchetypeAccess};
+    /// # use bevy_tasks::TaskPool;
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..100).map(|i| (i,)));
+    /// let mut access = ArchetypeAccess::default();
+    /// access.set_access_for_query::<&mut i32>(&world);
+    /// let mut query = Query::<&mut i32>::new(&world, &access, world.change_ticks());
+    /// query.par_iter(8).for_each(&TaskPool::new(), |mut i| *i *= 2);
+    /// assert_eq!(query.iter().iter().map(|i| *i).sum::<i32>(), 9900);
+    /// ```
+    pub fn for_each<'q, F>(&'q mut self, task_pool: &TaskPool, f: F)
+    where
+        F: Fn(<Q::Fetch as Fetch<'q>>::Item) + Send + Sync,
+    {
+        let mut batches = Vec::new();
+        for archetype in self.borrow.archetypes {
+            let mut offset = 0;
+            while offset < archetype.len() {
+                let fetch =
+                    match unsafe { Q::Fetch::get(archetype, offset as usize, self.borrow.ticks) } {
+                        Some(fetch) => fetch,
+                        None => break,
+                    };
+                batches.push(Batch::<Q> {
+                    iter: ChunkIter {
+                        fetch,
+                        len: self.batch_size.min(archetype.len() - offset),
+                    },
+                });
+                offset += self.batch_size;
+            }
+        }
+
+        let f = &f;
+        task_pool.scope(move |scope| {
+            for mut batch in batches {
+                scope.spawn(async move {
+                    while let Some(item) = unsafe { batch.iter.next() } {
+                        f(item);
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// A batch of entities from a single archetype, processed by one task of a [ParIter]
+struct Batch<Q: HecsQuery> {
+    iter: ChunkIter<Q>,
+}
+
+// SAFE: batches never overlap, and the `QueryBorrow` of the `ParIter` holds the borrows of their
+// components until all batches are done
+unsafe impl<Q: HecsQuery> Send for Batch<Q> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Query, QueryError};
+    use crate::ArchetypeAccess;
+    use bevy_hecs::{ComponentStorage, Entity, Mutated, World};
+
+    fn query_access<Q: bevy_hecs::Query>(world: &World) -> ArchetypeAccess {
+        let mut access = ArchetypeAccess::default();
+        access.set_access_for_query::<Q>(world);
+        access
+    }
+
+    #[test]
+    fn single() {
+        let mut world = World::new();
+        let access = query_access::<&u32>(&world);
+        let query = Query::<&u32>::new(&world, &access, world.change_ticks());
+        assert!(matches!(query.single(), Err(QueryError::NoEntities)));
+
+        world.spawn((1u32,));
+        world.spawn((2u64,));
+        let access = query_access::<(Entity, &u32)>(&world);
+        let query = Query::<(Entity, &u32)>::new(&world, &access, world.change_ticks());
+        assert_eq!(*query.single().unwrap().1, 1);
+
+        world.spawn((3u32, 4u64));
+        let access = query_access::<&u32>(&world);
+        let query = Query::<&u32>::new(&world, &access, world.change_ticks());
+        assert!(matches!(query.single(), Err(QueryError::MultipleEntities)));
+    }
+
+    #[test]
+    fn single_mut() {
+        let mut world = World::new();
+        let entity = world.spawn((1u32,));
+        let access = query_access::<&mut u32>(&world);
+        let mut query = Query::<&mut u32>::new(&world, &access, world.change_ticks());
+        assert!(matches!(
+            query.single(),
+            Err(QueryError::CannotWriteArchetype)
+        ));
+        **query.single_mut().unwrap() += 1;
+        // the borrows were released
+        **query.single_mut().unwrap() += 1;
+        assert_eq!(*world.get::<u32>(entity).unwrap(), 3);
+    }
+
+    #[test]
+    fn get_many_mut() {
+        let mut world = World::new();
+        let a = world.spawn((1u32,));
+        let b = world.spawn((2u32,));
+        let c = world.spawn((3u32, 0u64));
+        let d = world.spawn((4u64,));
+        let access = query_access::<&mut u32>(&world);
+        let mut query = Query::<&mut u32>::new(&world, &access, world.change_ticks());
+
+        {
+            // a and b share an archetype, c is in another one
+            let mut results = query.get_many_mut([a, b, c]).unwrap();
+            let [a, b, c] = &mut *results;
+            std::mem::swap(&mut **a, &mut **b);
+            **c += 10;
+        }
+        assert!(matches!(
+            query.get_many_mut([a, c, a]),
+            Err(QueryError::AliasedEntity(entity)) if entity == a
+        ));
+        assert!(matches!(
+            query.get_many_mut([a, d]),
+            Err(QueryError::NoMatch(entity)) if entity == d
+        ));
+        assert_eq!(*query.get_many_mut([b]).unwrap()[0], 1);
+
+        assert_eq!(*world.get::<u32>(a).unwrap(), 2);
+        assert_eq!(*world.get::<u32>(c).unwrap(), 13);
+
+        // entities that don't pass the query's filters don't match
+        let e = world.spawn((5u32, 0u64));
+        world.clear_trackers();
+        *world.get_mut::<u64>(c).unwrap() += 1;
+        let access = query_access::<(&mut u32, Mutated<u64>)>(&world);
+        let mut query =
+            Query::<(&mut u32, Mutated<u64>)>::new(&world, &access, world.change_ticks());
+        assert_eq!(*query.get_many_mut([c]).unwrap()[0].1, 1);
+        assert!(matches!(
+            query.get_many_mut([c, e]),
+            Err(QueryError::NoMatch(entity)) if entity == e
+        ));
+    }
+
+    #[test]
+    fn sparse_iter() {
+        let mut world = World::new();
+        world.set_component_storage::<bool>(ComponentStorage::SparseSet);
+        let a = world.spawn((1u32, true));
+        world.spawn((2u32,));
+        world.spawn((3u64, false));
+        let b = world.spawn((4u32, 5u64, false));
+        let access = query_access::<(Entity, &u32, &bool)>(&world);
+        let mut query = Query::<(Entity, &u32, &bool)>::new(&world, &access, world.change_ticks());
+
+        let mut borrow = query.iter();
+        let iter = borrow.iter();
+        assert_eq!(iter.len(), 2);
+        let mut results = iter.map(|(e, i, b)| (e, *i, *b)).collect::<Vec<_>>();
+        results.sort_by_key(|(_, i, _)| *i);
+        assert_eq!(results, &[(a, 1, true), (b, 4, false)]);
+    }
 }
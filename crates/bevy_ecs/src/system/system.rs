@@ -1,6 +1,6 @@
 use crate::resource::Resources;
-use bevy_hecs::{Access, Query, World};
-use bevy_utils::HashSet;
+use bevy_hecs::{Access, Fetch, Query, World};
+use bevy_utils::{HashMap, HashSet};
 use fixedbitset::FixedBitSet;
 use std::{any::TypeId, borrow::Cow};
 
@@ -28,14 +28,139 @@ pub trait System: Send + Sync {
     fn update_archetype_access(&mut self, world: &World);
     fn archetype_access(&self) -> &ArchetypeAccess;
     fn resource_access(&self) -> &TypeAccess;
+    /// The component types this system's queries read and write, whichever archetypes they are in
+    fn component_access(&self) -> &TypeAccess;
     fn thread_local_execution(&self) -> ThreadLocalExecution;
     fn run(&mut self, world: &World, resources: &Resources);
     fn run_thread_local(&mut self, world: &mut World, resources: &mut Resources);
     fn initialize(&mut self, _resources: &mut Resources) {}
+
+    /// The labels and explicit ordering of this system. Systems that don't store an ordering have none.
+    fn ordering(&self) -> &SystemOrdering {
+        &NO_ORDERING
+    }
+
+    /// Returns None if this system does not store an ordering. [System::label], [System::before] and [System::after]
+    /// then wrap it in a system that does.
+    fn ordering_mut(&mut self) -> Option<&mut SystemOrdering> {
+        None
+    }
+}
+
+static NO_ORDERING: SystemOrdering = SystemOrdering {
+    labels: Vec::new(),
+    before: Vec::new(),
+    after: Vec::new(),
+};
+
+impl dyn System {
+    /// Adds a label to this system. Other systems in the same stage can use the label to order themselves relative to it.
+    pub fn label(self: Box<Self>, label: impl Into<Cow<'static, str>>) -> Box<Self> {
+        self.with_ordering(|ordering| ordering.labels.push(label.into()))
+    }
+
+    /// Runs this system before all systems in the same stage that have the given label
+    pub fn before(self: Box<Self>, label: impl Into<Cow<'static, str>>) -> Box<Self> {
+        self.with_ordering(|ordering| ordering.before.push(label.into()))
+    }
+
+    /// Runs this system after all systems in the same stage that have the given label
+    pub fn after(self: Box<Self>, label: impl Into<Cow<'static, str>>) -> Box<Self> {
+        self.with_ordering(|ordering| ordering.after.push(label.into()))
+    }
+
+    fn with_ordering(mut self: Box<Self>, f: impl FnOnce(&mut SystemOrdering)) -> Box<Self> {
+        if let Some(ordering) = self.ordering_mut() {
+            f(ordering);
+            return self;
+        }
+        let mut ordering = SystemOrdering::default();
+        f(&mut ordering);
+        Box::new(OrderedSystem {
+            system: self,
+            ordering,
+        })
+    }
+}
+
+/// Gives an ordering to a [System] that does not store one itself
+struct OrderedSystem {
+    system: Box<dyn System>,
+    ordering: SystemOrdering,
+}
+
+impl System for OrderedSystem {
+    fn name(&self) -> Cow<'static, str> {
+        self.system.name()
+    }
+
+    fn id(&self) -> SystemId {
+        self.system.id()
+    }
+
+    fn update_archetype_access(&mut self, world: &World) {
+        self.system.update_archetype_access(world);
+    }
+
+    fn archetype_access(&self) -> &ArchetypeAccess {
+        self.system.archetype_access()
+    }
+
+    fn resource_access(&self) -> &TypeAccess {
+        self.system.resource_access()
+    }
+
+    fn component_access(&self) -> &TypeAccess {
+        self.system.component_access()
+    }
+
+    fn thread_local_execution(&self) -> ThreadLocalExecution {
+        self.system.thread_local_execution()
+    }
+
+    fn run(&mut self, world: &World, resources: &Resources) {
+        self.system.run(world, resources);
+    }
+
+    fn run_thread_local(&mut self, world: &mut World, resources: &mut Resources) {
+        self.system.run_thread_local(world, resources);
+    }
+
+    fn initialize(&mut self, resources: &mut Resources) {
+        self.system.initialize(resources);
+    }
+
+    fn ordering(&self) -> &SystemOrdering {
+        &self.ordering
+    }
+
+    fn ordering_mut(&mut self) -> Option<&mut SystemOrdering> {
+        Some(&mut self.ordering)
+    }
+}
+
+/// The labels of a [System] and the explicit ordering constraints it has relative to other labeled systems in its stage
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct SystemOrdering {
+    pub labels: Vec<Cow<'static, str>>,
+    pub before: Vec<Cow<'static, str>>,
+    pub after: Vec<Cow<'static, str>>,
+}
+
+impl SystemOrdering {
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.before.is_empty() && self.after.is_empty()
+    }
+
+    /// Returns true if a system with this ordering must run before a system with the `other` ordering
+    pub fn runs_before(&self, other: &SystemOrdering) -> bool {
+        self.before.iter().any(|label| other.labels.contains(label))
+            || other.after.iter().any(|label| self.labels.contains(label))
+    }
 }
 
 /// Provides information about the archetypes a [System] reads and writes
-#[derive(Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ArchetypeAccess {
     pub immutable: FixedBitSet,
     pub mutable: FixedBitSet,
@@ -76,16 +201,88 @@ impl ArchetypeAccess {
         self.immutable.clear();
         self.mutable.clear();
     }
+
+    /// Returns the archetypes that are written by one of `self` and `other` and read or written by the other
+    pub fn conflicts(&self, other: &ArchetypeAccess) -> Vec<usize> {
+        let mut conflicts = self
+            .mutable
+            .intersection(&other.mutable)
+            .chain(self.mutable.intersection(&other.immutable))
+            .chain(self.immutable.intersection(&other.mutable))
+            .collect::<Vec<_>>();
+        conflicts.sort_unstable();
+        conflicts.dedup();
+        conflicts
+    }
 }
 
 /// Provides information about the types a [System] reads and writes
-#[derive(Debug, Default, Eq, PartialEq, Clone)]
+#[derive(Debug, Default, Clone)]
 pub struct TypeAccess {
     pub immutable: HashSet<TypeId>,
     pub mutable: HashSet<TypeId>,
+    type_names: HashMap<TypeId, &'static str>,
+}
+
+impl PartialEq for TypeAccess {
+    fn eq(&self, other: &TypeAccess) -> bool {
+        self.immutable == other.immutable && self.mutable == other.mutable
+    }
 }
 
+impl Eq for TypeAccess {}
+
 impl TypeAccess {
+    /// Records that `T` is read
+    pub fn add_read<T: 'static>(&mut self) {
+        self.immutable.insert(TypeId::of::<T>());
+        self.type_names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
+    }
+
+    /// Records that `T` is written
+    pub fn add_write<T: 'static>(&mut self) {
+        self.mutable.insert(TypeId::of::<T>());
+        self.type_names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
+    }
+
+    /// Records the component types that `Q` reads and writes
+    pub fn add_query<Q: Query>(&mut self) {
+        Q::Fetch::component_access(&mut |ty, name, access| {
+            match access {
+                Access::Read => self.immutable.insert(ty),
+                Access::Write => self.mutable.insert(ty),
+                Access::Iterate => return,
+            };
+            self.type_names.insert(ty, name);
+        });
+    }
+
+    /// Returns the name of the type with the given id, if it was recorded with `add_read`, `add_write` or `add_query`
+    pub fn type_name(&self, type_id: &TypeId) -> Option<&'static str> {
+        self.type_names.get(type_id).copied()
+    }
+
+    /// Returns the types that are written by one of `self` and `other` and read or written by the other
+    pub fn conflicts(&self, other: &TypeAccess) -> Vec<TypeId> {
+        let mut conflicts = self
+            .mutable
+            .iter()
+            .filter(|ty| other.mutable.contains(ty) || other.immutable.contains(ty))
+            .chain(
+                self.immutable
+                    .iter()
+                    .filter(|ty| other.mutable.contains(ty)),
+            )
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        conflicts.sort_by_key(|ty| self.type_name(ty).or_else(|| other.type_name(ty)));
+        conflicts
+    }
+
     pub fn is_compatible(&self, other: &TypeAccess) -> bool {
         self.mutable.is_disjoint(&other.mutable)
             && self.mutable.is_disjoint(&other.immutable)
@@ -95,11 +292,13 @@ impl TypeAccess {
     pub fn union(&mut self, other: &TypeAccess) {
         self.mutable.extend(&other.mutable);
         self.immutable.extend(&other.immutable);
+        self.type_names.extend(&other.type_names);
     }
 
     pub fn clear(&mut self) {
         self.immutable.clear();
         self.mutable.clear();
+        self.type_names.clear();
     }
 }
 
@@ -0,0 +1,45 @@
+use bevy_hecs::Entity;
+use bevy_utils::HashMap;
+
+#[derive(Debug)]
+pub enum MapEntitiesError {
+    EntityNotFound(Entity),
+}
+
+/// Maps entities from one id space to another, for example from the ids stored in a scene to the
+/// entities spawned for them in a [World](bevy_hecs::World)
+#[derive(Default, Debug)]
+pub struct EntityMap {
+    map: HashMap<Entity, Entity>,
+}
+
+impl EntityMap {
+    pub fn insert(&mut self, from: Entity, to: Entity) {
+        self.map.insert(from, to);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        self.map.remove(&entity);
+    }
+
+    pub fn get(&self, entity: Entity) -> Result<Entity, MapEntitiesError> {
+        self.map
+            .get(&entity)
+            .cloned()
+            .ok_or(MapEntitiesError::EntityNotFound(entity))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.map.keys().cloned()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.map.values().cloned()
+    }
+}
+
+/// Components that store [Entity] values implement this so the stored entities can be remapped when
+/// the component is copied into another id space, such as when spawning a scene
+pub trait MapEntities {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError>;
+}
@@ -1,3 +1,5 @@
+mod entity_map;
 mod world_builder;
 
+pub use entity_map::*;
 pub use world_builder::*;
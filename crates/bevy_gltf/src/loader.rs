@@ -4,9 +4,9 @@ use bevy_render::{
 };
 
 use anyhow::Result;
-use bevy_asset::AssetLoader;
+use bevy_asset::{AssetIo, AssetIoError, AssetLoadError, AssetLoader, FileAssetIo};
 use gltf::{buffer::Source, mesh::Mode};
-use std::{fs, io, path::Path};
+use std::{io, path::Path};
 use thiserror::Error;
 
 /// Loads meshes from GLTF files into Mesh assets
@@ -17,7 +17,18 @@ pub struct GltfLoader;
 
 impl AssetLoader<Mesh> for GltfLoader {
     fn from_bytes(&self, asset_path: &Path, bytes: Vec<u8>) -> Result<Mesh> {
-        let mesh = load_gltf(asset_path, bytes)?;
+        let mesh = load_gltf(&FileAssetIo::default(), asset_path, bytes)?;
+        Ok(mesh)
+    }
+
+    /// Reads external buffers from the same [AssetIo] as the GLTF file
+    fn load_from_asset_io(
+        &self,
+        asset_io: &dyn AssetIo,
+        asset_path: &Path,
+    ) -> Result<Mesh, AssetLoadError> {
+        let bytes = asset_io.load_path(asset_path)?;
+        let mesh = load_gltf(asset_io, asset_path, bytes).map_err(anyhow::Error::from)?;
         Ok(mesh)
     }
 
@@ -36,6 +47,8 @@ pub enum GltfError {
     Gltf(#[from] gltf::Error),
     #[error("Failed to load file.")]
     Io(#[from] io::Error),
+    #[error("Failed to read buffer.")]
+    AssetIo(#[from] AssetIoError),
     #[error("Binary blob is missing.")]
     MissingBlob,
     #[error("Failed to decode base64 mesh data.")]
@@ -56,9 +69,14 @@ fn get_primitive_topology(mode: Mode) -> Result<PrimitiveTopology, GltfError> {
 }
 
 // TODO: this should return a scene
-pub fn load_gltf(asset_path: &Path, bytes: Vec<u8>) -> Result<Mesh, GltfError> {
+/// Loads the first mesh of a GLTF file. External buffers are read from `asset_io`, relative to `asset_path`.
+pub fn load_gltf(
+    asset_io: &dyn AssetIo,
+    asset_path: &Path,
+    bytes: Vec<u8>,
+) -> Result<Mesh, GltfError> {
     let gltf = gltf::Gltf::from_slice(&bytes)?;
-    let buffer_data = load_buffers(&gltf, asset_path)?;
+    let buffer_data = load_buffers(&gltf, asset_io, asset_path)?;
     for scene in gltf.scenes() {
         if let Some(node) = scene.nodes().next() {
             return Ok(load_node(&buffer_data, &node, 1)?);
@@ -112,7 +130,11 @@ fn load_node(buffer_data: &[Vec<u8>], node: &gltf::Node, depth: i32) -> Result<M
     panic!("failed to find mesh")
 }
 
-fn load_buffers(gltf: &gltf::Gltf, asset_path: &Path) -> Result<Vec<Vec<u8>>, GltfError> {
+fn load_buffers(
+    gltf: &gltf::Gltf,
+    asset_io: &dyn AssetIo,
+    asset_path: &Path,
+) -> Result<Vec<Vec<u8>>, GltfError> {
     const OCTET_STREAM_URI: &str = "data:application/octet-stream;base64,";
 
     let mut buffer_data = Vec::new();
@@ -127,7 +149,7 @@ fn load_buffers(gltf: &gltf::Gltf, asset_path: &Path) -> Result<Vec<Vec<u8>>, Gl
                     }
                 } else {
                     let buffer_path = asset_path.parent().unwrap().join(uri);
-                    let buffer_bytes = fs::read(buffer_path)?;
+                    let buffer_bytes = asset_io.load_path(&buffer_path)?;
                     buffer_data.push(buffer_bytes);
                 }
             }
@@ -143,3 +165,51 @@ fn load_buffers(gltf: &gltf::Gltf, asset_path: &Path) -> Result<Vec<Vec<u8>>, Gl
 
     Ok(buffer_data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::GltfLoader;
+    use bevy_asset::{AssetLoader, MemoryAssetIo};
+    use bevy_render::{mesh::VertexAttributeValues, pipeline::PrimitiveTopology};
+    use std::path::Path;
+
+    const TRIANGLE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+        "buffers": [{ "uri": "triangle.bin", "byteLength": 36 }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "accessors": [{
+            "bufferView": 0,
+            "componentType": 5126,
+            "count": 3,
+            "type": "VEC3",
+            "min": [0.0, 0.0, 0.0],
+            "max": [1.0, 1.0, 0.0]
+        }]
+    }"#;
+
+    #[test]
+    fn load_external_buffer_from_asset_io() {
+        let positions = [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        let buffer = positions
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect::<Vec<u8>>();
+        let asset_io = MemoryAssetIo::default();
+        asset_io.insert("models/triangle.gltf", TRIANGLE);
+        asset_io.insert("models/triangle.bin", buffer);
+
+        let mesh = GltfLoader
+            .load_from_asset_io(&asset_io, Path::new("models/triangle.gltf"))
+            .unwrap();
+        assert_eq!(mesh.primitive_topology, PrimitiveTopology::TriangleList);
+        match &mesh.attributes[0].values {
+            VertexAttributeValues::Float3(values) => assert_eq!(values, &positions),
+            _ => panic!("positions should be Float3"),
+        }
+    }
+}
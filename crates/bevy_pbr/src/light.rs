@@ -1,5 +1,5 @@
-use bevy_core::Byteable;
-use bevy_math::Mat4;
+use bevy_core::LayoutBytes;
+use bevy_math::{Mat4, Vec4};
 use bevy_property::Properties;
 use bevy_render::{
     camera::{CameraProjection, PerspectiveProjection},
@@ -26,16 +26,14 @@ impl Default for Light {
     }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy)]
+/// A [Light] as it is laid out in the `Lights` uniform block of the forward pipeline
+#[derive(Clone, Copy, LayoutBytes)]
 pub(crate) struct LightRaw {
-    pub proj: [[f32; 4]; 4],
-    pub pos: [f32; 4],
-    pub color: [f32; 4],
+    pub proj: Mat4,
+    pub pos: Vec4,
+    pub color: Color,
 }
 
-unsafe impl Byteable for LightRaw {}
-
 impl LightRaw {
     pub fn from(light: &Light, transform: &Mat4, translation: &Translation) -> LightRaw {
         let perspective = PerspectiveProjection {
@@ -46,11 +44,10 @@ impl LightRaw {
         };
 
         let proj = perspective.get_projection_matrix() * *transform;
-        let (x, y, z) = translation.0.into();
         LightRaw {
-            proj: proj.to_cols_array_2d(),
-            pos: [x, y, z, 1.0],
-            color: light.color.into(),
+            proj,
+            pos: translation.0.extend(1.0),
+            color: light.color,
         }
     }
 }
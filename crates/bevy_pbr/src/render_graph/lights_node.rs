@@ -2,7 +2,7 @@ use crate::{
     light::{Light, LightRaw},
     render_graph::uniform,
 };
-use bevy_core::{AsBytes, Byteable};
+use bevy_core::{AsBytes, BufferLayout, Byteable, LayoutBytes};
 use bevy_ecs::{Commands, IntoQuerySystem, Local, Query, Res, ResMut, Resources, System, World};
 use bevy_render::{
     render_graph::{CommandQueue, Node, ResourceSlots, SystemNode},
@@ -86,7 +86,7 @@ pub fn lights_node_system(
     let render_resource_context = &**render_resource_context;
 
     let light_count = query.iter().iter().count();
-    let size = std::mem::size_of::<LightRaw>();
+    let size = LightRaw::layout_size(BufferLayout::Std140);
     let light_count_size = std::mem::size_of::<LightCount>();
     let light_array_size = size * light_count;
     let light_array_max_size = size * state.max_lights;
@@ -137,9 +137,8 @@ pub fn lights_node_system(
                 .iter()
                 .zip(data[light_count_size..current_light_uniform_size].chunks_exact_mut(size))
             {
-                slot.copy_from_slice(
-                    LightRaw::from(&light, &transform.value, &translation).as_bytes(),
-                );
+                LightRaw::from(light, &transform.value, translation)
+                    .write_layout_bytes(BufferLayout::Std140, slot);
             }
         },
     );
@@ -1,18 +1,42 @@
-use crate::{impl_property, property_serde::Serializable, Property, PropertyTypeRegistry};
+use crate::{
+    impl_property,
+    property_serde::{Serializable, TYPE_FIELD, VALUE_FIELD},
+    Property, PropertyTypeRegistry,
+};
 use bevy_ecs::Entity;
 use erased_serde::Deserializer;
-use serde::Deserialize;
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 
 impl_property!(Entity, serialize_entity, deserialize_entity);
 
 mod private {
     use serde::{Deserialize, Serialize};
+
+    /// The id and generation of an entity
     #[derive(Serialize, Deserialize)]
-    pub(super) struct Entity(pub(super) u128);
+    pub(super) struct Entity(pub(super) u32, pub(super) u32);
+}
+
+/// Serializes an entity with its type name, like other non-primitive property values
+struct EntitySerializer(Entity);
+
+impl Serialize for EntitySerializer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_map(Some(2))?;
+        state.serialize_entry(TYPE_FIELD, self.0.type_name())?;
+        state.serialize_entry(
+            VALUE_FIELD,
+            &private::Entity(self.0.id(), self.0.generation()),
+        )?;
+        state.end()
+    }
 }
 
 fn serialize_entity(entity: &Entity) -> Serializable {
-    Serializable::Owned(Box::new(private::Entity(entity.id())))
+    Serializable::Owned(Box::new(EntitySerializer(*entity)))
 }
 
 fn deserialize_entity(
@@ -20,5 +44,5 @@ fn deserialize_entity(
     _registry: &PropertyTypeRegistry,
 ) -> Result<Box<dyn Property>, erased_serde::Error> {
     let entity = private::Entity::deserialize(deserializer)?;
-    Ok(Box::new(Entity::from_id(entity.0)))
+    Ok(Box::new(Entity::with_generation(entity.0, entity.1)))
 }
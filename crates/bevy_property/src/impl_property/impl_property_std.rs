@@ -9,6 +9,7 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     hash::{BuildHasher, Hash},
     ops::Range,
+    time::Duration,
 };
 
 impl<T> Properties for Vec<T>
@@ -114,6 +115,7 @@ impl_property!(BTreeMap<K, V> where
     K: Clone + Ord + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static,
     V: Clone + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static);
 impl_property!(Range<T> where T: Clone + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static);
+impl_property!(Duration);
 
 // TODO: Implement lossless primitive types in RON and remove all of these primitive "cast checks"
 impl Property for String {
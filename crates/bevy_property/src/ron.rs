@@ -1,9 +1,20 @@
 use crate::{
-    property_serde::DynamicPropertiesDeserializer, DynamicProperties, PropertyTypeRegistry,
+    property_serde::{DynamicPropertiesDeserializer, DynamicPropertiesSerializer},
+    DynamicProperties, PropertyTypeRegistry,
 };
 use bevy_ron::de::Deserializer;
 use serde::de::DeserializeSeed;
 
+pub fn serialize_dynamic_properties(
+    dynamic_properties: &DynamicProperties,
+    property_type_registry: &PropertyTypeRegistry,
+) -> Result<String, bevy_ron::Error> {
+    bevy_ron::ser::to_string(&DynamicPropertiesSerializer::new(
+        dynamic_properties,
+        property_type_registry,
+    ))
+}
+
 pub fn deserialize_dynamic_properties(
     ron_string: &str,
     property_type_registry: &PropertyTypeRegistry,
@@ -32,6 +32,29 @@ impl PropertyTypeRegistry {
             .insert(registration.name.to_string(), registration);
     }
 
+    /// Removes the registration of the type with the given full name. Returns false if it was not registered.
+    pub fn unregister(&mut self, type_name: &str) -> bool {
+        match self.registrations.remove(type_name) {
+            Some(registration) => {
+                if self
+                    .short_names
+                    .get(&registration.short_name)
+                    .map(|name| name.as_str())
+                    == Some(type_name)
+                {
+                    self.short_names.remove(&registration.short_name);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the full names of all registered types
+    pub fn type_names(&self) -> impl Iterator<Item = &str> {
+        self.registrations.keys().map(|name| name.as_str())
+    }
+
     pub fn get(&self, type_name: &str) -> Option<&PropertyTypeRegistration> {
         if let Some(long_name) = self.short_names.get(type_name) {
             self.registrations.get(long_name)
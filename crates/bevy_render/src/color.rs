@@ -4,7 +4,7 @@ use crate::{
     renderer::{RenderResource, RenderResourceType},
 };
 use bevy_asset::Handle;
-use bevy_core::{Byteable, Bytes};
+use bevy_core::{BufferLayout, Byteable, Bytes, LayoutBytes};
 use bevy_math::{Vec3, Vec4};
 use bevy_property::Property;
 use serde::{Deserialize, Serialize};
@@ -22,6 +22,21 @@ pub struct Color {
 
 unsafe impl Byteable for Color {}
 
+// Colors are vec4s in shaders
+impl LayoutBytes for Color {
+    fn layout_alignment(_layout: BufferLayout) -> usize {
+        16
+    }
+
+    fn layout_size(_layout: BufferLayout) -> usize {
+        16
+    }
+
+    fn write_layout_bytes(&self, _layout: BufferLayout, buffer: &mut [u8]) {
+        self.write_bytes(buffer);
+    }
+}
+
 impl Color {
     pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
     pub const BLUE: Color = Color::rgb(0.0, 0.0, 1.0);
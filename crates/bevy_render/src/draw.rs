@@ -11,11 +11,11 @@ use crate::{
 };
 use bevy_asset::{Assets, Handle};
 use bevy_ecs::{
-    FetchResource, Query, Res, ResMut, ResourceIndex, ResourceQuery, Resources, SystemId,
-    TypeAccess, UnsafeClone,
+    ChangeTicks, FetchResource, Query, Res, ResMut, ResourceIndex, ResourceQuery, Resources,
+    SystemId, TypeAccess, UnsafeClone,
 };
 use bevy_property::Properties;
-use std::{any::TypeId, ops::Range, sync::Arc};
+use std::{ops::Range, sync::Arc};
 use thiserror::Error;
 
 /// A queued command for the renderer
@@ -175,23 +175,51 @@ impl<'a> FetchResource<'a> for FetchDrawContext {
         resources.release::<SharedBuffers>();
     }
 
-    unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
+    unsafe fn get(
+        resources: &'a Resources,
+        _system_id: Option<SystemId>,
+        ticks: ChangeTicks,
+    ) -> Self::Item {
+        let pipelines = resources
+            .get_unsafe_ref_with_added_and_mutated::<Assets<PipelineDescriptor>>(
+                ResourceIndex::Global,
+            );
+        let shaders = resources
+            .get_unsafe_ref_with_added_and_mutated::<Assets<Shader>>(ResourceIndex::Global);
+        let pipeline_compiler = resources
+            .get_unsafe_ref_with_added_and_mutated::<PipelineCompiler>(ResourceIndex::Global);
+        let render_resource_context = resources
+            .get_unsafe_ref_with_added_and_mutated::<Box<dyn RenderResourceContext>>(
+                ResourceIndex::Global,
+            );
+        let vertex_buffer_descriptors = resources
+            .get_unsafe_ref_with_added_and_mutated::<VertexBufferDescriptors>(
+                ResourceIndex::Global,
+            );
+        let shared_buffers =
+            resources.get_unsafe_ref_with_added_and_mutated::<SharedBuffers>(ResourceIndex::Global);
         DrawContext {
-            pipelines: ResMut::new(
-                resources.get_unsafe_ref::<Assets<PipelineDescriptor>>(ResourceIndex::Global),
-            ),
-            shaders: ResMut::new(resources.get_unsafe_ref::<Assets<Shader>>(ResourceIndex::Global)),
+            pipelines: ResMut::new(pipelines.0, pipelines.2, ticks.change_tick),
+            shaders: ResMut::new(shaders.0, shaders.2, ticks.change_tick),
             pipeline_compiler: ResMut::new(
-                resources.get_unsafe_ref::<PipelineCompiler>(ResourceIndex::Global),
+                pipeline_compiler.0,
+                pipeline_compiler.2,
+                ticks.change_tick,
             ),
             render_resource_context: Res::new(
-                resources.get_unsafe_ref::<Box<dyn RenderResourceContext>>(ResourceIndex::Global),
+                render_resource_context.0,
+                ticks.is_newer(*render_resource_context.1.as_ptr()),
+                ticks.is_newer(*render_resource_context.2.as_ptr()),
             ),
             vertex_buffer_descriptors: Res::new(
-                resources.get_unsafe_ref::<VertexBufferDescriptors>(ResourceIndex::Global),
+                vertex_buffer_descriptors.0,
+                ticks.is_newer(*vertex_buffer_descriptors.1.as_ptr()),
+                ticks.is_newer(*vertex_buffer_descriptors.2.as_ptr()),
             ),
             shared_buffers: Res::new(
-                resources.get_unsafe_ref::<SharedBuffers>(ResourceIndex::Global),
+                shared_buffers.0,
+                ticks.is_newer(*shared_buffers.1.as_ptr()),
+                ticks.is_newer(*shared_buffers.2.as_ptr()),
             ),
             current_pipeline: None,
         }
@@ -199,18 +227,12 @@ impl<'a> FetchResource<'a> for FetchDrawContext {
 
     fn access() -> TypeAccess {
         let mut access = TypeAccess::default();
-        access
-            .mutable
-            .insert(TypeId::of::<Assets<PipelineDescriptor>>());
-        access.mutable.insert(TypeId::of::<Assets<Shader>>());
-        access.mutable.insert(TypeId::of::<PipelineCompiler>());
-        access
-            .immutable
-            .insert(TypeId::of::<Box<dyn RenderResourceContext>>());
-        access
-            .immutable
-            .insert(TypeId::of::<VertexBufferDescriptors>());
-        access.immutable.insert(TypeId::of::<SharedBuffers>());
+        access.add_write::<Assets<PipelineDescriptor>>();
+        access.add_write::<Assets<Shader>>();
+        access.add_write::<PipelineCompiler>();
+        access.add_read::<Box<dyn RenderResourceContext>>();
+        access.add_read::<VertexBufferDescriptors>();
+        access.add_read::<SharedBuffers>();
         access
     }
 }
@@ -1,7 +1,13 @@
-use super::{Edge, Node, NodeId, NodeLabel, NodeState, RenderGraphError, SlotLabel, SystemNode};
+use super::{
+    Edge, Node, NodeId, NodeLabel, NodeState, RenderGraphError, ResourceSlots, SlotLabel,
+    SystemNode,
+};
 use bevy_ecs::{Commands, Schedule};
 use bevy_utils::HashMap;
-use std::{borrow::Cow, fmt::Debug};
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Write},
+};
 pub struct RenderGraph {
     nodes: HashMap<NodeId, NodeState>,
     node_names: HashMap<Cow<'static, str>, NodeId>,
@@ -278,6 +284,88 @@ impl RenderGraph {
     pub fn take_commands(&mut self) -> Commands {
         std::mem::take(&mut self.commands)
     }
+
+    /// Returns a [Graphviz](https://graphviz.org) DOT graph of the nodes in this graph, their input and output slots,
+    /// and the edges between them. Slot edges connect the slots they pass resources between, node edges are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut nodes = self.iter_nodes().collect::<Vec<_>>();
+        nodes.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+        let node_indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.id, index))
+            .collect::<HashMap<_, _>>();
+
+        let mut dot = String::new();
+        writeln!(dot, "digraph render_graph {{").unwrap();
+        writeln!(dot, "    rankdir=LR;").unwrap();
+        writeln!(dot, "    node [shape=record];").unwrap();
+        for (index, node) in nodes.iter().enumerate() {
+            let slots = |slots: &ResourceSlots, port: &str| {
+                slots
+                    .iter()
+                    .enumerate()
+                    .map(|(i, slot)| format!("<{}{}> {}", port, i, escape_record(&slot.info.name)))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            };
+            let name = match node.name {
+                Some(ref name) => escape_record(name),
+                None => format!("{:?}", node.id),
+            };
+            writeln!(
+                dot,
+                "    node_{} [label=\"{{{{{}}}|{}|{{{}}}}}\"];",
+                index,
+                slots(&node.input_slots, "i"),
+                name,
+                slots(&node.output_slots, "o")
+            )
+            .unwrap();
+        }
+        for node in nodes.iter() {
+            for edge in node.edges.output_edges.iter() {
+                match *edge {
+                    Edge::SlotEdge {
+                        input_node,
+                        input_index,
+                        output_node,
+                        output_index,
+                    } => writeln!(
+                        dot,
+                        "    node_{}:o{} -> node_{}:i{};",
+                        node_indices[&output_node],
+                        output_index,
+                        node_indices[&input_node],
+                        input_index
+                    ),
+                    Edge::NodeEdge {
+                        input_node,
+                        output_node,
+                    } => writeln!(
+                        dot,
+                        "    node_{} -> node_{} [style=dashed];",
+                        node_indices[&output_node], node_indices[&input_node]
+                    ),
+                }
+                .unwrap();
+            }
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+/// Escapes the characters that have a special meaning in the label of a DOT record node
+fn escape_record(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '"' | '{' | '}' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 impl Debug for RenderGraph {
@@ -404,6 +492,24 @@ mod tests {
         assert!(output_nodes("D", &graph).is_empty(), "D has no outputs");
     }
 
+    #[test]
+    pub fn test_to_dot() {
+        let mut graph = RenderGraph::default();
+        graph.add_node("A", TestNode::new(0, 1));
+        graph.add_node("B", TestNode::new(0, 1));
+        graph.add_node("C|D", TestNode::new(2, 0));
+
+        graph.add_slot_edge("A", "out_0", "C|D", "in_1").unwrap();
+        graph.add_node_edge("B", "C|D").unwrap();
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph render_graph {"));
+        assert!(dot.contains("node_0 [label=\"{{}|A|{<o0> out_0}}\"];"));
+        assert!(dot.contains("node_2 [label=\"{{<i0> in_0|<i1> in_1}|C\\|D|{}}\"];"));
+        assert!(dot.contains("node_0:o0 -> node_2:i1;"));
+        assert!(dot.contains("node_1 -> node_2 [style=dashed];"));
+    }
+
     #[test]
     pub fn test_get_node_typed() {
         struct MyNode {
@@ -11,6 +11,9 @@ pub struct Scene {
 }
 
 pub struct Entity {
+    /// Stable id of the entity within the scene. Spawning the scene maps it to a new [Entity](bevy_ecs::Entity)
+    /// in the world. [Entity](bevy_ecs::Entity) values stored in components refer to other scene entities by this id:
+    /// its low 32 bits are the id of the [Entity](bevy_ecs::Entity) and the next 32 bits are its generation.
     pub entity: u128,
     pub components: Vec<DynamicProperties>,
 }
@@ -23,7 +26,7 @@ impl Scene {
             for (index, entity) in archetype.iter_entities().enumerate() {
                 if index == entities.len() {
                     entities.push(Entity {
-                        entity: *entity,
+                        entity: u128::from(entity.generation()) << 32 | u128::from(entity.id()),
                         components: Vec::new(),
                     })
                 }
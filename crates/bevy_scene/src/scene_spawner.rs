@@ -1,14 +1,32 @@
 use crate::Scene;
 use bevy_app::prelude::*;
 use bevy_asset::{AssetEvent, Assets, Handle};
-use bevy_ecs::{Resources, World};
+use bevy_ecs::{Entity, EntityMap, MapEntitiesError, Resources, World};
 use bevy_type_registry::TypeRegistry;
-use bevy_utils::{HashMap, HashSet};
+use bevy_utils::HashMap;
+use std::convert::TryFrom;
 use thiserror::Error;
 use uuid::Uuid;
 
+/// Maps the stable entity ids of a scene to the entities spawned for them in the [World]
+#[derive(Default)]
 struct InstanceInfo {
-    entity_map: HashMap<u128, bevy_ecs::Entity>,
+    entity_map: HashMap<u128, Entity>,
+}
+
+impl InstanceInfo {
+    /// Entities referenced by scene components are stored as [Entity] values built from scene ids,
+    /// so only scene ids that fit in a `u64` can be referenced. See [crate::Entity::entity].
+    fn component_entity_map(&self) -> EntityMap {
+        let mut entity_map = EntityMap::default();
+        for (scene_id, entity) in self.entity_map.iter() {
+            if let Ok(bits) = u64::try_from(*scene_id) {
+                let scene_entity = Entity::with_generation(bits as u32, (bits >> 32) as u32);
+                entity_map.insert(scene_entity, *entity);
+            }
+        }
+        entity_map
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -22,7 +40,7 @@ impl InstanceId {
 
 #[derive(Default)]
 pub struct SceneSpawner {
-    loaded_scenes: HashSet<Handle<Scene>>,
+    loaded_scenes: HashMap<Handle<Scene>, InstanceInfo>,
     spawned_scenes: HashMap<Handle<Scene>, Vec<InstanceId>>,
     spawned_instances: HashMap<InstanceId, InstanceInfo>,
     scene_asset_event_reader: EventReader<AssetEvent<Scene>>,
@@ -36,6 +54,8 @@ pub enum SceneSpawnError {
     UnregisteredComponent { type_name: String },
     #[error("Scene does not exist. Perhaps it is still loading?")]
     NonExistentScene { handle: Handle<Scene> },
+    #[error("Scene contains a component that refers to an entity that is not part of the scene.")]
+    MissingEntityReference { entity: Entity },
 }
 
 impl SceneSpawner {
@@ -53,9 +73,8 @@ impl SceneSpawner {
         resources: &Resources,
         scene_handle: Handle<Scene>,
     ) -> Result<(), SceneSpawnError> {
-        Self::load_internal(world, resources, scene_handle, None)?;
-        self.loaded_scenes.insert(scene_handle);
-        Ok(())
+        let instance_info = self.loaded_scenes.entry(scene_handle).or_default();
+        Self::load_internal(world, resources, scene_handle, instance_info)
     }
 
     pub fn spawn_sync(
@@ -65,10 +84,8 @@ impl SceneSpawner {
         scene_handle: Handle<Scene>,
     ) -> Result<(), SceneSpawnError> {
         let instance_id = InstanceId::new();
-        let mut instance_info = InstanceInfo {
-            entity_map: HashMap::default(),
-        };
-        Self::load_internal(world, resources, scene_handle, Some(&mut instance_info))?;
+        let mut instance_info = InstanceInfo::default();
+        Self::load_internal(world, resources, scene_handle, &mut instance_info)?;
         self.spawned_instances.insert(instance_id, instance_info);
         let spawned = self
             .spawned_scenes
@@ -82,7 +99,7 @@ impl SceneSpawner {
         world: &mut World,
         resources: &Resources,
         scene_handle: Handle<Scene>,
-        mut instance_info: Option<&mut InstanceInfo>,
+        instance_info: &mut InstanceInfo,
     ) -> Result<(), SceneSpawnError> {
         let type_registry = resources.get::<TypeRegistry>().unwrap();
         let component_registry = type_registry.component.read();
@@ -94,15 +111,12 @@ impl SceneSpawner {
             })?;
 
         for scene_entity in scene.entities.iter() {
-            let entity = if let Some(ref mut instance_info) = instance_info {
-                *instance_info
-                    .entity_map
-                    .entry(scene_entity.entity)
-                    .or_insert_with(bevy_ecs::Entity::new)
-            } else {
-                bevy_ecs::Entity::from_id(scene_entity.entity)
-            };
-            if world.contains(entity) {
+            let existing_entity = instance_info
+                .entity_map
+                .get(&scene_entity.entity)
+                .cloned()
+                .filter(|entity| world.contains(*entity));
+            if let Some(entity) = existing_entity {
                 for component in scene_entity.components.iter() {
                     let component_registration = component_registry
                         .get_with_name(&component.type_name)
@@ -114,7 +128,8 @@ impl SceneSpawner {
                     }
                 }
             } else {
-                world.spawn_as_entity(entity, (1,));
+                let entity = world.spawn(());
+                instance_info.entity_map.insert(scene_entity.entity, entity);
                 for component in scene_entity.components.iter() {
                     let component_registration = component_registry
                         .get_with_name(&component.type_name)
@@ -126,6 +141,23 @@ impl SceneSpawner {
                 }
             }
         }
+
+        let entity_map = instance_info.component_entity_map();
+        for scene_entity in scene.entities.iter() {
+            let entity = instance_info.entity_map[&scene_entity.entity];
+            for component in scene_entity.components.iter() {
+                let component_registration = component_registry
+                    .get_with_name(&component.type_name)
+                    .unwrap();
+                component_registration
+                    .map_component_entities(world, entity, &entity_map)
+                    .map_err(|error| match error {
+                        MapEntitiesError::EntityNotFound(entity) => {
+                            SceneSpawnError::MissingEntityReference { entity }
+                        }
+                    })?;
+            }
+        }
         Ok(())
     }
 
@@ -139,7 +171,7 @@ impl SceneSpawner {
             if let Some(spawned_instances) = self.spawned_scenes.get(scene_handle) {
                 for instance_id in spawned_instances.iter() {
                     if let Some(instance_info) = self.spawned_instances.get_mut(instance_id) {
-                        Self::load_internal(world, resources, *scene_handle, Some(instance_info))?;
+                        Self::load_internal(world, resources, *scene_handle, instance_info)?;
                     }
                 }
             }
@@ -200,7 +232,7 @@ pub fn scene_spawner_system(world: &mut World, resources: &mut Resources) {
         .iter(&scene_asset_events)
     {
         if let AssetEvent::Modified { handle } = event {
-            if scene_spawner.loaded_scenes.contains(handle) {
+            if scene_spawner.loaded_scenes.contains_key(handle) {
                 scene_spawner.load(*handle);
             }
             if scene_spawner.spawned_scenes.contains_key(handle) {
@@ -215,3 +247,92 @@ pub fn scene_spawner_system(world: &mut World, resources: &mut Resources) {
         .update_spawned_scenes(world, resources, &updated_spawned_scenes)
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SceneSpawner;
+    use crate::{serde::SceneDeserializer, Scene};
+    use bevy_asset::Assets;
+    use bevy_ecs::{
+        Entity, EntityMap, FromResources, MapEntities, MapEntitiesError, Resources, World,
+    };
+    use bevy_property::Properties;
+    use bevy_type_registry::TypeRegistry;
+    use serde::de::DeserializeSeed;
+
+    #[derive(Properties, Default)]
+    struct Value(u32);
+
+    #[derive(Properties)]
+    struct Reference(Entity);
+
+    impl FromResources for Reference {
+        fn from_resources(_resources: &Resources) -> Self {
+            Reference(Entity::new(u32::MAX))
+        }
+    }
+
+    impl MapEntities for Reference {
+        fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+            self.0 = entity_map.get(self.0)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn scene_round_trip_remaps_entities() {
+        let type_registry = TypeRegistry::default();
+        {
+            let mut component_registry = type_registry.component.write();
+            component_registry.register::<Value>();
+            component_registry.register_map_entities::<Reference>();
+            let mut property_registry = type_registry.property.write();
+            property_registry.register::<Value>();
+            property_registry.register::<Reference>();
+            property_registry.register::<Entity>();
+        }
+
+        // the referenced entity reuses the id of a despawned entity, so its generation is not zero
+        let mut source = World::new();
+        let despawned = source.spawn((Value(0),));
+        source.despawn(despawned).unwrap();
+        let target = source.spawn((Value(1),));
+        assert_ne!(target.generation(), 0);
+        source.spawn((Value(2), Reference(target)));
+
+        let scene = Scene::from_world(&source, &type_registry.component.read());
+        let ron = scene.serialize_ron(&type_registry.property.read()).unwrap();
+        let mut deserializer = bevy_ron::de::Deserializer::from_str(&ron).unwrap();
+        let scene = SceneDeserializer {
+            property_type_registry: &type_registry.property.read(),
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut scenes = Assets::<Scene>::default();
+        let scene_handle = scenes.add(scene);
+        let mut resources = Resources::default();
+        resources.insert(scenes);
+        resources.insert(type_registry);
+
+        // spawn the scene into a world whose ids are already partly used
+        let mut world = World::new();
+        world.spawn((Value(3),));
+        world.spawn((Value(4),));
+        let mut scene_spawner = SceneSpawner::default();
+        scene_spawner
+            .spawn_sync(&mut world, &resources, scene_handle)
+            .unwrap();
+
+        let references = world
+            .query::<(&Value, &Reference)>()
+            .iter()
+            .map(|(value, reference)| (value.0, reference.0))
+            .collect::<Vec<_>>();
+        assert_eq!(references.len(), 1);
+        let (value, reference) = references[0];
+        assert_eq!(value, 2);
+        assert_ne!(reference, target);
+        assert_eq!(world.get::<Value>(reference).unwrap().0, 1);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::Rect;
 use bevy_asset::Handle;
-use bevy_core::Bytes;
+use bevy_core::LayoutBytes;
 use bevy_math::Vec2;
 use bevy_render::{
     color::Color,
@@ -23,10 +23,9 @@ pub struct TextureAtlas {
     pub texture_handles: Option<HashMap<Handle<Texture>, usize>>,
 }
 
-// NOTE: cannot do `unsafe impl Byteable` here because Vec3 takes up the space of a Vec4. If/when glam changes this we can swap out
-// Bytes for Byteable as a micro-optimization. https://github.com/bitshifter/glam-rs/issues/36
-#[derive(Bytes, RenderResources, RenderResource)]
+#[derive(LayoutBytes, RenderResources, RenderResource)]
 #[render_resources(from_self)]
+#[render_resource(std140)]
 pub struct TextureAtlasSprite {
     pub color: Color,
     pub index: u32,
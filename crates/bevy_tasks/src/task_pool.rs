@@ -7,6 +7,7 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    task::{Context, Poll, Wake, Waker},
     thread::{self, JoinHandle},
 };
 
@@ -212,7 +213,27 @@ impl TaskPool {
         let fut: Pin<&'static mut (dyn Future<Output = Vec<T>> + Send + 'static)> =
             unsafe { mem::transmute(fut) };
 
-        pollster::block_on(self.executor.spawn(fut))
+        let mut task = self.executor.spawn(fut);
+
+        // Run the tasks of the pool while waiting, so that a scope started from one of the pool's
+        // threads, like a parallel query in a system, can't wait for threads that are all waiting
+        // on scopes themselves
+        let (parker, unparker) = parking::pair();
+        let unparker = Arc::new(unparker);
+        let ticker = {
+            let unparker = unparker.clone();
+            self.executor.ticker(move || unparker.unpark())
+        };
+        let waker = Waker::from(Arc::new(UnparkWaker(unparker)));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(results) = Pin::new(&mut task).poll(&mut cx) {
+                return results;
+            }
+            if !ticker.tick() {
+                parker.park();
+            }
+        }
     }
 
     /// Spawns a static future onto the thread pool. The returned Task is a future. It can also be
@@ -229,6 +250,19 @@ impl TaskPool {
     }
 }
 
+/// Wakes a thread that is waiting for a scope
+struct UnparkWaker(Arc<Unparker>);
+
+impl Wake for UnparkWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
 impl Default for TaskPool {
     fn default() -> Self {
         Self::new()
@@ -282,4 +316,25 @@ mod tests {
             assert_eq!(output, 42);
         }
     }
+
+    #[test]
+    pub fn nested_scope_on_one_thread() {
+        let pool = TaskPoolBuilder::new().num_threads(1).build();
+        let outputs = pool.scope(|scope| {
+            for i in 0..4 {
+                let pool = &pool;
+                scope.spawn(async move {
+                    // the pool's only thread runs this, so it has to run the inner tasks itself
+                    pool.scope(|scope| {
+                        for j in 0..4 {
+                            scope.spawn(async move { i * 4 + j });
+                        }
+                    })
+                    .into_iter()
+                    .sum::<usize>()
+                });
+            }
+        });
+        assert_eq!(outputs.into_iter().sum::<usize>(), (0..16).sum());
+    }
 }
@@ -1,4 +1,4 @@
-use bevy_ecs::{Entity, FromResources};
+use bevy_ecs::{Entity, EntityMap, FromResources, MapEntities, MapEntitiesError};
 use bevy_property::Properties;
 use std::ops::{Deref, DerefMut};
 
@@ -11,7 +11,14 @@ pub struct Parent(pub Entity);
 // ways to handle cases like this.
 impl FromResources for Parent {
     fn from_resources(_resources: &bevy_ecs::Resources) -> Self {
-        Parent(Entity::from_id(u128::MAX))
+        Parent(Entity::new(u32::MAX))
+    }
+}
+
+impl MapEntities for Parent {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+        self.0 = entity_map.get(self.0)?;
+        Ok(())
     }
 }
 
@@ -82,7 +82,10 @@ impl WorldWriter for PushChildren {
 
 impl<'a> ChildBuilder<'a> {
     pub fn spawn(&mut self, components: impl DynamicBundle + Send + Sync + 'static) -> &mut Self {
-        self.spawn_as_entity(Entity::new(), components)
+        self.commands.spawn(components);
+        let entity = self.commands.current_entity.unwrap();
+        self.push_children.children.push(entity);
+        self
     }
 
     pub fn spawn_as_entity(
@@ -223,7 +226,7 @@ mod tests {
     fn build_children() {
         let mut world = World::default();
         let mut resources = Resources::default();
-        let mut commands = Commands::default();
+        let mut commands = Commands::new(&world);
 
         let mut parent = None;
         let mut child1 = None;
@@ -270,7 +273,7 @@ mod tests {
     fn push_and_insert_children() {
         let mut world = World::default();
         let mut resources = Resources::default();
-        let mut commands = Commands::default();
+        let mut commands = Commands::new(&world);
         let entities = world
             .spawn_batch(vec![(1,), (2,), (3,), (4,), (5,)])
             .collect::<Vec<Entity>>();
@@ -0,0 +1,181 @@
+use crate::components::Parent;
+use bevy_core::Name;
+use bevy_ecs::{Changed, Entity, Query, ResMut};
+use bevy_utils::HashMap;
+
+/// Finds named entities by their path in the hierarchy, like `"Player/Arm/Hand"`. Each segment of a path is the [Name]
+/// of an entity, and each entity after the first is a child of the one before it. The first entity has no [Parent].
+///
+/// The index is updated incrementally by [entity_paths_system] as names and parents change. If several siblings have the
+/// same name, paths resolve to the one that has had it the longest.
+#[derive(Default, Debug)]
+pub struct EntityPaths {
+    children: HashMap<Option<Entity>, HashMap<String, Vec<Entity>>>,
+    entities: HashMap<Entity, (Option<Entity>, String)>,
+}
+
+impl EntityPaths {
+    /// Returns the entity at `path`, which starts at an entity without a parent
+    pub fn get(&self, path: &str) -> Option<Entity> {
+        self.resolve(None, path)
+    }
+
+    /// Returns the entity at `path`, relative to `root`
+    pub fn get_relative(&self, root: Entity, path: &str) -> Option<Entity> {
+        self.resolve(Some(root), path)
+    }
+
+    /// Returns the child of `parent` called `name`, or the entity without a parent called `name` if `parent` is None
+    pub fn get_child(&self, parent: Option<Entity>, name: &str) -> Option<Entity> {
+        self.children
+            .get(&parent)
+            .and_then(|children| children.get(name))
+            .and_then(|entities| entities.first().copied())
+    }
+
+    /// Returns the path of `entity`, if it and all of its ancestors are named
+    pub fn path(&self, entity: Entity) -> Option<String> {
+        let mut names = Vec::new();
+        let mut current = Some(entity);
+        while let Some(entity) = current {
+            let (parent, name) = self.entities.get(&entity)?;
+            names.push(name.as_str());
+            current = *parent;
+        }
+        names.reverse();
+        Some(names.join("/"))
+    }
+
+    fn resolve(&self, root: Option<Entity>, path: &str) -> Option<Entity> {
+        let mut current = root;
+        for name in path.split('/') {
+            current = Some(self.get_child(current, name)?);
+        }
+        current
+    }
+
+    fn insert(&mut self, entity: Entity, parent: Option<Entity>, name: &str) {
+        if let Some((current_parent, current_name)) = self.entities.get(&entity) {
+            if *current_parent == parent && current_name == name {
+                return;
+            }
+            self.remove(entity);
+        }
+        self.children
+            .entry(parent)
+            .or_default()
+            .entry(name.to_string())
+            .or_default()
+            .push(entity);
+        self.entities.insert(entity, (parent, name.to_string()));
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        let (parent, name) = match self.entities.remove(&entity) {
+            Some(key) => key,
+            None => return,
+        };
+        let children = self.children.get_mut(&parent).unwrap();
+        let entities = children.get_mut(&name).unwrap();
+        entities.retain(|e| *e != entity);
+        if entities.is_empty() {
+            children.remove(&name);
+            if children.is_empty() {
+                self.children.remove(&parent);
+            }
+        }
+    }
+}
+
+/// Updates [EntityPaths] with the entities whose [Name] or [Parent] was added, changed or removed since it last ran
+pub fn entity_paths_system(
+    mut entity_paths: ResMut<EntityPaths>,
+    names_query: Query<(Entity, &Name, Option<&Parent>)>,
+    mut changed_names_query: Query<(Entity, Changed<Name>, Option<&Parent>)>,
+    mut changed_parents_query: Query<(Entity, &Name, Changed<Parent>)>,
+) {
+    for entity in names_query.removed::<Name>().iter() {
+        entity_paths.remove(*entity);
+    }
+    // entities that lost their parent but kept their name become roots
+    for entity in names_query.removed::<Parent>().iter() {
+        if let Ok(name) = names_query.get::<Name>(*entity) {
+            if names_query.get::<Parent>(*entity).is_err() {
+                entity_paths.insert(*entity, None, &name);
+            }
+        }
+    }
+
+    for (entity, name, parent) in &mut changed_names_query.iter() {
+        entity_paths.insert(entity, parent.map(|parent| parent.0), &name);
+    }
+    for (entity, name, parent) in &mut changed_parents_query.iter() {
+        entity_paths.insert(entity, Some(parent.0), name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entity_paths_system, EntityPaths};
+    use crate::{components::Parent, hierarchy::BuildChildren};
+    use bevy_core::Name;
+    use bevy_ecs::{Commands, Entity, IntoQuerySystem, Resources, Schedule, World};
+
+    #[test]
+    fn entity_paths() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(EntityPaths::default());
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", entity_paths_system.system());
+
+        let mut commands = Commands::new(&world);
+        let mut entities = Vec::<Entity>::new();
+        commands
+            .spawn((Name::new("Player"),))
+            .for_current_entity(|entity| entities.push(entity))
+            .with_children(|parent| {
+                parent
+                    .spawn((Name::new("Arm"),))
+                    .for_current_entity(|entity| entities.push(entity))
+                    .with_children(|parent| {
+                        parent
+                            .spawn((Name::new("Hand"),))
+                            .for_current_entity(|entity| entities.push(entity));
+                    });
+            });
+        commands.apply(&mut world, &mut resources);
+        let (player, arm, hand) = (entities[0], entities[1], entities[2]);
+
+        schedule.initialize(&mut resources);
+        schedule.run(&mut world, &mut resources);
+        world.clear_trackers();
+        {
+            let paths = resources.get::<EntityPaths>().unwrap();
+            assert_eq!(paths.get("Player/Arm/Hand"), Some(hand));
+            assert_eq!(paths.get_relative(player, "Arm"), Some(arm));
+            assert_eq!(paths.get("Arm"), None);
+            assert_eq!(paths.path(hand).unwrap(), "Player/Arm/Hand");
+        }
+
+        world.get_mut::<Name>(arm).unwrap().set("LeftArm");
+        schedule.run(&mut world, &mut resources);
+        world.clear_trackers();
+        {
+            let paths = resources.get::<EntityPaths>().unwrap();
+            assert_eq!(paths.get("Player/LeftArm/Hand"), Some(hand));
+            assert_eq!(paths.get("Player/Arm/Hand"), None);
+        }
+
+        world.despawn(player).unwrap();
+        world.remove_one::<Parent>(arm).unwrap();
+        schedule.run(&mut world, &mut resources);
+        {
+            let paths = resources.get::<EntityPaths>().unwrap();
+            assert_eq!(paths.get("Player"), None);
+            assert_eq!(paths.get("LeftArm/Hand"), Some(hand));
+            assert_eq!(paths.path(hand).unwrap(), "LeftArm/Hand");
+        }
+    }
+}
@@ -97,7 +97,7 @@ mod tests {
     fn despawn_recursive() {
         let mut world = World::default();
         let mut resources = Resources::default();
-        let mut command_buffer = Commands::default();
+        let mut command_buffer = Commands::new(&world);
 
         command_buffer.spawn((0u32, 0u64)).with_children(|parent| {
             parent.spawn((0u32, 0u64));
@@ -123,7 +123,7 @@ mod test {
         }
 
         // Add parent entities
-        let mut commands = Commands::default();
+        let mut commands = Commands::new(&world);
         let mut parent = None;
         let mut children = Vec::new();
         commands
@@ -1,10 +1,12 @@
 mod child_builder;
+mod entity_paths;
 #[allow(clippy::module_inception)]
 mod hierarchy;
 mod hierarchy_maintenance_system;
 mod world_child_builder;
 
 pub use child_builder::*;
+pub use entity_paths::*;
 pub use hierarchy::*;
 pub use hierarchy_maintenance_system::*;
 pub use world_child_builder::*;
@@ -8,7 +8,9 @@ pub struct WorldChildBuilder<'a, 'b> {
 
 impl<'a, 'b> WorldChildBuilder<'a, 'b> {
     pub fn spawn(&mut self, components: impl DynamicBundle + Send + Sync + 'static) -> &mut Self {
-        self.spawn_as_entity(Entity::new(), components)
+        self.world_builder.spawn(components);
+        let entity = self.world_builder.current_entity.unwrap();
+        self.add_to_parent(entity)
     }
 
     pub fn spawn_as_entity(
@@ -16,18 +18,21 @@ impl<'a, 'b> WorldChildBuilder<'a, 'b> {
         entity: Entity,
         components: impl DynamicBundle + Send + Sync + 'static,
     ) -> &mut Self {
+        self.world_builder.spawn_as_entity(entity, components);
+        self.add_to_parent(entity)
+    }
+
+    fn add_to_parent(&mut self, entity: Entity) -> &mut Self {
         let parent_entity = self
             .parent_entities
             .last()
             .cloned()
             .expect("There should always be a parent at this point.");
-        self.world_builder
-            .spawn_as_entity(entity, components)
-            .with_bundle((
-                Parent(parent_entity),
-                PreviousParent(Some(parent_entity)),
-                LocalTransform::default(),
-            ));
+        self.world_builder.with_bundle((
+            Parent(parent_entity),
+            PreviousParent(Some(parent_entity)),
+            LocalTransform::default(),
+        ));
         {
             let world = &mut self.world_builder.world;
             let mut added = false;
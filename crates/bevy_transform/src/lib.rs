@@ -8,7 +8,7 @@ pub mod prelude {
     pub use crate::{components::*, hierarchy::*, TransformPlugin};
 }
 
-use bevy_app::prelude::*;
+use bevy_app::{prelude::*, startup_stage};
 use bevy_ecs::prelude::*;
 use bevy_type_registry::RegisterType;
 use prelude::{
@@ -31,8 +31,8 @@ pub struct TransformPlugin;
 
 impl Plugin for TransformPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.register_component::<Children>()
-            .register_component::<Parent>()
+        app.register_map_entities_component::<Children>()
+            .register_map_entities_component::<Parent>()
             .register_component::<LocalTransform>()
             .register_component::<Transform>()
             .register_component::<Translation>()
@@ -41,6 +41,12 @@ impl Plugin for TransformPlugin {
             .register_component::<NonUniformScale>()
             // add transform systems to startup so the first update is "correct"
             .add_startup_systems(transform_systems())
-            .add_systems_to_stage(stage::POST_UPDATE, transform_systems());
+            .add_systems_to_stage(stage::POST_UPDATE, transform_systems())
+            .init_resource::<hierarchy::EntityPaths>()
+            .add_startup_system_to_stage(
+                startup_stage::POST_STARTUP,
+                hierarchy::entity_paths_system.system(),
+            )
+            .add_system_to_stage(stage::POST_UPDATE, hierarchy::entity_paths_system.system());
     }
 }
@@ -127,7 +127,7 @@ mod test {
         }
 
         // Root entity
-        let mut commands = Commands::default();
+        let mut commands = Commands::new(&world);
         let mut children = Vec::new();
         commands
             .spawn((Translation::new(1.0, 0.0, 0.0), Transform::identity()))
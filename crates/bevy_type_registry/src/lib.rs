@@ -1,10 +1,13 @@
 mod register_type;
+mod reload;
 mod type_registry;
 
 pub use register_type::*;
+pub use reload::*;
 pub use type_registry::*;
 
 use bevy_app::prelude::*;
+use bevy_ecs::Entity;
 use bevy_property::DynamicProperties;
 
 #[derive(Default)]
@@ -13,6 +16,8 @@ pub struct TypeRegistryPlugin;
 impl Plugin for TypeRegistryPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_resource::<TypeRegistry>()
-            .register_property::<DynamicProperties>();
+            .register_property::<DynamicProperties>()
+            .register_property::<Entity>()
+            .add_reload_hook(TypeRegistryReloadHook::default());
     }
 }
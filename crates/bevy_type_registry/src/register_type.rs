@@ -1,12 +1,15 @@
 use crate::TypeRegistry;
 use bevy_app::AppBuilder;
-use bevy_ecs::{Component, FromResources};
+use bevy_ecs::{Component, FromResources, MapEntities};
 use bevy_property::{DeserializeProperty, Properties, Property};
 
 pub trait RegisterType {
     fn register_component<T>(&mut self) -> &mut Self
     where
         T: Properties + DeserializeProperty + Component + FromResources;
+    fn register_map_entities_component<T>(&mut self) -> &mut Self
+    where
+        T: Properties + DeserializeProperty + Component + FromResources + MapEntities;
     fn register_properties<T>(&mut self) -> &mut Self
     where
         T: Properties + DeserializeProperty + FromResources;
@@ -28,6 +31,18 @@ impl RegisterType for AppBuilder {
         self
     }
 
+    fn register_map_entities_component<T>(&mut self) -> &mut Self
+    where
+        T: Properties + DeserializeProperty + Component + FromResources + MapEntities,
+    {
+        {
+            let type_registry = self.app.resources.get::<TypeRegistry>().unwrap();
+            type_registry.component.write().register_map_entities::<T>();
+            type_registry.property.write().register::<T>();
+        }
+        self
+    }
+
     fn register_properties<T>(&mut self) -> &mut Self
     where
         T: Properties + DeserializeProperty + Component + FromResources,
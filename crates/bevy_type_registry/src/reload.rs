@@ -0,0 +1,193 @@
+use crate::TypeRegistry;
+use bevy_app::ReloadHook;
+use bevy_ecs::{ComponentId, Entity, Resources, World};
+use bevy_property::ron::{deserialize_dynamic_properties, serialize_dynamic_properties};
+use bevy_utils::{HashMap, HashSet};
+
+/// The types that a reloadable plugin registered
+#[derive(Default)]
+struct PluginTypes {
+    components: Vec<ComponentId>,
+    properties: Vec<String>,
+}
+
+/// A component that was removed from an entity when the plugin defining its type was unloaded
+struct SavedComponent {
+    entity: Entity,
+    type_name: String,
+    ron: String,
+}
+
+/// Keeps the components of reloadable plugins across reloads. Before a plugin is unloaded, the components of the types
+/// it registered are serialized, the types are purged from the [World] and unregistered. Once the new version
+/// of the plugin has been built, the components are deserialized and added back using the new registrations.
+///
+/// Components in sparse sets and components of types that the new version no longer registers are dropped.
+#[derive(Default)]
+pub struct TypeRegistryReloadHook {
+    components: HashSet<ComponentId>,
+    properties: HashSet<String>,
+    plugin_types: HashMap<String, PluginTypes>,
+    saved: Vec<SavedComponent>,
+}
+
+impl ReloadHook for TypeRegistryReloadHook {
+    fn before_build(&mut self, _plugin: &str, _world: &mut World, resources: &mut Resources) {
+        if let Some(type_registry) = resources.get::<TypeRegistry>() {
+            let component_registry = type_registry.component.read();
+            self.components = component_registry.registrations.keys().copied().collect();
+            let property_registry = type_registry.property.read();
+            self.properties = property_registry.type_names().map(String::from).collect();
+        }
+    }
+
+    fn after_build(&mut self, plugin: &str, world: &mut World, resources: &mut Resources) {
+        let type_registry = match resources.get::<TypeRegistry>() {
+            Some(type_registry) => type_registry,
+            None => return,
+        };
+        let component_registry = type_registry.component.read();
+        let property_registry = type_registry.property.read();
+        let plugin_types = PluginTypes {
+            components: component_registry
+                .registrations
+                .keys()
+                .filter(|id| !self.components.contains(id))
+                .copied()
+                .collect(),
+            properties: property_registry
+                .type_names()
+                .filter(|name| !self.properties.contains(*name))
+                .map(String::from)
+                .collect(),
+        };
+        self.plugin_types.insert(plugin.to_string(), plugin_types);
+
+        for saved in self.saved.drain(..) {
+            let registration = match component_registry.get_with_full_name(&saved.type_name) {
+                Some(registration) => registration,
+                None => {
+                    log::warn!(
+                        "component type is no longer registered: {}",
+                        saved.type_name
+                    );
+                    continue;
+                }
+            };
+            match deserialize_dynamic_properties(&saved.ron, &property_registry) {
+                Ok(properties) => registration.add_component_to_entity(
+                    world,
+                    resources,
+                    saved.entity,
+                    &properties,
+                ),
+                Err(err) => log::warn!("failed to restore {}: {}", saved.type_name, err),
+            }
+        }
+    }
+
+    fn before_unload(&mut self, plugin: &str, world: &mut World, resources: &mut Resources) {
+        let plugin_types = match self.plugin_types.remove(plugin) {
+            Some(plugin_types) => plugin_types,
+            None => return,
+        };
+        let type_registry = match resources.get::<TypeRegistry>() {
+            Some(type_registry) => type_registry,
+            None => return,
+        };
+        let mut component_registry = type_registry.component.write();
+        let mut property_registry = type_registry.property.write();
+        for id in plugin_types.components.iter() {
+            let registration = match component_registry.unregister(id) {
+                Some(registration) => registration,
+                None => continue,
+            };
+            for archetype in world
+                .archetypes()
+                .filter(|archetype| archetype.has_dynamic(*id))
+            {
+                for (index, entity) in archetype.iter_entities().enumerate() {
+                    let properties = registration
+                        .get_component_properties(archetype, index)
+                        .to_dynamic();
+                    match serialize_dynamic_properties(&properties, &property_registry) {
+                        Ok(ron) => self.saved.push(SavedComponent {
+                            entity: *entity,
+                            type_name: registration.long_name.clone(),
+                            ron,
+                        }),
+                        Err(err) => {
+                            log::warn!("failed to save {}: {}", registration.long_name, err)
+                        }
+                    }
+                }
+            }
+            // the archetypes storing the type keep its type info and drop function, which belong to the old plugin
+            world.purge_component(*id);
+        }
+        for type_name in plugin_types.properties.iter() {
+            property_registry.unregister(type_name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RegisterType, TypeRegistry, TypeRegistryPlugin};
+    use bevy_app::{App, AppBuilder, Plugin};
+    use bevy_property::Properties;
+
+    #[derive(Debug, Default, PartialEq, Properties)]
+    struct Health {
+        value: f32,
+    }
+
+    struct HealthPlugin;
+
+    impl Plugin for HealthPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.register_component::<Health>();
+        }
+    }
+
+    #[test]
+    fn reload_keeps_components() {
+        let mut builder = App::build();
+        builder
+            .add_plugin(TypeRegistryPlugin)
+            .add_reloadable_plugin(HealthPlugin);
+        let mut app = std::mem::take(&mut builder.app);
+        let a = app.world.spawn((Health { value: 1.5 }, 1u32));
+        let b = app.world.spawn((Health { value: 2.5 },));
+        let stale = app
+            .world
+            .archetypes()
+            .enumerate()
+            .filter(|(_, archetype)| archetype.has::<Health>())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert_eq!(stale.len(), 2);
+
+        app.reload_plugin(
+            std::any::type_name::<HealthPlugin>(),
+            Box::new(HealthPlugin),
+        );
+
+        assert_eq!(*app.world.get::<Health>(a).unwrap(), Health { value: 1.5 });
+        assert_eq!(*app.world.get::<u32>(a).unwrap(), 1);
+        assert_eq!(*app.world.get::<Health>(b).unwrap(), Health { value: 2.5 });
+        let archetypes = app.world.archetypes().collect::<Vec<_>>();
+        for index in stale {
+            assert!(
+                !archetypes[index].has::<Health>(),
+                "archetypes of the old plugin's types are purged"
+            );
+        }
+        let type_registry = app.resources.get::<TypeRegistry>().unwrap();
+        assert!(type_registry
+            .component
+            .read()
+            .get_with_name("Health")
+            .is_some());
+    }
+}
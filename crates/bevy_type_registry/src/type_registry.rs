@@ -1,8 +1,11 @@
-use bevy_ecs::{Archetype, Component, Entity, FromResources, Resources, World};
+use bevy_ecs::{
+    Archetype, Component, ComponentId, Entity, EntityMap, FromResources, MapEntities,
+    MapEntitiesError, Resources, World,
+};
 use bevy_property::{Properties, Property, PropertyTypeRegistration, PropertyTypeRegistry};
 use bevy_utils::{HashMap, HashSet};
 use parking_lot::RwLock;
-use std::{any::TypeId, sync::Arc};
+use std::sync::Arc;
 
 #[derive(Clone, Default)]
 pub struct TypeRegistry {
@@ -12,9 +15,9 @@ pub struct TypeRegistry {
 
 #[derive(Default)]
 pub struct ComponentRegistry {
-    pub registrations: HashMap<TypeId, ComponentRegistration>,
-    pub short_names: HashMap<String, TypeId>,
-    pub full_names: HashMap<String, TypeId>,
+    pub registrations: HashMap<ComponentId, ComponentRegistration>,
+    pub short_names: HashMap<String, ComponentId>,
+    pub full_names: HashMap<String, ComponentId>,
     pub ambigous_names: HashSet<String>,
 }
 
@@ -23,10 +26,28 @@ impl ComponentRegistry {
     where
         T: Properties + Component + FromResources,
     {
-        let registration = ComponentRegistration::of::<T>();
+        self.add_registration(ComponentRegistration::of::<T>());
+    }
+
+    /// Registers a component that stores [Entity] values, which are remapped when the component is
+    /// spawned as part of a scene
+    pub fn register_map_entities<T>(&mut self)
+    where
+        T: Properties + Component + FromResources + MapEntities,
+    {
+        self.add_registration(ComponentRegistration::of_map_entities::<T>());
+    }
+
+    /// Registers a component that is only known at runtime, such as one defined by a script. See
+    /// [ComponentRegistration::dynamic].
+    pub fn register_dynamic(&mut self, registration: ComponentRegistration) {
+        self.add_registration(registration);
+    }
+
+    fn add_registration(&mut self, registration: ComponentRegistration) {
         let short_name = registration.short_name.to_string();
         self.full_names
-            .insert(registration.long_name.to_string(), registration.ty);
+            .insert(registration.long_name.clone(), registration.ty);
         if self.short_names.contains_key(&short_name) || self.ambigous_names.contains(&short_name) {
             // name is ambiguous. fall back to long names for all ambiguous types
             self.short_names.remove(&short_name);
@@ -37,8 +58,18 @@ impl ComponentRegistry {
         self.registrations.insert(registration.ty, registration);
     }
 
-    pub fn get(&self, type_id: &TypeId) -> Option<&ComponentRegistration> {
-        self.registrations.get(type_id)
+    /// Removes the registration of a component type, for example because the plugin that defined it is being unloaded
+    pub fn unregister(&mut self, id: &ComponentId) -> Option<ComponentRegistration> {
+        let registration = self.registrations.remove(id)?;
+        self.full_names.remove(&registration.long_name);
+        if self.short_names.get(&registration.short_name) == Some(id) {
+            self.short_names.remove(&registration.short_name);
+        }
+        Some(registration)
+    }
+
+    pub fn get(&self, id: &ComponentId) -> Option<&ComponentRegistration> {
+        self.registrations.get(id)
     }
 
     pub fn get_with_full_name(&self, full_name: &str) -> Option<&ComponentRegistration> {
@@ -65,42 +96,89 @@ impl ComponentRegistry {
     }
 }
 
+type ComponentAddFn = dyn Fn(&mut World, &Resources, Entity, &dyn Property) + Send + Sync;
+type ComponentApplyFn = dyn Fn(&mut World, Entity, &dyn Property) + Send + Sync;
+type ComponentPropertiesFn = dyn (Fn(&Archetype, usize) -> &dyn Properties) + Send + Sync;
+type ComponentMapEntitiesFn =
+    dyn Fn(&mut World, Entity, &EntityMap) -> Result<(), MapEntitiesError> + Send + Sync;
+
 #[derive(Clone)]
 pub struct ComponentRegistration {
-    pub ty: TypeId,
-    component_add_fn: fn(&mut World, resources: &Resources, Entity, &dyn Property),
-    component_apply_fn: fn(&mut World, Entity, &dyn Property),
-    component_properties_fn: fn(&Archetype, usize) -> &dyn Properties,
+    pub ty: ComponentId,
+    component_add_fn: Arc<ComponentAddFn>,
+    component_apply_fn: Arc<ComponentApplyFn>,
+    component_properties_fn: Arc<ComponentPropertiesFn>,
+    component_map_entities_fn: Arc<ComponentMapEntitiesFn>,
     pub short_name: String,
-    pub long_name: &'static str,
+    pub long_name: String,
 }
 
 impl ComponentRegistration {
     pub fn of<T: Properties + Component + FromResources>() -> Self {
-        let ty = TypeId::of::<T>();
         Self {
-            ty,
-            component_add_fn: |world: &mut World,
-                               resources: &Resources,
-                               entity: Entity,
-                               property: &dyn Property| {
-                let mut component = T::from_resources(resources);
-                component.apply(property);
-                world.insert_one(entity, component).unwrap();
-            },
-            component_apply_fn: |world: &mut World, entity: Entity, property: &dyn Property| {
-                let mut component = world.get_mut::<T>(entity).unwrap();
-                component.apply(property);
-            },
-            component_properties_fn: |archetype: &Archetype, index: usize| {
+            ty: ComponentId::of::<T>(),
+            component_add_fn: Arc::new(
+                |world: &mut World,
+                 resources: &Resources,
+                 entity: Entity,
+                 property: &dyn Property| {
+                    let mut component = T::from_resources(resources);
+                    component.apply(property);
+                    world.insert_one(entity, component).unwrap();
+                },
+            ),
+            component_apply_fn: Arc::new(
+                |world: &mut World, entity: Entity, property: &dyn Property| {
+                    let mut component = world.get_mut::<T>(entity).unwrap();
+                    component.apply(property);
+                },
+            ),
+            component_properties_fn: Arc::new(|archetype: &Archetype, index: usize| {
                 // the type has been looked up by the caller, so this is safe
                 unsafe {
                     let ptr = archetype.get::<T>().unwrap().as_ptr().add(index);
-                    ptr.as_ref().unwrap()
+                    ptr.as_ref().unwrap() as &dyn Properties
                 }
-            },
+            }),
+            component_map_entities_fn: Arc::new(|_world, _entity, _entity_map| Ok(())),
             short_name: PropertyTypeRegistration::get_short_name(std::any::type_name::<T>()),
-            long_name: std::any::type_name::<T>(),
+            long_name: std::any::type_name::<T>().to_string(),
+        }
+    }
+
+    pub fn of_map_entities<T: Properties + Component + FromResources + MapEntities>() -> Self {
+        Self {
+            component_map_entities_fn: Arc::new(
+                |world: &mut World, entity: Entity, entity_map: &EntityMap| {
+                    let mut component = world.get_mut::<T>(entity).unwrap();
+                    component.map_entities(entity_map)
+                },
+            ),
+            ..Self::of::<T>()
+        }
+    }
+
+    /// Describes a component that is only known at runtime, such as one defined by a script, so it
+    /// can be saved to and loaded from scenes. `name` is used as both the short and long name.
+    ///
+    /// `add` inserts a component built from the scene's properties into an entity, `apply` updates
+    /// the existing component of an entity, and `properties` returns the component of the entity
+    /// at an index of an archetype that stores it.
+    pub fn dynamic(
+        ty: ComponentId,
+        name: &str,
+        add: impl Fn(&mut World, &Resources, Entity, &dyn Property) + Send + Sync + 'static,
+        apply: impl Fn(&mut World, Entity, &dyn Property) + Send + Sync + 'static,
+        properties: impl (Fn(&Archetype, usize) -> &dyn Properties) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            ty,
+            component_add_fn: Arc::new(add),
+            component_apply_fn: Arc::new(apply),
+            component_properties_fn: Arc::new(properties),
+            component_map_entities_fn: Arc::new(|_world, _entity, _entity_map| Ok(())),
+            short_name: name.to_string(),
+            long_name: name.to_string(),
         }
     }
 
@@ -123,6 +201,17 @@ impl ComponentRegistration {
         (self.component_apply_fn)(world, entity, property);
     }
 
+    /// Remaps the [Entity] values stored in this component on `entity`. Does nothing for components
+    /// that were not registered with [ComponentRegistry::register_map_entities].
+    pub fn map_component_entities(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        entity_map: &EntityMap,
+    ) -> Result<(), MapEntitiesError> {
+        (self.component_map_entities_fn)(world, entity, entity_map)
+    }
+
     pub fn get_component_properties<'a>(
         &self,
         archetype: &'a Archetype,
@@ -131,3 +220,90 @@ impl ComponentRegistration {
         (self.component_properties_fn)(archetype, entity_index)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentRegistration, ComponentRegistry};
+    use bevy_ecs::{ComponentId, Resources, World};
+    use bevy_property::DynamicProperties;
+
+    mod a {
+        use bevy_property::Properties;
+
+        #[derive(Debug, Default, PartialEq, Properties)]
+        pub struct Health {
+            pub value: f32,
+        }
+    }
+
+    mod b {
+        use bevy_property::Properties;
+
+        #[derive(Default, Properties)]
+        pub struct Health {
+            pub max: f32,
+        }
+    }
+
+    use a::Health;
+
+    #[test]
+    fn register_and_unregister() {
+        let mut registry = ComponentRegistry::default();
+        registry.register::<Health>();
+        let full_name = std::any::type_name::<Health>();
+        assert_eq!(
+            registry.get_with_name("Health").unwrap().long_name,
+            full_name
+        );
+        assert!(registry.get_with_full_name(full_name).is_some());
+
+        let registration = registry.unregister(&ComponentId::of::<Health>()).unwrap();
+        assert_eq!(registration.short_name, "Health");
+        assert!(registry.get(&ComponentId::of::<Health>()).is_none());
+        assert!(registry.get_with_name("Health").is_none());
+        assert!(registry.get_with_full_name(full_name).is_none());
+        assert!(registry.unregister(&ComponentId::of::<Health>()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Type name is ambiguous: Health")]
+    fn ambiguous_short_name() {
+        let mut registry = ComponentRegistry::default();
+        registry.register::<a::Health>();
+        registry.register::<b::Health>();
+        assert!(registry
+            .get_with_name(std::any::type_name::<b::Health>())
+            .is_some());
+        registry.get_with_name("Health");
+    }
+
+    #[test]
+    fn add_and_apply_component() {
+        let registration = ComponentRegistration::of::<Health>();
+        let mut world = World::new();
+        let resources = Resources::default();
+        let entity = world.spawn((1u32,));
+
+        let mut properties = DynamicProperties::map();
+        properties.set("value", 2.0f32);
+        registration.add_component_to_entity(&mut world, &resources, entity, &properties);
+        assert_eq!(*world.get::<Health>(entity).unwrap(), Health { value: 2.0 });
+
+        properties.set("value", 3.0f32);
+        registration.apply_component_to_entity(&mut world, entity, &properties);
+        assert_eq!(*world.get::<Health>(entity).unwrap(), Health { value: 3.0 });
+
+        let archetype = world
+            .archetypes()
+            .find(|archetype| archetype.has::<Health>())
+            .unwrap();
+        let properties = registration.get_component_properties(archetype, 0);
+        let value = properties
+            .prop("value")
+            .unwrap()
+            .any()
+            .downcast_ref::<f32>();
+        assert_eq!(value, Some(&3.0));
+    }
+}
@@ -13,9 +13,11 @@ fn animate_sprite_system(
     mut query: Query<(&mut Timer, &mut TextureAtlasSprite, &Handle<TextureAtlas>)>,
 ) {
     for (timer, mut sprite, texture_atlas_handle) in &mut query.iter() {
-        if timer.finished {
+        if timer.finished() {
             let texture_atlas = texture_atlases.get(&texture_atlas_handle).unwrap();
-            sprite.index = ((sprite.index as usize + 1) % texture_atlas.textures.len()) as u32;
+            // a long frame can skip frames of the animation
+            let frames = timer.times_finished() as usize;
+            sprite.index = ((sprite.index as usize + frames) % texture_atlas.textures.len()) as u32;
         }
     }
 }
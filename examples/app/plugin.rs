@@ -40,8 +40,8 @@ struct PrintMessageState {
 }
 
 fn print_message_system(mut state: ResMut<PrintMessageState>, time: Res<Time>) {
-    state.timer.tick(time.delta_seconds);
-    if state.timer.finished {
+    state.timer.tick(time.delta);
+    if state.timer.finished() {
         println!("{}", state.message);
     }
 }
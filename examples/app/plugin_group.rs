@@ -0,0 +1,50 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+/// PluginGroups are a way to group sets of plugins that should be registered together.
+fn main() {
+    App::build()
+        // the default plugins are a plugin group
+        .add_default_plugins()
+        // adding a plugin group adds all of its plugins in order
+        .add_plugins(HelloWorldPlugins)
+        // plugins in a group can also be disabled, replaced or surrounded by other plugins before the group is added.
+        // a headless server could, for example, disable the WindowPlugin, WinitPlugin and WgpuPlugin of DefaultPlugins.
+        // .add_plugins_with(HelloWorldPlugins, |group| {
+        //     group
+        //         .disable::<PrintWorldPlugin>()
+        //         .add_before::<PrintHelloPlugin, _>(bevy::diagnostic::PrintDiagnosticsPlugin::default())
+        // })
+        .run();
+}
+
+pub struct HelloWorldPlugins;
+
+impl PluginGroup for HelloWorldPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group.add(PrintHelloPlugin).add(PrintWorldPlugin);
+    }
+}
+
+pub struct PrintHelloPlugin;
+
+impl Plugin for PrintHelloPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(print_hello_system.system());
+    }
+}
+
+fn print_hello_system() {
+    println!("hello");
+}
+
+pub struct PrintWorldPlugin;
+
+impl Plugin for PrintWorldPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(print_world_system.system());
+    }
+}
+
+fn print_world_system() {
+    println!("world");
+}
@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+
+const FIXED_UPDATE: &str = "fixed_update";
+
+fn main() {
+    App::build()
+        .add_default_plugins()
+        // this stage runs 60 times per second, regardless of the frame rate
+        .add_stage_after(stage::UPDATE, FIXED_UPDATE)
+        .set_stage_run_criteria(FIXED_UPDATE, FixedTimestep::steps_per_second(60.0))
+        .add_system_to_stage(FIXED_UPDATE, fixed_update.system())
+        .run();
+}
+
+fn fixed_update(mut last_time: Local<f64>, time: Res<Time>) {
+    // this is only printed on fixed ticks, so the printed interval stays close to 1/60th of a second on average
+    println!("fixed update: {}", time.seconds_since_startup - *last_time);
+    *last_time = time.seconds_since_startup;
+}
@@ -0,0 +1,46 @@
+use bevy::{prelude::*, tasks::ComputeTaskPool};
+
+struct Velocity(Vec2);
+struct Position(Vec2);
+
+fn main() {
+    App::build()
+        .add_default_plugins()
+        .add_startup_system(spawn_particles.system())
+        .add_system(move_particles.system())
+        .add_system(count_escaped.system())
+        .run();
+}
+
+fn spawn_particles(mut commands: Commands) {
+    for i in 0..100_000 {
+        let angle = i as f32 * 0.001;
+        commands.spawn((
+            Position(Vec2::zero()),
+            Velocity(Vec2::new(angle.cos(), angle.sin()) * (1.0 + (i % 100) as f32)),
+        ));
+    }
+}
+
+// the query is split into batches of 1024 entities, which are moved in parallel on the compute task pool
+fn move_particles(
+    pool: Res<ComputeTaskPool>,
+    time: Res<Time>,
+    mut particles: Query<(&Velocity, &mut Position)>,
+) {
+    let delta = time.delta_seconds;
+    particles
+        .par_iter(1024)
+        .for_each(&pool, |(velocity, mut position)| {
+            position.0 += velocity.0 * delta;
+        });
+}
+
+fn count_escaped(mut particles: Query<&Position>) {
+    let escaped = particles
+        .iter()
+        .iter()
+        .filter(|position| position.0.length() > 1000.0)
+        .count();
+    println!("{} particles escaped", escaped);
+}
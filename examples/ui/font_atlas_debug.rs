@@ -60,8 +60,8 @@ fn atlas_render_system(
 
 fn text_update_system(mut state: ResMut<State>, time: Res<Time>, mut query: Query<&mut Text>) {
     for mut text in &mut query.iter() {
-        state.timer.tick(time.delta_seconds);
-        if state.timer.finished {
+        state.timer.tick(time.delta);
+        if state.timer.finished() {
             text.value = format!("{}", rand::random::<u8>() as char);
             state.timer.reset();
         }